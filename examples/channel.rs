@@ -0,0 +1,39 @@
+use ndarray::{Array, Array2};
+use qua_ten_net::channel::*;
+
+fn main() {
+    // Amplitude damping channel compared with the identity channel
+    let gamma: f64 = 0.1;
+    let kraus = vec![
+        Array::from_shape_vec((2, 2), vec![1.0, 0.0, 0.0, (1.0 - gamma).sqrt()])
+            .expect("ShapeError!"),
+        Array::from_shape_vec((2, 2), vec![0.0, gamma.sqrt(), 0.0, 0.0]).expect("ShapeError!"),
+    ];
+
+    let (damping, identity) = match (
+        choi_from_kraus(&kraus),
+        choi_from_kraus(&[Array2::<f64>::eye(2)]),
+    ) {
+        (Ok(damping), Ok(identity)) => (damping, identity),
+        _ => {
+            println!("\nError on building Choi matrices");
+            return;
+        }
+    };
+    println!("\nChoi matrix of amplitude damping: \n{:?}", damping);
+
+    match choi_rank(&damping, 1e-10) {
+        Ok(rank) => println!("\nChoi rank: {}", rank),
+        Err(err) => println!("\nError on Choi rank: \n{}", err),
+    }
+
+    match process_fidelity(&damping, &identity, 2) {
+        Ok(f) => println!("\nProcess fidelity to the identity: {}", f),
+        Err(err) => println!("\nError on process fidelity: \n{}", err),
+    }
+
+    match diamond_norm_bounds(&damping, &identity, 2) {
+        Ok((lower, upper)) => println!("\nDiamond distance bounds: [{}, {}]", lower, upper),
+        Err(err) => println!("\nError on diamond norm: \n{}", err),
+    }
+}
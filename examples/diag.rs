@@ -0,0 +1,41 @@
+use ndarray::{s, Array2};
+use qua_ten_net::diag::DiagTensor;
+use qua_ten_net::tencon::contract;
+use qua_ten_net::tensor::svd;
+
+fn main() {
+    // Two-site state of two qubits, written as a 2x2 matrix
+    let state = Array2::from_shape_vec((2, 2), vec![0.8, 0.1, 0.1, 0.58]).expect("ShapeError!");
+
+    let result = match svd(state) {
+        Ok(result) => result,
+        Err(err) => {
+            eprintln!("\nError on SVD: \n{}", err);
+            return;
+        }
+    };
+
+    // Γ–Λ form: U Λ V^T with the bond weights kept as a diagonal tensor
+    let lambda: DiagTensor = result.sigma_diag();
+    println!("\nBond weights: \n{:?}", lambda.weights());
+
+    // Split the weights symmetrically between the two sites
+    let half = lambda.sqrt();
+    let split = half
+        .apply(
+            &result.u.slice(s![.., ..lambda.dim()]).to_owned().into_dyn(),
+            1,
+        )
+        .and_then(|u| {
+            let vt = half.apply(&result.vt.clone().into_dyn(), 0)?;
+            Ok((u, vt))
+        });
+
+    match split.and_then(|(u, vt)| contract(&[u, vt], &[&[-1, 1], &[1, -2]])) {
+        Ok(rebuilt) => println!("\nReconstructed state: \n{:?}", rebuilt),
+        Err(err) => eprintln!("\nError on reconstruction: \n{}", err),
+    }
+
+    // Pseudo-inverse, as used to return from the Γ–Λ form to a canonical MPS
+    println!("\nInverse weights: \n{:?}", lambda.inverse(1e-12).weights());
+}
@@ -0,0 +1,31 @@
+use qua_ten_net::einsum::{contract_labeled, einsum};
+use qua_ten_net::tensor::random;
+
+fn main() {
+    let tensor_a = random(&[2, 2, 3, 2]);
+    let tensor_b = random(&[3, 2, 4]);
+    let tensor_c = random(&[3, 4]);
+
+    // Same network as the `tencon` example, written with einsum subscripts
+    match einsum(
+        "bacd,cbe,fe->adf",
+        &[tensor_a.clone(), tensor_b.clone(), tensor_c.clone()],
+    ) {
+        Ok(result) => println!("\nContraction result: \n{:?}", result),
+        Err(err) => eprintln!("\nError during contraction: \n{}", err),
+    }
+
+    // ... and with named legs
+    match contract_labeled(
+        &[tensor_a, tensor_b, tensor_c],
+        &[
+            &["up", "left", "bond", "right"],
+            &["bond", "up", "link"],
+            &["down", "link"],
+        ],
+        &["left", "right", "down"],
+    ) {
+        Ok(result) => println!("\nLabeled contraction result: \n{:?}", result),
+        Err(err) => eprintln!("\nError during contraction: \n{}", err),
+    }
+}
@@ -0,0 +1,22 @@
+use qua_ten_net::index::{contract_indexed, Index, Tensor};
+
+fn main() {
+    // Three-site chain with physical legs s1..s3 and bonds b1, b2
+    let s: Vec<Index> = (1..=3)
+        .map(|n| Index::with_tags(2, &["Site", &format!("n={}", n)]))
+        .collect();
+    let b1 = Index::with_tags(3, &["Link"]);
+    let b2 = Index::with_tags(3, &["Link"]);
+
+    let a = Tensor::random(&[s[0].clone(), b1.clone()]);
+    let b = Tensor::random(&[b1, s[1].clone(), b2.clone()]);
+    let c = Tensor::random(&[b2, s[2].clone()]);
+
+    match contract_indexed(&[a, b, c]) {
+        Ok(psi) => {
+            println!("\nFree indices: \n{:?}", psi.indices());
+            println!("\nWavefunction: \n{:?}", psi.data());
+        }
+        Err(err) => eprintln!("\nError during contraction: \n{}", err),
+    }
+}
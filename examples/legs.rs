@@ -0,0 +1,32 @@
+use ndarray::Array2;
+use qua_ten_net::legs::{fuse_legs, split_leg, transpose_to};
+use qua_ten_net::tensor::{random, svd};
+
+fn main() {
+    // Two-site tensor with legs (left, s1, s2, right), split into two sites by an SVD
+    let theta = random(&[3, 2, 2, 3]);
+
+    let step = fuse_legs(&theta, &[vec![0, 1], vec![2, 3]]).and_then(|(matrix, fused)| {
+        let matrix: Array2<f64> = matrix.into_dimensionality()?;
+        let result = svd(matrix)?;
+        let left = split_leg(&result.u.into_dyn(), 0, &fused.leg_dims(0)?)?;
+        let right = split_leg(&result.vt.into_dyn(), 1, &fused.leg_dims(1)?)?;
+        Ok((left, right))
+    });
+    match step {
+        Ok((left, right)) => {
+            println!("\nLeft site tensor with shape {:?}", left.shape());
+            println!("\nRight site tensor with shape {:?}", right.shape());
+        }
+        Err(err) => {
+            println!("\nError on splitting the two-site tensor: \n{}", err);
+        }
+    }
+
+    // The same tensor with its legs addressed by name
+    let legs = ["left", "s1", "s2", "right"];
+    match transpose_to(theta.view(), &legs, &["s1", "s2", "left", "right"]) {
+        Ok(view) => println!("\nPhysical legs first: shape {:?}", view.shape()),
+        Err(err) => println!("\nError on transposing: \n{}", err),
+    }
+}
@@ -0,0 +1,80 @@
+use ndarray::{Array1, Array2, ArrayD};
+use qua_ten_net::linop::*;
+
+fn main() {
+    // Shifted 1D Laplacian, applied without storing the matrix
+    let n = 20;
+    let op = FnOperator::new(n, |v: &Array1<f64>| {
+        Array1::from_shape_fn(n, |i| {
+            let left = if i > 0 { v[i - 1] } else { 0.0 };
+            let right = if i + 1 < n { v[i + 1] } else { 0.0 };
+            2.5 * v[i] - left - right
+        })
+    });
+    let b = Array1::ones(n);
+
+    match gmres(&op, &b, None, 1e-10, 200, 10) {
+        Ok(x) => println!("\nGMRES solution: \n{:?}", x),
+        Err(err) => println!("\nError on GMRES: \n{}", err),
+    }
+
+    match bicgstab(&op, &b, None, 1e-10, 200) {
+        Ok(x) => println!("\nBiCGSTAB solution: \n{:?}", x),
+        Err(err) => println!("\nError on BiCGSTAB: \n{}", err),
+    }
+
+    // Dense matrices are operators too
+    let a = Array2::from_shape_fn((3, 3), |(i, j)| if i == j { 2.0 } else { 0.5 });
+    match gmres(&a, &Array1::ones(3), None, 1e-12, 10, 3) {
+        Ok(x) => println!("\nDense solution: \n{:?}", x),
+        Err(err) => println!("\nError on GMRES: \n{}", err),
+    }
+
+    // Three lowest levels of a particle on an open chain of 12 sites, as a rank-1 tensor
+    let chain = |v: &ArrayD<f64>| {
+        let n = v.len();
+        ArrayD::from_shape_fn(v.raw_dim(), |i| {
+            let left = if i[0] > 0 { v[[i[0] - 1]] } else { 0.0 };
+            let right = if i[0] + 1 < n { v[[i[0] + 1]] } else { 0.0 };
+            -left - right
+        })
+    };
+    let guess = ArrayD::from_shape_fn(vec![12], |i| 1.0 + i[0] as f64);
+    match lanczos(chain, &guess, 3, 1e-10, 12) {
+        Ok((values, _)) => println!("\nLanczos eigenvalues: \n{:?}", values),
+        Err(err) => println!("\nError on Lanczos: \n{}", err),
+    }
+
+    // The periodic ring has doubly degenerate levels, which a block method resolves
+    let ring = |v: &ArrayD<f64>| {
+        let n = v.len();
+        ArrayD::from_shape_fn(v.raw_dim(), |i| {
+            -v[[(i[0] + 1) % n]] - v[[(i[0] + n - 1) % n]]
+        })
+    };
+    let block: Vec<ArrayD<f64>> = (0..3)
+        .map(|j| ArrayD::from_shape_fn(vec![12], |i| ((i[0] * (j + 2) + j) % 7) as f64))
+        .collect();
+    match lobpcg(ring, &block, None, 1e-10, 300) {
+        Ok((values, _)) => println!("\nLOBPCG eigenvalues: \n{:?}", values),
+        Err(err) => println!("\nError on LOBPCG: \n{}", err),
+    }
+
+    // Dominant eigenvalue of a non-symmetric transfer matrix
+    let transfer =
+        Array2::from_shape_vec((3, 3), vec![0.5, 0.2, 0.0, 0.1, 0.6, 0.3, 0.4, 0.0, 0.7])
+            .expect("ShapeError!");
+    let matvec = |v: &ArrayD<f64>| {
+        transfer
+            .dot(&v.view().into_shape_with_order(3).expect("ShapeError!"))
+            .into_dyn()
+    };
+    let guess = ArrayD::from_elem(vec![3], 1.0);
+    match arnoldi(matvec, &guess, 1, Which::LargestMagnitude, 1e-10, 3) {
+        Ok((values, vectors)) => {
+            println!("\nArnoldi dominant eigenvalue: {}", values[0]);
+            println!("\nArnoldi dominant eigenvector: \n{:?}", vectors[0]);
+        }
+        Err(err) => println!("\nError on Arnoldi: \n{}", err),
+    }
+}
@@ -0,0 +1,82 @@
+use qua_ten_net::network::{find_isomorphism, signature, Layer, TensorNetwork};
+use qua_ten_net::tencon::contract_info;
+use qua_ten_net::tensor::random;
+use std::collections::HashMap;
+
+fn main() {
+    // Two layers of a brick-wall circuit on four qubits: gates act on (0, 1), (2, 3) and (1, 2)
+    let gate = vec![2, 2, 2, 2];
+    let shapes = vec![gate.clone(); 3];
+    let first: [&[i32]; 3] = [&[-1, -2, -7, 1], &[-3, -4, 2, -8], &[1, 2, -5, -6]];
+    let second: [&[i32]; 3] = [&[-3, -4, 9, -8], &[7, 9, -5, -6], &[-1, -2, -7, 7]];
+
+    // Layers with equal signatures can share one contraction result
+    let mut results: HashMap<_, &str> = HashMap::new();
+    for (name, order) in [("first", &first), ("second", &second)] {
+        match signature(&shapes, order) {
+            Ok(sig) => match results.get(&sig) {
+                Some(other) => println!("\nThe {} layer reuses the result of the {}.", name, other),
+                None => {
+                    println!("\nThe {} layer is contracted.", name);
+                    results.insert(sig, name);
+                }
+            },
+            Err(err) => eprintln!("\nError on network signature: \n{}", err),
+        }
+    }
+
+    match find_isomorphism(&shapes, &first, &shapes, &second) {
+        Ok(Some(map)) => println!(
+            "\nTensor k of the first layer is tensor map[k] of the second: \n{:?}",
+            map
+        ),
+        Ok(None) => println!("\nThe layers differ."),
+        Err(err) => eprintln!("\nError on network isomorphism: \n{}", err),
+    }
+
+    // Ten stacked layers: the outputs -5, -6, -7, -8 of each feed the inputs of the next
+    let stacked = Layer::new(&shapes, &first, &[-1, -2, -3, -4], &[-7, -5, -6, -8])
+        .and_then(|layer| layer.repeat(10));
+    match stacked {
+        Ok(circuit) => match contract_info(&circuit.shapes, &circuit.order()) {
+            Ok(info) => println!(
+                "\nTen layers: {} tensors, output shape {:?}, {} FLOPs",
+                circuit.shapes.len(),
+                info.output_shape,
+                info.flops
+            ),
+            Err(err) => eprintln!("\nError on contraction info: \n{}", err),
+        },
+        Err(err) => eprintln!("\nError on layer stacking: \n{}", err),
+    }
+
+    // Coarse-graining step: merge two sites of a chain and split the result with a truncated SVD
+    let mut chain = TensorNetwork::new();
+    let built = chain
+        .add(random(&[2, 4]), &[-1, 1])
+        .and_then(|_| chain.add(random(&[4, 2, 4]), &[1, -2, 2]))
+        .and_then(|_| chain.add(random(&[4, 2]), &[2, -3]));
+    let step = built
+        .and_then(|_| chain.contract_region(&[0, 1]))
+        .and_then(|merged| chain.split(merged, &[0], Some(2)));
+    match step {
+        Ok((node, bond)) => println!(
+            "\nNew node {} on bond {}, network order: \n{:?}",
+            node,
+            bond,
+            chain.order()
+        ),
+        Err(err) => eprintln!("\nError on network surgery: \n{}", err),
+    }
+
+    // The same split with the bond chosen to discard at most 1e-3 of the weight
+    match chain.split_to_error(1, &[0], 1e-3, Some(8)) {
+        Ok((node, _, weight)) => println!(
+            "\nNew node {} on bond of dimension {}, discarded weight {:e}",
+            node,
+            chain.tensors()[node].shape()[0],
+            weight
+        ),
+        Err(err) => eprintln!("\nError on adaptive split: \n{}", err),
+    }
+}
@@ -0,0 +1,34 @@
+use ndarray::{arr2, ArrayD};
+use qua_ten_net::param::{contract_param, ParamTensor};
+use qua_ten_net::tensor::random;
+
+fn main() {
+    // Single-qubit Hamiltonian H = h X + g Z sandwiched between two random states
+    let x = arr2(&[[0.0, 1.0], [1.0, 0.0]]).into_dyn();
+    let z = arr2(&[[1.0, 0.0], [0.0, -1.0]]).into_dyn();
+    let hamiltonian = ParamTensor::new(ArrayD::zeros(vec![2, 2]))
+        .with_term("h", x)
+        .and_then(|h| h.with_term("g", z));
+
+    let network = match hamiltonian {
+        Ok(h) => [
+            ParamTensor::new(random(&[2])),
+            h,
+            ParamTensor::new(random(&[2])),
+        ],
+        Err(err) => return eprintln!("\nError on building the Hamiltonian: \n{}", err),
+    };
+
+    let energy = match contract_param(&network, &[&[1], &[1, 2], &[2]]) {
+        Ok(energy) => energy,
+        Err(err) => return eprintln!("\nError during contraction: \n{}", err),
+    };
+
+    // Sweep the field without contracting the network again
+    for h in [0.0, 0.5, 1.0] {
+        match energy.evaluate(&[("h", h), ("g", 1.0)]) {
+            Ok(value) => println!("\nh = {}: <a|H|b> = {:?}", h, value.sum()),
+            Err(err) => eprintln!("\nError on evaluation: \n{}", err),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use qua_ten_net::path::{
+    calibrate, optimize_path, path_cost, reconfigure_path, to_opt_einsum, CostModel, Optimizer,
+};
+
+fn main() {
+    // Matrix chain (2x100)(100x3)(3x50)
+    let shapes = vec![vec![2, 100], vec![100, 3], vec![3, 50]];
+    let order: [&[i32]; 3] = [&[-1, 1], &[1, 2], &[2, -2]];
+    let left = [vec![0, 1], vec![0, 1]];
+    let right = [vec![1, 2], vec![0, 1]];
+
+    let flops = CostModel::default();
+    let measured = calibrate::<f64>(5);
+    println!("\nCalibrated cost model: \n{:?}", measured);
+
+    for (name, path) in [("left-to-right", &left), ("right-to-left", &right)] {
+        match (
+            path_cost(&shapes, &order, path, &flops),
+            path_cost(&shapes, &order, path, &measured),
+        ) {
+            (Ok(f), Ok(m)) => println!("\n{}: {} FLOPs, {:.0} calibrated", name, f, m),
+            (Err(err), _) | (_, Err(err)) => eprintln!("\nError on path cost: \n{}", err),
+        }
+    }
+
+    // Let the random-greedy planner search for a cheap path under the calibrated model
+    let optimizer = Optimizer::RandomGreedy {
+        trials: 32,
+        temperature: 1.0,
+        seed: 0,
+    };
+    match optimize_path(&shapes, &order, &optimizer, &measured) {
+        Ok(path) => println!("\nRandom-greedy path: \n{:?}", path),
+        Err(err) => eprintln!("\nError on path search: \n{}", err),
+    }
+
+    let optimizer = Optimizer::BranchAndBound { max_nodes: 10_000 };
+    match optimize_path(&shapes, &order, &optimizer, &measured) {
+        Ok(path) => match to_opt_einsum(&path, shapes.len()) {
+            Ok(exported) => println!(
+                "\nBranch-and-bound path (opt_einsum format): \n{}",
+                exported
+            ),
+            Err(err) => eprintln!("\nError on path export: \n{}", err),
+        },
+        Err(err) => eprintln!("\nError on path search: \n{}", err),
+    }
+
+    // Repair the expensive right-to-left path by reconfiguring its subtrees
+    match reconfigure_path(&shapes, &order, &right, 3, 4, &flops) {
+        Ok(path) => println!("\nReconfigured right-to-left path: \n{:?}", path),
+        Err(err) => eprintln!("\nError on path reconfiguration: \n{}", err),
+    }
+}
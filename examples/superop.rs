@@ -0,0 +1,32 @@
+use ndarray::Array;
+use ndarray_linalg::c64;
+use qua_ten_net::superop::*;
+
+fn main() {
+    // Qubit with Hamiltonian sigma_z / 2 and amplitude damping at rate gamma
+    let gamma: f64 = 0.2;
+    let zero = c64::new(0.0, 0.0);
+    let hamiltonian = Array::from_shape_vec(
+        (2, 2),
+        vec![c64::new(0.5, 0.0), zero, zero, c64::new(-0.5, 0.0)],
+    )
+    .expect("ShapeError!");
+    let lowering =
+        Array::from_shape_vec((2, 2), vec![zero, c64::new(gamma.sqrt(), 0.0), zero, zero])
+            .expect("ShapeError!");
+
+    match liouvillian(&hamiltonian, &[lowering]) {
+        Ok(l) => {
+            println!("\nLiouvillian: \n{:?}", l);
+            match liouvillian_spectrum(&l) {
+                Ok(spectrum) => println!("\nLiouvillian spectrum: \n{:?}", spectrum),
+                Err(err) => println!("\nError on spectrum: \n{}", err),
+            }
+            match steady_state(&l, 1e-10) {
+                Ok(rho) => println!("\nSteady state: \n{:?}", rho),
+                Err(err) => println!("\nError on steady state: \n{}", err),
+            }
+        }
+        Err(err) => println!("\nError on Liouvillian: \n{}", err),
+    }
+}
@@ -1,4 +1,6 @@
-use qua_ten_net::tencon::contract;
+use qua_ten_net::tencon::{
+    compile, contract, contract_info, contract_profiled, plan, ContractOptions,
+};
 use qua_ten_net::tensor::random;
 
 fn main() {
@@ -13,4 +15,41 @@ fn main() {
         Ok(result) => println!("\nContraction result: \n{:?}", result),
         Err(err) => eprintln!("\nError during contraction: \n{}", err),
     }
+
+    // Plan once and contract many networks with the same dimensions
+    let shapes = vec![vec![2, 2, 3, 2], vec![3, 2, 4], vec![3, 4]];
+    let order: [&[i32]; 3] = [&[1, -1, 2, -2], &[2, 1, 3], &[-3, 3]];
+    match contract_info(&shapes, &order) {
+        Ok(info) => println!("\nContraction estimate: \n{:?}", info),
+        Err(err) => eprintln!("\nError during estimation: \n{}", err),
+    }
+    let plan = match plan(&shapes, &order) {
+        Ok(plan) => plan,
+        Err(err) => return eprintln!("\nError during planning: \n{}", err),
+    };
+    println!("\nPlanned path: \n{:?}", plan.path());
+
+    for _ in 0..2 {
+        let tensors: Vec<_> = shapes.iter().map(|s| random(s)).collect();
+        match plan.execute(&tensors) {
+            Ok(result) => println!("\nPlanned contraction result: \n{:?}", result.shape()),
+            Err(err) => eprintln!("\nError during contraction: \n{}", err),
+        }
+    }
+
+    // Slice the contraction so that no intermediate exceeds 16 elements
+    let options = ContractOptions::default()
+        .memory_limit(16)
+        .parallel_slices(true);
+    match compile(&shapes, &order, &options) {
+        Ok(plan) => println!("\nSliced contraction estimate: \n{:?}", plan.info()),
+        Err(err) => eprintln!("\nError during planning: \n{}", err),
+    }
+
+    // Profile the pairwise steps of a contraction
+    let tensors: Vec<_> = shapes.iter().map(|s| random(s)).collect();
+    match contract_profiled(&tensors, &order, &ContractOptions::default()) {
+        Ok((_, report)) => println!("\nContraction profile: \n{}", report),
+        Err(err) => eprintln!("\nError during contraction: \n{}", err),
+    }
 }
@@ -1,4 +1,4 @@
-use qua_ten_net::tendot::tensor_dot;
+use qua_ten_net::tendot::{outer, tensor_dot, Axes};
 use qua_ten_net::tensor::random;
 
 fn main() {
@@ -18,4 +18,16 @@ fn main() {
             println!("\nError on tensor dot product: \n{}", err);
         }
     }
+
+    // The same product, pairing the last axis of A with the first axis of B as numpy does
+    match tensor_dot(&tensor_a, &tensor_b, Axes::Last(1)) {
+        Ok(result) => println!("\nDot product with Axes::Last(1): \n{:?}", result),
+        Err(err) => println!("\nError on tensor dot product: \n{}", err),
+    }
+
+    let product = outer(&tensor_a, &tensor_b);
+    println!(
+        "\nOuter product of A and B with shape {:?}",
+        product.shape()
+    );
 }
@@ -37,4 +37,57 @@ fn main() {
             println!("\nError on SVD: \n{}", err);
         }
     }
+
+    // The 3x3 sample has rank 2, so a relative cutoff drops the third singular value
+    match svd_truncated(sample, None, 1e-10, false) {
+        Ok((svd, discarded)) => {
+            println!("\nTruncated singular values: \n{:?}", svd.sigma);
+            println!("\nDiscarded weight: {}", discarded);
+        }
+        Err(err) => {
+            println!("\nError on truncated SVD: \n{}", err);
+        }
+    }
+
+    // Right-canonical form of a 2x4 matrix: Q has orthonormal rows
+    let wide =
+        Array2::from_shape_vec((2, 4), (0..8).map(|x| x as f64).collect()).expect("ShapeError!");
+    match lq(wide) {
+        Ok((l, q)) => {
+            println!("\nL factor of the LQ decomposition: \n{:?}", l);
+            println!("\nQ factor of the LQ decomposition: \n{:?}", q);
+        }
+        Err(err) => {
+            println!("\nError on LQ: \n{}", err);
+        }
+    }
+
+    // Spectrum of a 3-site tight-binding Hamiltonian, in ascending order
+    let hamiltonian = Array2::from_shape_vec(
+        (3, 3),
+        vec![0.0, -1.0, 0.0, -1.0, 0.0, -1.0, 0.0, -1.0, 0.0],
+    )
+    .expect("ShapeError!");
+    match eigh(&hamiltonian) {
+        Ok((w, v)) => {
+            println!("\nEigenvalues: \n{:?}", w);
+            println!("\nEigenvectors: \n{:?}", v);
+        }
+        Err(err) => {
+            println!("\nError on eigh: \n{}", err);
+        }
+    }
+
+    // Non-symmetric matrix with a complex pair of eigenvalues, dominant first
+    let nonsymmetric =
+        Array2::from_shape_vec((2, 2), vec![0.0, -1.0, 1.0, 0.5]).expect("ShapeError!");
+    match eig(&nonsymmetric) {
+        Ok((w, v)) => {
+            println!("\nComplex eigenvalues: \n{:?}", w);
+            println!("\nComplex eigenvectors: \n{:?}", v);
+        }
+        Err(err) => {
+            println!("\nError on eig: \n{}", err);
+        }
+    }
 }
@@ -0,0 +1,28 @@
+use ndarray::Array;
+use qua_ten_net::transfer::*;
+
+fn main() {
+    // Classical Ising chain at inverse temperature beta
+    let beta: f64 = 0.5;
+    let (p, m) = (beta.exp(), (-beta).exp());
+    let t = Array::from_shape_vec((2, 2), vec![p, m, m, p]).expect("ShapeError!");
+
+    match leading_eigenvalue(&t, 1e-12, 1000) {
+        Ok((lambda, v)) => {
+            println!("\nTransfer matrix: \n{:?}", t);
+            println!("\nLeading eigenvalue: {}", lambda);
+            println!("\nLeading eigenvector: \n{:?}", v);
+        }
+        Err(err) => println!("\nError on power iteration: \n{}", err),
+    }
+
+    match free_energy_density(&t, beta, 1) {
+        Ok(f) => println!("\nFree-energy density (infinite chain): {}", f),
+        Err(err) => println!("\nError on free energy: \n{}", err),
+    }
+
+    match finite_free_energy_density(&t, 10, beta, 1, true) {
+        Ok(f) => println!("\nFree-energy density (periodic chain of 10 sites): {}", f),
+        Err(err) => println!("\nError on free energy: \n{}", err),
+    }
+}
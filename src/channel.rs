@@ -0,0 +1,533 @@
+use crate::error::QtnError;
+use crate::scalar::Scalar;
+use crate::superop::vectorize;
+use crate::tensor::dagger;
+use ndarray::{Array, Array1, Array2, ArrayD, Dimension, Ix1, Ix2, IxDyn};
+use ndarray_linalg::{Eigh, Scalar as _, UPLO};
+use rand::Rng;
+
+/// A single outcome of a quantum instrument (generalized measurement).
+///
+/// `state` holds the normalized post-measurement state, or `None` if the outcome has zero
+/// probability.
+pub struct MeasurementOutcome<A: Scalar, D: Dimension> {
+    pub probability: A::Real,
+    pub state: Option<Array<A, D>>,
+}
+
+/// Builds the Choi matrix of a channel given by its Kraus operators.
+///
+/// The (unnormalized) Choi matrix is `J = sum_k vec(K_k) vec(K_k)^†` with column stacking, so its
+/// row index is `out + in * d_out` and `Tr J = d_in` for a trace-preserving channel.
+///
+/// # Arguments
+/// - `kraus`: A slice of Kraus operators of shape `(d_out, d_in)`.
+///
+/// # Returns
+/// A `Result<Array2, QtnError>` containing the Choi matrix, or an error if the list is
+/// empty or the Kraus operators have different shapes.
+pub fn choi_from_kraus<A: Scalar>(kraus: &[Array2<A>]) -> Result<Array2<A>, QtnError> {
+    let shape = kraus
+        .first()
+        .ok_or_else(|| {
+            QtnError::InvalidArgument("Channel needs at least one Kraus operator.".to_string())
+        })?
+        .dim();
+
+    let n = shape.0 * shape.1;
+    let mut choi = Array2::zeros((n, n));
+    for (k, op) in kraus.iter().enumerate() {
+        if op.dim() != shape {
+            return Err(QtnError::InvalidArgument(format!(
+                "Kraus operator {} has shape {:?}, expected {:?}.",
+                k,
+                op.dim(),
+                shape
+            )));
+        }
+        let v = vectorize(op);
+        choi = choi + Array2::from_shape_fn((n, n), |(i, j)| v[i] * v[j].conj());
+    }
+
+    Ok(choi)
+}
+
+/// Converts a superoperator (see [`crate::superop`]) into the Choi matrix of the same channel.
+///
+/// # Arguments
+/// - `superop`: The superoperator acting on column-stacked operators, of shape
+///   `(d_out * d_out, d_in * d_in)`.
+/// - `d_in`: The input dimension of the channel.
+/// - `d_out`: The output dimension of the channel.
+///
+/// # Returns
+/// A `Result<Array2, QtnError>` containing the Choi matrix, or an error if the shape of
+/// `superop` does not match the given dimensions.
+pub fn choi_from_superoperator<A: Scalar>(
+    superop: &Array2<A>,
+    d_in: usize,
+    d_out: usize,
+) -> Result<Array2<A>, QtnError> {
+    if superop.dim() != (d_out * d_out, d_in * d_in) {
+        return Err(QtnError::InvalidArgument(format!(
+            "Superoperator has shape {:?}, expected ({}, {}).",
+            superop.dim(),
+            d_out * d_out,
+            d_in * d_in
+        )));
+    }
+
+    let n = d_in * d_out;
+    Ok(Array2::from_shape_fn((n, n), |(r, c)| {
+        let (i, j) = (r % d_out, r / d_out);
+        let (ip, jp) = (c % d_out, c / d_out);
+        superop[[i + ip * d_out, j + jp * d_in]]
+    }))
+}
+
+/// Computes the Choi rank of a channel, i.e. the minimal number of Kraus operators.
+///
+/// # Arguments
+/// - `choi`: The Hermitian Choi matrix of the channel.
+/// - `tol`: Relative tolerance; eigenvalues below `tol` times the largest eigenvalue are treated
+///   as zero.
+///
+/// # Returns
+/// A `Result<usize, QtnError>` containing the number of non-zero eigenvalues of `choi`, or an error
+/// message if the eigenvalue decomposition fails.
+pub fn choi_rank<A: Scalar>(choi: &Array2<A>, tol: A::Real) -> Result<usize, QtnError> {
+    let (eigvals, _) = choi
+        .eigh(UPLO::Lower)
+        .map_err(|err| QtnError::LinalgFailure(format!("Eigenvalue error: {:?}", err)))?;
+
+    let max = eigvals
+        .iter()
+        .fold(A::real(0.0), |a, &b| if b > a { b } else { a });
+
+    Ok(eigvals.iter().filter(|&&x| x > tol * max).count())
+}
+
+/// Computes the process (entanglement) fidelity between two channels.
+///
+/// The process fidelity is the Uhlmann fidelity `(Tr sqrt(sqrt(rho) sigma sqrt(rho)))^2` of the
+/// normalized Choi states `rho = J_a / d_in` and `sigma = J_b / d_in`. If one of the channels is
+/// unitary this reduces to the familiar `|Tr(U^† K)|^2 / d^2` summed over Kraus operators.
+///
+/// # Arguments
+/// - `choi_a`: The Choi matrix of the first channel.
+/// - `choi_b`: The Choi matrix of the second channel.
+/// - `d_in`: The input dimension of both channels.
+///
+/// # Returns
+/// A `Result<A::Real, QtnError>` containing the process fidelity in `[0, 1]`, or an error if
+/// the Choi matrices have different shapes or an eigenvalue decomposition fails.
+pub fn process_fidelity<A: Scalar>(
+    choi_a: &Array2<A>,
+    choi_b: &Array2<A>,
+    d_in: usize,
+) -> Result<A::Real, QtnError> {
+    check_choi_pair(choi_a, choi_b, d_in)?;
+
+    let norm = A::from_real(A::real(d_in as f64));
+    let rho = choi_a.mapv(|x| x / norm);
+    let sigma = choi_b.mapv(|x| x / norm);
+
+    let sqrt_rho = psd_sqrt(&rho)?;
+    let product = sqrt_rho.dot(&sigma).dot(&sqrt_rho);
+    let (eigvals, _) = product
+        .eigh(UPLO::Lower)
+        .map_err(|err| QtnError::LinalgFailure(format!("Eigenvalue error: {:?}", err)))?;
+
+    let trace = eigvals.iter().fold(A::real(0.0), |acc, &x| {
+        if x > A::real(0.0) {
+            acc + ndarray_linalg::Scalar::sqrt(x)
+        } else {
+            acc
+        }
+    });
+
+    Ok(trace * trace)
+}
+
+/// Computes the average gate fidelity between two channels with equal input and output dimension.
+///
+/// Uses the relation `F_avg = (d * F_pro + 1) / (d + 1)` to the process fidelity.
+///
+/// # Arguments
+/// - `choi_a`: The Choi matrix of the first channel.
+/// - `choi_b`: The Choi matrix of the second channel.
+/// - `d`: The Hilbert-space dimension the channels act on.
+///
+/// # Returns
+/// A `Result<A::Real, QtnError>` containing the average gate fidelity, or an error under the
+/// same conditions as [`process_fidelity`].
+pub fn average_gate_fidelity<A: Scalar>(
+    choi_a: &Array2<A>,
+    choi_b: &Array2<A>,
+    d: usize,
+) -> Result<A::Real, QtnError> {
+    let f_pro = process_fidelity(choi_a, choi_b, d)?;
+    let d = A::real(d as f64);
+    Ok((d * f_pro + A::real(1.0)) / (d + A::real(1.0)))
+}
+
+/// Estimates the diamond-norm distance between two channels without solving an SDP.
+///
+/// For the difference `Delta = J_a - J_b` of the Choi matrices the diamond norm of the difference
+/// map is bounded by
+/// `||Delta||_1 / d_in <= ||Phi_a - Phi_b||_◇ <= ||Tr_out |Delta| ||_∞`,
+/// where the lower bound is attained by the maximally entangled input state.
+///
+/// # Arguments
+/// - `choi_a`: The Choi matrix of the first channel.
+/// - `choi_b`: The Choi matrix of the second channel.
+/// - `d_in`: The input dimension of both channels.
+///
+/// # Returns
+/// A `Result<(A::Real, A::Real), QtnError>` containing the `(lower, upper)` bounds, or an error
+/// message if the Choi matrices have different shapes or an eigenvalue decomposition fails.
+pub fn diamond_norm_bounds<A: Scalar>(
+    choi_a: &Array2<A>,
+    choi_b: &Array2<A>,
+    d_in: usize,
+) -> Result<(A::Real, A::Real), QtnError> {
+    check_choi_pair(choi_a, choi_b, d_in)?;
+
+    let delta = choi_a - choi_b;
+    let (eigvals, eigvecs) = delta
+        .eigh(UPLO::Lower)
+        .map_err(|err| QtnError::LinalgFailure(format!("Eigenvalue error: {:?}", err)))?;
+
+    let trace_norm = eigvals.iter().fold(A::real(0.0), |acc, &x| acc + x.abs());
+    let lower = trace_norm / A::real(d_in as f64);
+
+    // |Delta| = V |D| V^†, followed by the partial trace over the (fast) output index
+    let abs_delta = eigvecs
+        .dot(&Array2::from_diag(&eigvals.mapv(|x| A::from_real(x.abs()))))
+        .dot(&dagger(&eigvecs));
+
+    let d_out = delta.nrows() / d_in;
+    let reduced = Array2::from_shape_fn((d_in, d_in), |(j, jp)| {
+        (0..d_out).fold(A::zero(), |acc, i| {
+            acc + abs_delta[[i + j * d_out, i + jp * d_out]]
+        })
+    });
+    let (reduced_eigvals, _) = reduced
+        .eigh(UPLO::Lower)
+        .map_err(|err| QtnError::LinalgFailure(format!("Eigenvalue error: {:?}", err)))?;
+
+    let upper = reduced_eigvals
+        .iter()
+        .fold(A::real(0.0), |a, &b| if b.abs() > a { b.abs() } else { a });
+
+    Ok((lower, upper))
+}
+
+/// Converts POVM effects into the Kraus operators of the corresponding Lüders instrument.
+///
+/// Each effect `E_k` is mapped to `sqrt(E_k)`, which is the minimally disturbing measurement
+/// realizing the POVM.
+///
+/// # Arguments
+/// - `effects`: A slice of positive semidefinite effects that sum to the identity.
+///
+/// # Returns
+/// A `Result<Vec<Array2>, QtnError>` containing the Kraus operators, or an error if the
+/// effects are not square, have different shapes, do not sum to the identity, or an eigenvalue
+/// decomposition fails.
+pub fn povm_kraus<A: Scalar>(effects: &[Array2<A>]) -> Result<Vec<Array2<A>>, QtnError> {
+    let kraus = effects
+        .iter()
+        .map(psd_sqrt)
+        .collect::<Result<Vec<_>, QtnError>>()?;
+    check_instrument(&kraus, effects.first().map_or(0, |e| e.nrows()))?;
+    Ok(kraus)
+}
+
+/// Applies a quantum instrument to selected subsystems of a pure state.
+///
+/// The state is interpreted as a tensor with one leg per subsystem, and each Kraus operator acts
+/// on the joint space of `sites` (in the given order, row-major). For every outcome the Born-rule
+/// probability `||M_k psi||^2` and the normalized post-measurement state are returned.
+///
+/// # Arguments
+/// - `psi`: The state vector, with length equal to the product of `dims`.
+/// - `dims`: The local dimension of each subsystem.
+/// - `sites`: The distinct subsystems the instrument acts on.
+/// - `kraus`: The Kraus operators `M_k` of the instrument, satisfying `sum_k M_k^† M_k = I`.
+///
+/// # Returns
+/// A `Result<Vec<MeasurementOutcome<A, Ix1>>, QtnError>` with one entry per Kraus operator, or an
+/// error if the inputs are inconsistent.
+pub fn apply_instrument_to_state<A: Scalar>(
+    psi: &Array1<A>,
+    dims: &[usize],
+    sites: &[usize],
+    kraus: &[Array2<A>],
+) -> Result<Vec<MeasurementOutcome<A, Ix1>>, QtnError> {
+    let d_sub = check_sites(psi.len(), dims, sites)?;
+    check_instrument(kraus, d_sub)?;
+
+    let tensor = psi.to_shape(IxDyn(dims))?.into_owned();
+
+    Ok(kraus
+        .iter()
+        .map(|op| {
+            let out = apply_on_axes(&tensor, op, dims, sites);
+            let out: Array1<A> = out.iter().cloned().collect();
+            let probability = out.iter().fold(A::real(0.0), |acc, x| acc + x.square());
+            let state = normalized(out, ndarray_linalg::Scalar::sqrt(probability));
+            MeasurementOutcome { probability, state }
+        })
+        .collect())
+}
+
+/// Applies a quantum instrument to selected subsystems of a density matrix.
+///
+/// For every Kraus operator the unnormalized post-measurement state `M_k rho M_k^†` is formed;
+/// its trace is the outcome probability and its normalization the post-measurement state.
+///
+/// # Arguments
+/// - `rho`: The density matrix, with dimension equal to the product of `dims`.
+/// - `dims`: The local dimension of each subsystem.
+/// - `sites`: The distinct subsystems the instrument acts on.
+/// - `kraus`: The Kraus operators `M_k` of the instrument, satisfying `sum_k M_k^† M_k = I`.
+///
+/// # Returns
+/// A `Result<Vec<MeasurementOutcome<A, Ix2>>, QtnError>` with one entry per Kraus operator, or an
+/// error if the inputs are inconsistent.
+pub fn apply_instrument<A: Scalar>(
+    rho: &Array2<A>,
+    dims: &[usize],
+    sites: &[usize],
+    kraus: &[Array2<A>],
+) -> Result<Vec<MeasurementOutcome<A, Ix2>>, QtnError> {
+    if rho.nrows() != rho.ncols() {
+        return Err(QtnError::InvalidArgument(format!(
+            "Density matrix must be square, found shape {:?}.",
+            rho.dim()
+        )));
+    }
+    let d_sub = check_sites(rho.nrows(), dims, sites)?;
+    check_instrument(kraus, d_sub)?;
+
+    let n = dims.len();
+    let doubled_dims = [dims, dims].concat();
+    let bra_sites: Vec<usize> = sites.iter().map(|&s| s + n).collect();
+    let tensor = rho.to_shape(IxDyn(&doubled_dims))?.into_owned();
+
+    Ok(kraus
+        .iter()
+        .map(|op| {
+            let ket = apply_on_axes(&tensor, op, &doubled_dims, sites);
+            let out = apply_on_axes(&ket, &op.mapv(|x| x.conj()), &doubled_dims, &bra_sites);
+            let out = Array2::from_shape_vec(rho.dim(), out.iter().cloned().collect())
+                .expect("ShapeError!");
+            let probability = out.diag().iter().fold(A::real(0.0), |acc, x| acc + x.re());
+            let state = normalized(out, probability);
+            MeasurementOutcome { probability, state }
+        })
+        .collect())
+}
+
+/// Computes the reduced density matrix of selected subsystems.
+///
+/// The density matrix is interpreted as an operator on the tensor product of subsystems of
+/// dimensions `dims` (row-major, the first subsystem varying slowest), and all subsystems not
+/// in `keep` are traced out.
+///
+/// # Arguments
+/// - `rho`: The density matrix, with dimension equal to the product of `dims`.
+/// - `dims`: The local dimension of each subsystem.
+/// - `keep`: The distinct subsystems that remain, in the order of their factors in the result.
+///
+/// # Returns
+/// A `Result<Array2<A>, QtnError>` containing the reduced density matrix, of dimension equal to
+/// the product of the kept dimensions, or an error if `rho` is not square or the subsystems
+/// are inconsistent.
+pub fn partial_trace<A: Scalar>(
+    rho: &Array2<A>,
+    dims: &[usize],
+    keep: &[usize],
+) -> Result<Array2<A>, QtnError> {
+    if rho.nrows() != rho.ncols() {
+        return Err(QtnError::InvalidArgument(format!(
+            "Density matrix must be square, found shape {:?}.",
+            rho.dim()
+        )));
+    }
+    let d_keep = check_sites(rho.nrows(), dims, keep)?;
+    let d_traced = rho.nrows() / d_keep.max(1);
+
+    // Order the ket and bra legs as (kept, traced) and sum the diagonal of the traced block
+    let n = dims.len();
+    let traced: Vec<usize> = (0..n).filter(|s| !keep.contains(s)).collect();
+    let ket: Vec<usize> = keep.iter().chain(&traced).cloned().collect();
+    let perm: Vec<usize> = ket
+        .iter()
+        .cloned()
+        .chain(ket.iter().map(|&s| s + n))
+        .collect();
+    let tensor = rho.to_shape(IxDyn(&[dims, dims].concat()))?;
+    let blocks = tensor.permuted_axes(IxDyn(&perm));
+    let blocks = blocks.to_shape((d_keep, d_traced, d_keep, d_traced))?;
+
+    Ok(Array2::from_shape_fn((d_keep, d_keep), |(i, j)| {
+        (0..d_traced).fold(A::zero(), |acc, t| acc + blocks[[i, t, j, t]])
+    }))
+}
+
+/// Samples an outcome index according to the Born-rule probabilities of an instrument.
+///
+/// # Arguments
+/// - `outcomes`: The outcomes returned by [`apply_instrument`] or [`apply_instrument_to_state`].
+///
+/// # Returns
+/// The index of the sampled outcome.
+pub fn sample_outcome<A: Scalar, D: Dimension>(outcomes: &[MeasurementOutcome<A, D>]) -> usize {
+    let total = outcomes
+        .iter()
+        .fold(A::real(0.0), |acc, o| acc + o.probability);
+    let mut r = A::real(rand::rng().random::<f64>()) * total;
+
+    for (k, outcome) in outcomes.iter().enumerate() {
+        if r < outcome.probability {
+            return k;
+        }
+        r -= outcome.probability;
+    }
+    outcomes.len().saturating_sub(1)
+}
+
+/// Checks that the subsystem selection is valid and returns the dimension of the selected space.
+fn check_sites(total: usize, dims: &[usize], sites: &[usize]) -> Result<usize, QtnError> {
+    if dims.iter().product::<usize>() != total {
+        return Err(QtnError::InvalidArgument(format!(
+            "Subsystem dimensions {:?} do not match the state dimension {}.",
+            dims, total
+        )));
+    }
+    for (k, &site) in sites.iter().enumerate() {
+        if site >= dims.len() || sites[..k].contains(&site) {
+            return Err(QtnError::InvalidArgument(format!(
+                "Invalid site {} for a system of {} subsystems.",
+                site,
+                dims.len()
+            )));
+        }
+    }
+    Ok(sites.iter().map(|&s| dims[s]).product())
+}
+
+/// Checks that the Kraus operators have the right shape and satisfy the completeness relation.
+fn check_instrument<A: Scalar>(kraus: &[Array2<A>], dim: usize) -> Result<(), QtnError> {
+    if kraus.is_empty() {
+        return Err(QtnError::InvalidArgument(
+            "Instrument needs at least one Kraus operator.".to_string(),
+        ));
+    }
+    let mut completeness = Array2::<A>::zeros((dim, dim));
+    for (k, op) in kraus.iter().enumerate() {
+        if op.dim() != (dim, dim) {
+            return Err(QtnError::InvalidArgument(format!(
+                "Kraus operator {} has shape {:?}, expected ({}, {}).",
+                k,
+                op.dim(),
+                dim,
+                dim
+            )));
+        }
+        completeness = completeness + dagger(op).dot(op);
+    }
+
+    let deviation = (completeness - Array2::<A>::eye(dim))
+        .iter()
+        .fold(A::real(0.0), |a, x| if x.abs() > a { x.abs() } else { a });
+    if deviation > A::real(1e-10) {
+        return Err(QtnError::InvalidArgument(format!(
+            "Kraus operators do not satisfy the completeness relation (deviation {}).",
+            deviation
+        )));
+    }
+    Ok(())
+}
+
+/// Applies a matrix to the given axes of a tensor, leaving the axis order unchanged.
+fn apply_on_axes<A: Scalar>(
+    tensor: &ArrayD<A>,
+    op: &Array2<A>,
+    dims: &[usize],
+    axes: &[usize],
+) -> ArrayD<A> {
+    let rest: Vec<usize> = (0..dims.len()).filter(|k| !axes.contains(k)).collect();
+    let perm = [axes, &rest[..]].concat();
+    let d_sub: usize = axes.iter().map(|&k| dims[k]).product();
+    let d_rest: usize = rest.iter().map(|&k| dims[k]).product();
+
+    let permuted = tensor.view().permuted_axes(IxDyn(&perm));
+    let matrix = permuted
+        .to_shape((d_sub, d_rest))
+        .expect("Failed to reshape permuted tensor");
+    let permuted_dims: Vec<usize> = perm.iter().map(|&k| dims[k]).collect();
+    let result = op
+        .dot(&matrix)
+        .into_shape_with_order(IxDyn(&permuted_dims))
+        .expect("Failed to reshape output");
+
+    let mut inverse = vec![0; perm.len()];
+    for (k, &p) in perm.iter().enumerate() {
+        inverse[p] = k;
+    }
+    result
+        .permuted_axes(IxDyn(&inverse))
+        .as_standard_layout()
+        .into_owned()
+}
+
+/// Divides an array by the given norm, or returns `None` if the norm vanishes.
+fn normalized<A: Scalar, D: Dimension>(arr: Array<A, D>, norm: A::Real) -> Option<Array<A, D>> {
+    if norm > A::real(0.0) {
+        let norm = A::from_real(norm);
+        Some(arr.mapv(|x| x / norm))
+    } else {
+        None
+    }
+}
+
+/// Checks that two Choi matrices have the same square shape compatible with `d_in`.
+fn check_choi_pair<A>(choi_a: &Array2<A>, choi_b: &Array2<A>, d_in: usize) -> Result<(), QtnError> {
+    if choi_a.dim() != choi_b.dim() {
+        return Err(QtnError::InvalidArgument(format!(
+            "Choi matrices have different shapes: {:?} and {:?}.",
+            choi_a.dim(),
+            choi_b.dim()
+        )));
+    }
+    if choi_a.nrows() != choi_a.ncols() || d_in == 0 || !choi_a.nrows().is_multiple_of(d_in) {
+        return Err(QtnError::InvalidArgument(format!(
+            "Choi matrix of shape {:?} is incompatible with input dimension {}.",
+            choi_a.dim(),
+            d_in
+        )));
+    }
+    Ok(())
+}
+
+/// Computes the square root of a positive semidefinite Hermitian matrix.
+fn psd_sqrt<A: Scalar>(m: &Array2<A>) -> Result<Array2<A>, QtnError> {
+    let (eigvals, eigvecs) = m
+        .eigh(UPLO::Lower)
+        .map_err(|err| QtnError::LinalgFailure(format!("Eigenvalue error: {:?}", err)))?;
+
+    let sqrt_vals = eigvals.mapv(|x| {
+        if x > A::real(0.0) {
+            A::from_real(ndarray_linalg::Scalar::sqrt(x))
+        } else {
+            A::zero()
+        }
+    });
+
+    Ok(eigvecs
+        .dot(&Array2::from_diag(&sqrt_vals))
+        .dot(&dagger(&eigvecs)))
+}
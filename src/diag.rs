@@ -0,0 +1,161 @@
+use crate::error::QtnError;
+use crate::scalar::Scalar;
+use ndarray::{Array1, Array2, ArrayD, Axis};
+
+/// A diagonal matrix stored as the vector of its diagonal entries.
+///
+/// Bond weights, such as the singular values between the tensors of a canonical (Γ–Λ) form or
+/// the weights of simple-update PEPS, are diagonal matrices. Storing only the weights avoids the
+/// `d x d` dense matrix, and contracting them with a tensor rescales the slices along one axis
+/// instead of performing a matrix product. A dense copy for general networks is available from
+/// [`DiagTensor::to_dense`], which the planner can exploit when the tensor is flagged as
+/// [`crate::path::TensorStructure::Diagonal`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiagTensor<A: Scalar = f64> {
+    weights: Array1<A>,
+}
+
+impl<A: Scalar> DiagTensor<A> {
+    /// Creates a diagonal tensor from its weights.
+    ///
+    /// # Arguments
+    /// - `weights`: The diagonal entries.
+    ///
+    /// # Returns
+    /// A new `DiagTensor` of dimension `weights.len()`.
+    pub fn new(weights: Array1<A>) -> Self {
+        DiagTensor { weights }
+    }
+
+    /// Creates a diagonal tensor from real weights, e.g. singular values.
+    ///
+    /// # Arguments
+    /// - `weights`: The real diagonal entries, converted to the scalar type `A`.
+    ///
+    /// # Returns
+    /// A new `DiagTensor` of dimension `weights.len()`.
+    pub fn from_real(weights: &Array1<A::Real>) -> Self {
+        DiagTensor {
+            weights: weights.mapv(A::from_real),
+        }
+    }
+
+    /// Returns the dimension of the legs.
+    pub fn dim(&self) -> usize {
+        self.weights.len()
+    }
+
+    /// Returns the diagonal entries.
+    pub fn weights(&self) -> &Array1<A> {
+        &self.weights
+    }
+
+    /// Returns the dense `dim x dim` matrix.
+    pub fn to_dense(&self) -> Array2<A> {
+        Array2::from_diag(&self.weights)
+    }
+
+    /// Returns the elementwise square root of the weights.
+    ///
+    /// Splitting a bond weight symmetrically between two neighbouring tensors uses the square root
+    /// on both sides.
+    pub fn sqrt(&self) -> Self {
+        DiagTensor {
+            weights: self.weights.mapv(|w| w.sqrt()),
+        }
+    }
+
+    /// Returns the pseudo-inverse of the diagonal matrix.
+    ///
+    /// Weights whose absolute value does not exceed `cutoff` are mapped to zero instead of being
+    /// inverted, so that vanishing singular values do not blow up when a canonical form is
+    /// converted back.
+    ///
+    /// # Arguments
+    /// - `cutoff`: The absolute value below which a weight is treated as zero.
+    ///
+    /// # Returns
+    /// A new `DiagTensor` with the inverted weights.
+    pub fn inverse(&self, cutoff: A::Real) -> Self {
+        DiagTensor {
+            weights: self.weights.mapv(|w| {
+                if w.abs() > cutoff {
+                    A::one() / w
+                } else {
+                    A::zero()
+                }
+            }),
+        }
+    }
+
+    /// Contracts the diagonal tensor with one axis of a dense tensor.
+    ///
+    /// Since the matrix is diagonal, the result has the shape of `tensor`, and its slices along
+    /// `axis` are scaled by the corresponding weights. The new leg takes the place of the
+    /// contracted one.
+    ///
+    /// # Arguments
+    /// - `tensor`: The dense tensor to contract with.
+    /// - `axis`: The axis of `tensor` that is contracted with the diagonal tensor.
+    ///
+    /// # Returns
+    /// A `Result<ArrayD<A>, QtnError>` where:
+    /// - `Ok(ArrayD<A>)` contains the rescaled tensor.
+    /// - `Err(QtnError)` contains the error if the axis cannot be contracted.
+    ///
+    /// # Errors
+    /// This function may return an error if:
+    /// - `axis` is not an axis of `tensor` ([`QtnError::InvalidArgument`]).
+    /// - The dimension of `axis` differs from the dimension of the diagonal tensor
+    ///   ([`QtnError::ShapeMismatch`], with `tensor` as tensor 0 and the diagonal as tensor 1).
+    pub fn apply(&self, tensor: &ArrayD<A>, axis: usize) -> Result<ArrayD<A>, QtnError> {
+        if axis >= tensor.ndim() {
+            return Err(QtnError::InvalidArgument(format!(
+                "Axis {} is out of range for a tensor of rank {}.",
+                axis,
+                tensor.ndim()
+            )));
+        }
+        if tensor.shape()[axis] != self.dim() {
+            return Err(QtnError::ShapeMismatch {
+                tensor: 0,
+                axis,
+                dim: tensor.shape()[axis],
+                other_tensor: 1,
+                other_axis: 0,
+                other_dim: self.dim(),
+            });
+        }
+
+        let mut result = tensor.clone();
+        for (mut slice, &w) in result.axis_iter_mut(Axis(axis)).zip(&self.weights) {
+            slice.mapv_inplace(|x| x * w);
+        }
+        Ok(result)
+    }
+
+    /// Contracts two diagonal tensors over one leg, which multiplies their weights.
+    ///
+    /// # Arguments
+    /// - `other`: The diagonal tensor to contract with.
+    ///
+    /// # Returns
+    /// A `Result<DiagTensor<A>, QtnError>` containing the product, or
+    /// [`QtnError::ShapeMismatch`] if the dimensions differ.
+    pub fn contract(&self, other: &DiagTensor<A>) -> Result<Self, QtnError> {
+        if self.dim() != other.dim() {
+            return Err(QtnError::ShapeMismatch {
+                tensor: 0,
+                axis: 1,
+                dim: self.dim(),
+                other_tensor: 1,
+                other_axis: 0,
+                other_dim: other.dim(),
+            });
+        }
+
+        Ok(DiagTensor {
+            weights: &self.weights * &other.weights,
+        })
+    }
+}
@@ -0,0 +1,282 @@
+use crate::error::QtnError;
+use crate::scalar::Scalar;
+use crate::tencon::contract;
+use crate::trace::trace;
+use ndarray::{ArrayBase, ArrayD, Axis, CowArray, Data, IxDyn};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::hash::Hash;
+
+/// Input label groups and optional output labels of a parsed einsum expression.
+type Subscripts = (Vec<Vec<char>>, Option<Vec<char>>);
+
+/// Contracts a list of tensors according to an einsum subscript string.
+///
+/// The subscripts follow the `numpy.einsum` notation: one group of single-letter labels per input
+/// tensor, separated by commas, optionally followed by `->` and the labels of the output tensor,
+/// e.g. `"abc,cd->abd"`. Without `->` the output consists of the labels that appear exactly once,
+/// in alphabetical order. The expression is translated into the integer convention of
+/// [`contract`]: output labels become the negative indices `-1, -2, ...` in output order and
+/// every label shared by two tensors becomes a positive index.
+///
+/// Labels repeated inside one input (e.g. `"ii->"`) are traced, and labels that appear in a single
+/// input but not in the output are summed over before the contraction.
+///
+/// # Arguments
+/// - `subscripts`: The einsum expression. Whitespace is ignored.
+/// - `tensors`: A slice of `ArrayD<A>` or `ArrayViewD<A>` holding one tensor per input group of
+///   `subscripts`.
+///
+/// # Returns
+/// A `Result<ArrayD<A>, QtnError>` where:
+/// - `Ok(ArrayD<A>)` contains the resulting tensor, with its axes in the order of the output labels.
+/// - `Err(QtnError)` contains the error if the expression is invalid or the contraction fails.
+///
+/// # Errors
+/// This function may return an error if:
+/// - The expression contains characters other than ASCII letters, `,`, `-` and `>`, or more than one `->`.
+/// - The number of input groups differs from the number of tensors, or a group's length differs
+///   from the rank of its tensor.
+/// - A label is used with two different dimensions.
+/// - An output label is repeated or does not appear in any input.
+/// - A label appears in more than two input positions, or appears both in the output and more
+///   than once in the inputs (batch and diagonal indices are not supported by [`contract`]).
+pub fn einsum<A, S>(
+    subscripts: &str,
+    tensors: &[ArrayBase<S, IxDyn>],
+) -> Result<ArrayD<A>, QtnError>
+where
+    A: Scalar,
+    S: Data<Elem = A>,
+{
+    let (inputs, output) = parse_subscripts(subscripts)?;
+
+    let output = match output {
+        Some(output) => output,
+        None => {
+            let mut implicit: Vec<char> = inputs
+                .iter()
+                .flatten()
+                .filter(|&label| inputs.iter().flatten().filter(|&l| l == label).count() == 1)
+                .cloned()
+                .collect();
+            implicit.sort_unstable();
+            implicit
+        }
+    };
+
+    labeled_contraction(tensors, &inputs, &output)
+}
+
+/// Contracts a list of tensors whose legs are named by string labels.
+///
+/// This is the labeled counterpart of [`contract`]: instead of signed integers, every leg carries
+/// a name. Labels shared by two tensors are contracted, and the remaining legs are kept in the
+/// order given by `output`. As in [`einsum`], a label repeated inside one tensor is traced and a
+/// label that appears on a single leg but not in `output` is summed over.
+///
+/// # Arguments
+/// - `tensors`: A slice of `ArrayD<A>` representing the tensors to be contracted.
+/// - `labels`: One slice of leg labels per tensor, e.g. `&[&["i", "j"], &["j", "k"]]`.
+/// - `output`: The labels of the legs of the resulting tensor, in the desired axis order.
+///
+/// # Returns
+/// A `Result<ArrayD<A>, QtnError>` where:
+/// - `Ok(ArrayD<A>)` contains the resulting tensor, with its axes in the order of `output`.
+/// - `Err(QtnError)` contains the error if the labels are inconsistent or the contraction fails.
+///
+/// # Errors
+/// This function may return an error under the same conditions as [`einsum`], i.e. if the number
+/// of label lists or their lengths do not match the tensors, a label has two dimensions, an output
+/// label is repeated or missing from the inputs, or a label appears more than twice.
+pub fn contract_labeled<A, S>(
+    tensors: &[ArrayBase<S, IxDyn>],
+    labels: &[&[&str]],
+    output: &[&str],
+) -> Result<ArrayD<A>, QtnError>
+where
+    A: Scalar,
+    S: Data<Elem = A>,
+{
+    let inputs: Vec<Vec<&str>> = labels.iter().map(|l| l.to_vec()).collect();
+    labeled_contraction(tensors, &inputs, output)
+}
+
+/// Validates labeled inputs and translates them into the integer convention of [`contract`].
+fn labeled_contraction<A, S, L>(
+    tensors: &[ArrayBase<S, IxDyn>],
+    inputs: &[Vec<L>],
+    output: &[L],
+) -> Result<ArrayD<A>, QtnError>
+where
+    A: Scalar,
+    S: Data<Elem = A>,
+    L: Copy + Eq + Hash + Display,
+{
+    if inputs.len() != tensors.len() {
+        return Err(QtnError::InvalidArgument(format!(
+            "Labels describe {} tensors, but {} were given.",
+            inputs.len(),
+            tensors.len()
+        )));
+    }
+
+    let mut counts: HashMap<L, usize> = HashMap::new();
+    let mut dims: HashMap<L, usize> = HashMap::new();
+    for (k, (labels, tensor)) in inputs.iter().zip(tensors).enumerate() {
+        if labels.len() != tensor.ndim() {
+            return Err(QtnError::InvalidArgument(format!(
+                "Tensor {} has {} labels, but {} legs.",
+                k,
+                labels.len(),
+                tensor.ndim()
+            )));
+        }
+        for (&label, &dim) in labels.iter().zip(tensor.shape()) {
+            *counts.entry(label).or_insert(0) += 1;
+            if *dims.entry(label).or_insert(dim) != dim {
+                return Err(QtnError::InvalidArgument(format!(
+                    "Label '{}' is used with dimensions {} and {}.",
+                    label, dims[&label], dim
+                )));
+            }
+        }
+    }
+
+    for (k, label) in output.iter().enumerate() {
+        if output[..k].contains(label) {
+            return Err(QtnError::InvalidArgument(format!(
+                "Output label '{}' is repeated.",
+                label
+            )));
+        }
+        match counts.get(label) {
+            None => {
+                return Err(QtnError::InvalidArgument(format!(
+                    "Output label '{}' does not appear in any input.",
+                    label
+                )))
+            }
+            Some(&1) => {}
+            Some(_) => {
+                return Err(QtnError::InvalidArgument(format!(
+                    "Output label '{}' appears more than once in the inputs.",
+                    label
+                )))
+            }
+        }
+    }
+    if let Some((label, _)) = counts.iter().find(|&(_, &count)| count > 2) {
+        return Err(QtnError::InvalidArgument(format!(
+            "Label '{}' appears more than twice in the inputs.",
+            label
+        )));
+    }
+
+    // Trace repeated labels and sum dangling ones, so each tensor only keeps shared and output legs
+    let mut reduced = Vec::with_capacity(tensors.len());
+    let mut labels_list = Vec::with_capacity(tensors.len());
+    for (labels, tensor) in inputs.iter().zip(tensors) {
+        let (tensor, labels) = reduce_term(tensor, labels, &counts, output)?;
+        reduced.push(tensor);
+        labels_list.push(labels);
+    }
+
+    if reduced.len() == 1 {
+        let labels = &labels_list[0];
+        let perm: Vec<usize> = output
+            .iter()
+            .map(|label| labels.iter().position(|l| l == label).unwrap())
+            .collect();
+        return Ok(reduced.remove(0).into_owned().permuted_axes(IxDyn(&perm)));
+    }
+
+    let mut ids: HashMap<L, i32> = output
+        .iter()
+        .enumerate()
+        .map(|(k, &label)| (label, -(k as i32 + 1)))
+        .collect();
+    let mut next = 1;
+    let orders: Vec<Vec<i32>> = labels_list
+        .iter()
+        .map(|labels| {
+            labels
+                .iter()
+                .map(|&label| {
+                    *ids.entry(label).or_insert_with(|| {
+                        next += 1;
+                        next - 1
+                    })
+                })
+                .collect()
+        })
+        .collect();
+
+    let order_refs: Vec<&[i32]> = orders.iter().map(|o| o.as_slice()).collect();
+    contract(&reduced, &order_refs)
+}
+
+/// Splits an einsum expression into its input label groups and optional output labels.
+fn parse_subscripts(subscripts: &str) -> Result<Subscripts, QtnError> {
+    let compact: String = subscripts.chars().filter(|c| !c.is_whitespace()).collect();
+
+    let mut parts = compact.split("->");
+    let lhs = parts.next().unwrap_or("");
+    let rhs = parts.next();
+    if parts.next().is_some() {
+        return Err(QtnError::InvalidArgument(format!(
+            "Subscripts '{}' contain more than one '->'.",
+            subscripts
+        )));
+    }
+
+    let check = |group: &str| -> Result<Vec<char>, QtnError> {
+        match group.chars().find(|c| !c.is_ascii_alphabetic()) {
+            Some(c) => Err(QtnError::InvalidArgument(format!(
+                "Invalid character '{}' in subscripts '{}'.",
+                c, subscripts
+            ))),
+            None => Ok(group.chars().collect()),
+        }
+    };
+
+    let inputs = lhs.split(',').map(check).collect::<Result<Vec<_>, _>>()?;
+    let output = rhs.map(check).transpose()?;
+
+    Ok((inputs, output))
+}
+
+/// Traces labels repeated within one term and sums labels that appear nowhere else.
+fn reduce_term<'a, A, S, L>(
+    tensor: &'a ArrayBase<S, IxDyn>,
+    labels: &[L],
+    counts: &HashMap<L, usize>,
+    output: &[L],
+) -> Result<(CowArray<'a, A, IxDyn>, Vec<L>), QtnError>
+where
+    A: Scalar,
+    S: Data<Elem = A>,
+    L: Copy + Eq + Hash,
+{
+    // The tensor is only copied once a leg is traced or summed
+    let mut tensor = CowArray::from(tensor.view());
+    let mut labels = labels.to_vec();
+
+    let mut k = 0;
+    while k < labels.len() {
+        let label = labels[k];
+        if let Some(offset) = labels[k + 1..].iter().position(|&l| l == label) {
+            let j = k + 1 + offset;
+            tensor = CowArray::from(trace(&tensor, vec![k, j])?);
+            labels.remove(j);
+            labels.remove(k);
+        } else if counts[&label] == 1 && !output.contains(&label) {
+            tensor = CowArray::from(tensor.sum_axis(Axis(k)));
+            labels.remove(k);
+        } else {
+            k += 1;
+        }
+    }
+
+    Ok((tensor, labels))
+}
@@ -0,0 +1,101 @@
+use ndarray::ShapeError;
+use thiserror::Error;
+
+/// Error type returned by the fallible operations of this crate.
+///
+/// The variants carry the offending indices, axes and dimensions as fields, so callers can react
+/// to a specific failure without parsing the error message.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum QtnError {
+    /// A list of paired axes (as used by `tensor_dot`) has an odd number of entries.
+    #[error("Axis length is not even number! (Axes length is {len}.)")]
+    OddAxisCount { len: usize },
+
+    /// A trace was requested over a number of axes other than two.
+    #[error("Trace calculation need two axes index. (Axes length is {len}!)")]
+    TraceAxisCount { len: usize },
+
+    /// Two legs that are contracted or traced together have different dimensions.
+    #[error(
+        "Shape mismatch along specified axes: tensor {tensor} axis {axis} has dimension {dim}, \
+         tensor {other_tensor} axis {other_axis} has dimension {other_dim}."
+    )]
+    ShapeMismatch {
+        tensor: usize,
+        axis: usize,
+        dim: usize,
+        other_tensor: usize,
+        other_axis: usize,
+        other_dim: usize,
+    },
+
+    /// A contraction was requested over an empty list of tensors.
+    #[error("Contraction needs at least one tensor.")]
+    EmptyNetwork,
+
+    /// The contraction order lists a different number of tensors than were given.
+    #[error("Contraction order describes {orders} tensors, but {tensors} were given.")]
+    OrderCountMismatch { tensors: usize, orders: usize },
+
+    /// A tensor has a different number of legs than indices in its contraction order.
+    #[error(
+        "Tensor {tensor} has rank {rank}, but its contraction order lists {order_len} indices."
+    )]
+    RankMismatch {
+        tensor: usize,
+        rank: usize,
+        order_len: usize,
+    },
+
+    /// An index of an integer contraction order is zero or appears the wrong number of times.
+    ///
+    /// Positive indices must appear exactly twice and negative indices at most once.
+    #[error("Index {index} appears {count} times in contraction order list.")]
+    InvalidContractionOrder { index: i32, count: usize },
+
+    /// A requested output leg ordering is not a permutation of the free indices of the network.
+    #[error("Output order {0:?} is not a permutation of the free indices.")]
+    InvalidOutputOrder(Vec<i32>),
+
+    /// A tensor passed to a compiled contraction has a different shape than it was compiled for.
+    #[error("Tensor {tensor} has shape {found:?}, but the plan was compiled for {expected:?}.")]
+    PlanShapeMismatch {
+        tensor: usize,
+        expected: Vec<usize>,
+        found: Vec<usize>,
+    },
+
+    /// A contraction path refers to missing tensors or does not reduce the network to one tensor.
+    #[error("Contraction path is invalid at step {step}.")]
+    InvalidPath { step: usize },
+
+    /// A contraction cannot be sliced so that every intermediate fits the memory limit.
+    #[error(
+        "Contraction needs an intermediate of {required} elements, above the limit of {limit}."
+    )]
+    MemoryLimitExceeded { limit: usize, required: usize },
+
+    /// A LAPACK routine (SVD, eigenvalue decomposition, ...) failed or returned no result.
+    #[error("{0}")]
+    LinalgFailure(String),
+
+    /// An array could not be reshaped.
+    #[error(transparent)]
+    Shape(#[from] ShapeError),
+
+    /// The arguments of an operation are inconsistent, e.g. mismatched operator shapes.
+    #[error("{0}")]
+    InvalidArgument(String),
+
+    /// An iterative method did not reach the requested tolerance.
+    #[error("{0}")]
+    NotConverged(String),
+
+    /// A contraction result disagrees with the reference computation of the self-test.
+    #[error("Self-test failed at result element {index:?}: found {found}, expected {expected}.")]
+    SelfTestFailed {
+        index: Vec<usize>,
+        found: String,
+        expected: String,
+    },
+}
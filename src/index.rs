@@ -0,0 +1,321 @@
+use crate::error::QtnError;
+use crate::scalar::Scalar;
+use crate::tencon::contract;
+use crate::tensor::random;
+use ndarray::{ArcArray, ArrayBase, ArrayD, ArrayViewD, ArrayViewMutD, DataOwned, IxDyn};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A tensor leg with a dimension, a unique identity, optional tags and a prime level.
+///
+/// Two indices are equal if they share the same id, tags and prime level. Every call to
+/// [`Index::new`] or [`Index::with_tags`] creates a fresh id, so an index only matches its own
+/// copies. Priming an index keeps its id but makes it distinct from the unprimed version, which is
+/// the usual way to tell apart the ket and bra legs of an operator.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Index {
+    dim: usize,
+    id: u64,
+    tags: Vec<String>,
+    prime_level: u32,
+}
+
+impl Index {
+    /// Creates a new index of the given dimension with a fresh id and no tags.
+    ///
+    /// # Arguments
+    /// - `dim`: The dimension of the leg.
+    ///
+    /// # Returns
+    /// A new `Index` at prime level zero.
+    pub fn new(dim: usize) -> Self {
+        Index {
+            dim,
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            tags: Vec::new(),
+            prime_level: 0,
+        }
+    }
+
+    /// Creates a new index of the given dimension with a fresh id and the given tags.
+    ///
+    /// # Arguments
+    /// - `dim`: The dimension of the leg.
+    /// - `tags`: Descriptive labels such as `"Site"` or `"Link"`. Duplicates are ignored.
+    ///
+    /// # Returns
+    /// A new `Index` at prime level zero.
+    pub fn with_tags(dim: usize, tags: &[&str]) -> Self {
+        let mut index = Index::new(dim);
+        for &tag in tags {
+            if !index.has_tag(tag) {
+                index.tags.push(tag.to_string());
+            }
+        }
+        index
+    }
+
+    /// Returns the dimension of the index.
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    /// Returns the unique id shared by all copies and primed versions of the index.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Returns the tags of the index.
+    pub fn tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Returns `true` if the index carries the given tag.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// Returns the prime level of the index.
+    pub fn prime_level(&self) -> u32 {
+        self.prime_level
+    }
+
+    /// Returns a copy of the index with the prime level raised by one.
+    pub fn prime(&self) -> Self {
+        self.set_prime(self.prime_level + 1)
+    }
+
+    /// Returns a copy of the index at prime level zero.
+    pub fn noprime(&self) -> Self {
+        self.set_prime(0)
+    }
+
+    /// Returns a copy of the index at the given prime level.
+    pub fn set_prime(&self, level: u32) -> Self {
+        Index {
+            prime_level: level,
+            ..self.clone()
+        }
+    }
+}
+
+/// A dense tensor whose legs are labeled by [`Index`] values.
+///
+/// Contracting two such tensors sums over every index they have in common, so networks can be
+/// assembled by sharing indices instead of maintaining integer contraction lists by hand.
+///
+/// The entries are held in a reference-counted buffer. Cloning, priming or permuting a tensor
+/// shares the buffer instead of copying it, and the buffer is only copied when one of the
+/// sharing tensors is modified through [`Tensor::data_mut`]. Tensors can be sent to and shared
+/// between threads.
+#[derive(Clone, Debug)]
+pub struct Tensor<A: Scalar = f64> {
+    data: ArcArray<A, IxDyn>,
+    indices: Vec<Index>,
+}
+
+impl<A: Scalar> Tensor<A> {
+    /// Wraps an array together with the indices of its legs.
+    ///
+    /// # Arguments
+    /// - `data`: The tensor entries, with one axis per index. Both owned arrays and shared
+    ///   `ArcArray`s are accepted; the latter keep sharing their buffer.
+    /// - `indices`: The indices of the legs, in axis order.
+    ///
+    /// # Returns
+    /// A `Result<Tensor<A>, QtnError>` containing the indexed tensor, or an error if the
+    /// number of indices differs from the rank of `data`, an index dimension does not match the
+    /// corresponding axis, or an index appears twice.
+    pub fn new<S>(data: ArrayBase<S, IxDyn>, indices: &[Index]) -> Result<Self, QtnError>
+    where
+        S: DataOwned<Elem = A>,
+    {
+        if data.ndim() != indices.len() {
+            return Err(QtnError::InvalidArgument(format!(
+                "Tensor of rank {} cannot carry {} indices.",
+                data.ndim(),
+                indices.len()
+            )));
+        }
+        for (axis, (index, &dim)) in indices.iter().zip(data.shape()).enumerate() {
+            if index.dim != dim {
+                return Err(QtnError::InvalidArgument(format!(
+                    "Index of dimension {} does not match axis {} of dimension {}.",
+                    index.dim, axis, dim
+                )));
+            }
+            if indices[..axis].contains(index) {
+                return Err(QtnError::InvalidArgument(format!(
+                    "Index on axis {} appears twice.",
+                    axis
+                )));
+            }
+        }
+
+        Ok(Tensor {
+            data: data.into_shared(),
+            indices: indices.to_vec(),
+        })
+    }
+
+    /// Returns the tensor entries.
+    pub fn data(&self) -> &ArcArray<A, IxDyn> {
+        &self.data
+    }
+
+    /// Returns a mutable view of the tensor entries.
+    ///
+    /// If the buffer is shared with other tensors, it is copied first, so the other tensors are
+    /// not affected.
+    pub fn data_mut(&mut self) -> ArrayViewMutD<'_, A> {
+        self.data.view_mut()
+    }
+
+    /// Consumes the tensor and returns its entries. The buffer is only copied if it is shared.
+    pub fn into_data(self) -> ArrayD<A> {
+        self.data.into_owned()
+    }
+
+    /// Returns `true` if both tensors hold their entries in the same buffer.
+    pub fn shares_data(&self, other: &Tensor<A>) -> bool {
+        self.data.as_ptr() == other.data.as_ptr()
+    }
+
+    /// Returns the indices of the legs, in axis order.
+    pub fn indices(&self) -> &[Index] {
+        &self.indices
+    }
+
+    /// Returns a copy of the tensor with every index primed once. The entries are shared.
+    pub fn prime(&self) -> Self {
+        Tensor {
+            data: self.data.clone(),
+            indices: self.indices.iter().map(Index::prime).collect(),
+        }
+    }
+
+    /// Returns a copy of the tensor with its axes reordered. The entries are shared.
+    ///
+    /// # Arguments
+    /// - `indices`: The indices of the tensor in the desired axis order.
+    ///
+    /// # Returns
+    /// A `Result<Tensor<A>, QtnError>` containing the permuted tensor, or an error if
+    /// `indices` is not a permutation of the tensor's indices.
+    pub fn permute(&self, indices: &[Index]) -> Result<Self, QtnError> {
+        let mut perm = Vec::with_capacity(indices.len());
+        for index in indices {
+            match self.indices.iter().position(|i| i == index) {
+                Some(axis) if !perm.contains(&axis) => perm.push(axis),
+                _ => {
+                    return Err(QtnError::InvalidArgument(
+                        "Indices are not a permutation of the tensor legs.".to_string(),
+                    ))
+                }
+            }
+        }
+        if perm.len() != self.indices.len() {
+            return Err(QtnError::InvalidArgument(
+                "Indices are not a permutation of the tensor legs.".to_string(),
+            ));
+        }
+
+        Ok(Tensor {
+            data: self.data.clone().permuted_axes(IxDyn(&perm)),
+            indices: indices.to_vec(),
+        })
+    }
+
+    /// Contracts this tensor with another one over all shared indices.
+    ///
+    /// # Arguments
+    /// - `other`: The tensor to contract with.
+    ///
+    /// # Returns
+    /// A `Result<Tensor<A>, QtnError>` containing the product, or an error under the same
+    /// conditions as [`contract_indexed`].
+    pub fn contract(&self, other: &Tensor<A>) -> Result<Self, QtnError> {
+        contract_indexed(&[self.clone(), other.clone()])
+    }
+}
+
+impl Tensor<f64> {
+    /// Creates a tensor with the given indices, filled with random values in the range [0.0, 1.0].
+    pub fn random(indices: &[Index]) -> Self {
+        let shape: Vec<usize> = indices.iter().map(Index::dim).collect();
+        Tensor::new(random(&shape), indices).expect("Indices must be distinct")
+    }
+}
+
+/// Contracts a list of indexed tensors by matching their indices.
+///
+/// Every index that appears on two tensors is summed over, and the remaining indices become the
+/// legs of the result in order of first appearance. The network is translated into the integer
+/// convention of [`contract`] and contracted with its greedy planner.
+///
+/// # Arguments
+/// - `tensors`: A slice of [`Tensor`] values forming the network.
+///
+/// # Returns
+/// A `Result<Tensor<A>, QtnError>` where:
+/// - `Ok(Tensor<A>)` contains the contracted tensor and its free indices.
+/// - `Err(QtnError)` contains the error if the contraction fails.
+///
+/// # Errors
+/// This function may return an error if:
+/// - `tensors` is empty.
+/// - An index appears on more than two tensors.
+pub fn contract_indexed<A: Scalar>(tensors: &[Tensor<A>]) -> Result<Tensor<A>, QtnError> {
+    if tensors.is_empty() {
+        return Err(QtnError::EmptyNetwork);
+    }
+
+    let mut counts: HashMap<&Index, usize> = HashMap::new();
+    for index in tensors.iter().flat_map(|t| t.indices.iter()) {
+        *counts.entry(index).or_insert(0) += 1;
+    }
+    if let Some((index, _)) = counts.iter().find(|&(_, &count)| count > 2) {
+        return Err(QtnError::InvalidArgument(format!(
+            "Index {} (tags {:?}) appears on more than two tensors.",
+            index.id, index.tags
+        )));
+    }
+
+    let mut free = Vec::new();
+    let mut ids: HashMap<&Index, i32> = HashMap::new();
+    let mut next = 1;
+    let orders: Vec<Vec<i32>> = tensors
+        .iter()
+        .map(|t| {
+            t.indices
+                .iter()
+                .map(|index| {
+                    *ids.entry(index).or_insert_with(|| {
+                        if counts[index] == 1 {
+                            free.push(index.clone());
+                            -(free.len() as i32)
+                        } else {
+                            next += 1;
+                            next - 1
+                        }
+                    })
+                })
+                .collect()
+        })
+        .collect();
+
+    if tensors.len() == 1 {
+        return Ok(tensors[0].clone());
+    }
+
+    let order_refs: Vec<&[i32]> = orders.iter().map(|o| o.as_slice()).collect();
+    let views: Vec<ArrayViewD<A>> = tensors.iter().map(|t| t.data.view()).collect();
+    let data = contract(&views, &order_refs)?;
+
+    Ok(Tensor {
+        data: data.into_shared(),
+        indices: free,
+    })
+}
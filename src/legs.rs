@@ -0,0 +1,271 @@
+use crate::error::QtnError;
+use crate::scalar::Scalar;
+use ndarray::{ArrayBase, ArrayD, IxDyn, RawData};
+use std::fmt::Debug;
+
+/// Bookkeeping of a leg fusion, as returned by [`fuse_legs`].
+///
+/// It records which legs of the original tensor were merged into each fused leg and their
+/// dimensions, so the fusion can be undone with [`FusedLegs::unfuse`], or a single fused leg of
+/// a derived tensor, such as the row leg of the `U` factor of an SVD, can be split again with
+/// [`split_leg`] and [`FusedLegs::leg_dims`]. Records are only created by [`fuse_legs`], so the
+/// groups always partition the axes of the recorded shape.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FusedLegs {
+    groups: Vec<Vec<usize>>,
+    shape: Vec<usize>,
+}
+
+impl FusedLegs {
+    /// Returns the axes of the original tensor merged into each fused leg, in their order of
+    /// fusion.
+    pub fn groups(&self) -> &[Vec<usize>] {
+        &self.groups
+    }
+
+    /// Returns the shape of the original tensor.
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    /// Returns the dimensions of the original legs merged into a fused leg.
+    ///
+    /// # Arguments
+    /// - `leg`: The position of the fused leg.
+    ///
+    /// # Returns
+    /// A `Result<Vec<usize>, QtnError>` containing the dimensions, in the order of fusion, as
+    /// expected by [`split_leg`], or [`QtnError::InvalidArgument`] if `leg` is out of range.
+    pub fn leg_dims(&self, leg: usize) -> Result<Vec<usize>, QtnError> {
+        let group = self.groups.get(leg).ok_or_else(|| {
+            QtnError::InvalidArgument(format!(
+                "Leg {} is out of range for {} fused legs.",
+                leg,
+                self.groups.len()
+            ))
+        })?;
+        Ok(group.iter().map(|&axis| self.shape[axis]).collect())
+    }
+
+    /// Returns the shape of the fused tensor.
+    pub fn fused_shape(&self) -> Vec<usize> {
+        self.groups
+            .iter()
+            .map(|group| group.iter().map(|&axis| self.shape[axis]).product())
+            .collect()
+    }
+
+    /// Splits all fused legs and restores the original axis order.
+    ///
+    /// # Arguments
+    /// - `tensor`: A tensor with the shape of the fused tensor, e.g. the fused tensor after an
+    ///   elementwise operation.
+    ///
+    /// # Returns
+    /// A `Result<ArrayD<A>, QtnError>` containing the tensor with the original shape, or
+    /// [`QtnError::InvalidArgument`] if `tensor` does not have the fused shape.
+    pub fn unfuse<A: Scalar>(&self, tensor: &ArrayD<A>) -> Result<ArrayD<A>, QtnError> {
+        let fused_shape = self.fused_shape();
+        if tensor.shape() != fused_shape.as_slice() {
+            return Err(QtnError::InvalidArgument(format!(
+                "Tensor has shape {:?}, but the fused legs have shape {:?}.",
+                tensor.shape(),
+                fused_shape
+            )));
+        }
+
+        let order: Vec<usize> = self.groups.iter().flatten().cloned().collect();
+        let grouped_shape: Vec<usize> = order.iter().map(|&axis| self.shape[axis]).collect();
+        let mut inverse = vec![0; order.len()];
+        for (position, &axis) in order.iter().enumerate() {
+            inverse[axis] = position;
+        }
+        let grouped = tensor.to_shape(IxDyn(&grouped_shape))?;
+        Ok(grouped.permuted_axes(IxDyn(&inverse)).to_owned())
+    }
+}
+
+/// Fuses groups of legs of a tensor into single legs.
+///
+/// The legs of each group are merged in the given order, with the last one varying fastest, and
+/// the fused legs appear in the order of the groups. Fusing the legs of a tensor into two groups
+/// gives the matrix that is passed to an SVD or QR decomposition.
+///
+/// # Arguments
+/// - `tensor`: The tensor whose legs are fused.
+/// - `groups`: The axes merged into each fused leg. Every axis of `tensor` must appear in
+///   exactly one group.
+///
+/// # Returns
+/// A `Result<(ArrayD<A>, FusedLegs), QtnError>` containing the fused tensor and the
+/// bookkeeping needed to undo the fusion, or [`QtnError::InvalidArgument`] if `groups` is not a
+/// partition of the axes of `tensor`.
+pub fn fuse_legs<A: Scalar>(
+    tensor: &ArrayD<A>,
+    groups: &[Vec<usize>],
+) -> Result<(ArrayD<A>, FusedLegs), QtnError> {
+    let order: Vec<usize> = groups.iter().flatten().cloned().collect();
+    let mut sorted = order.clone();
+    sorted.sort_unstable();
+    if sorted != (0..tensor.ndim()).collect::<Vec<usize>>() {
+        return Err(QtnError::InvalidArgument(format!(
+            "Groups {:?} do not partition the {} axes of the tensor.",
+            groups,
+            tensor.ndim()
+        )));
+    }
+
+    let fused = FusedLegs {
+        groups: groups.to_vec(),
+        shape: tensor.shape().to_vec(),
+    };
+    let permuted = tensor.view().permuted_axes(IxDyn(&order));
+    let result = permuted.to_shape(IxDyn(&fused.fused_shape()))?.into_owned();
+    Ok((result, fused))
+}
+
+/// Splits a leg of a tensor into several legs.
+///
+/// This is the inverse of fusing the legs into `axis`: the new legs take its place, with the
+/// last one varying fastest.
+///
+/// # Arguments
+/// - `tensor`: The tensor whose leg is split.
+/// - `axis`: The axis to split.
+/// - `dims`: The dimensions of the new legs, whose product must be the dimension of `axis`.
+///
+/// # Returns
+/// A `Result<ArrayD<A>, QtnError>` containing the tensor with the split leg, or
+/// [`QtnError::InvalidArgument`] if `axis` is out of range or `dims` does not match its
+/// dimension.
+pub fn split_leg<A: Scalar>(
+    tensor: &ArrayD<A>,
+    axis: usize,
+    dims: &[usize],
+) -> Result<ArrayD<A>, QtnError> {
+    let shape = tensor.shape();
+    if axis >= shape.len() || dims.iter().product::<usize>() != shape[axis] {
+        return Err(QtnError::InvalidArgument(format!(
+            "Axis {} of a tensor of shape {:?} cannot be split into dimensions {:?}.",
+            axis, shape, dims
+        )));
+    }
+
+    let new_shape = [&shape[..axis], dims, &shape[axis + 1..]].concat();
+    Ok(tensor.to_shape(IxDyn(&new_shape))?.into_owned())
+}
+
+/// Permutes the axes of a tensor, checking that `perm` is a permutation of its axes.
+///
+/// Like `permuted_axes`, this only rearranges the strides, so owned tensors and views are
+/// permuted without copying.
+///
+/// # Arguments
+/// - `tensor`: The tensor, owned or as a view.
+/// - `perm`: The axis of `tensor` that becomes each axis of the result.
+///
+/// # Returns
+/// A `Result<ArrayBase<S, IxDyn>, QtnError>` containing the permuted tensor, or
+/// [`QtnError::InvalidArgument`] if `perm` is not a permutation of the axes.
+pub fn permute<S: RawData>(
+    tensor: ArrayBase<S, IxDyn>,
+    perm: &[usize],
+) -> Result<ArrayBase<S, IxDyn>, QtnError> {
+    let mut sorted = perm.to_vec();
+    sorted.sort_unstable();
+    if sorted != (0..tensor.ndim()).collect::<Vec<usize>>() {
+        return Err(QtnError::InvalidArgument(format!(
+            "{:?} is not a permutation of the {} axes of the tensor.",
+            perm,
+            tensor.ndim()
+        )));
+    }
+    Ok(tensor.permuted_axes(IxDyn(perm)))
+}
+
+/// Moves an axis of a tensor to a new position, keeping the order of the other axes.
+///
+/// # Arguments
+/// - `tensor`: The tensor, owned or as a view.
+/// - `source`: The axis to move.
+/// - `destination`: The position of the axis in the result.
+///
+/// # Returns
+/// A `Result<ArrayBase<S, IxDyn>, QtnError>` containing the tensor with the moved axis, or
+/// [`QtnError::InvalidArgument`] if `source` or `destination` is out of range.
+pub fn moveaxis<S: RawData>(
+    tensor: ArrayBase<S, IxDyn>,
+    source: usize,
+    destination: usize,
+) -> Result<ArrayBase<S, IxDyn>, QtnError> {
+    let ndim = tensor.ndim();
+    if source >= ndim || destination >= ndim {
+        return Err(QtnError::InvalidArgument(format!(
+            "Cannot move axis {} to position {} in a tensor of rank {}.",
+            source, destination, ndim
+        )));
+    }
+    let mut perm: Vec<usize> = (0..ndim).filter(|&axis| axis != source).collect();
+    perm.insert(destination, source);
+    permute(tensor, &perm)
+}
+
+/// Exchanges two axes of a tensor.
+///
+/// # Arguments
+/// - `tensor`: The tensor, owned or as a view.
+/// - `first`: The first axis.
+/// - `second`: The second axis.
+///
+/// # Returns
+/// A `Result<ArrayBase<S, IxDyn>, QtnError>` containing the tensor with the axes exchanged,
+/// or [`QtnError::InvalidArgument`] if an axis is out of range.
+pub fn swapaxes<S: RawData>(
+    tensor: ArrayBase<S, IxDyn>,
+    first: usize,
+    second: usize,
+) -> Result<ArrayBase<S, IxDyn>, QtnError> {
+    let ndim = tensor.ndim();
+    if first >= ndim || second >= ndim {
+        return Err(QtnError::InvalidArgument(format!(
+            "Cannot swap axes {} and {} of a tensor of rank {}.",
+            first, second, ndim
+        )));
+    }
+    let mut perm: Vec<usize> = (0..ndim).collect();
+    perm.swap(first, second);
+    permute(tensor, &perm)
+}
+
+/// Permutes the axes of a tensor by their labels.
+///
+/// # Arguments
+/// - `tensor`: The tensor, owned or as a view.
+/// - `labels`: The label of each axis of `tensor`, e.g. leg names or contraction indices.
+/// - `target`: The labels in the desired axis order of the result.
+///
+/// # Returns
+/// A `Result<ArrayBase<S, IxDyn>, QtnError>` containing the permuted tensor, or
+/// [`QtnError::InvalidArgument`] if `labels` does not label every axis once or `target` is
+/// not a reordering of `labels`.
+pub fn transpose_to<S: RawData, L: PartialEq + Debug>(
+    tensor: ArrayBase<S, IxDyn>,
+    labels: &[L],
+    target: &[L],
+) -> Result<ArrayBase<S, IxDyn>, QtnError> {
+    let perm: Option<Vec<usize>> = target
+        .iter()
+        .map(|label| labels.iter().position(|l| l == label))
+        .collect();
+    match perm {
+        Some(perm) if labels.len() == tensor.ndim() && target.len() == labels.len() => {
+            permute(tensor, &perm)
+        }
+        _ => Err(QtnError::InvalidArgument(format!(
+            "Labels {:?} cannot be brought into the order {:?} for a tensor of rank {}.",
+            labels,
+            target,
+            tensor.ndim()
+        ))),
+    }
+}
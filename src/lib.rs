@@ -1,4 +1,18 @@
+pub mod channel;
+pub mod diag;
+pub mod einsum;
+pub mod error;
+pub mod index;
+pub mod legs;
+pub mod linop;
+pub mod network;
+pub mod param;
+pub mod path;
+pub mod prelude;
+pub mod scalar;
+pub mod superop;
 pub mod tencon;
 pub mod tendot;
 pub mod tensor;
 pub mod trace;
+pub mod transfer;
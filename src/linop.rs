@@ -0,0 +1,829 @@
+use crate::error::QtnError;
+use crate::scalar::Scalar;
+use crate::tensor::{eig, eigh};
+use ndarray::{s, Array1, Array2, ArrayD, IxDyn};
+use ndarray_linalg::Scalar as _;
+
+/// A square linear map that is only available through its action on vectors.
+///
+/// Effective Hamiltonians, environment equations and the norm matrices of full-update schemes
+/// are usually far too large to store, but applying them to a vector is a cheap contraction.
+/// The iterative solvers of this module only need that action.
+pub trait LinearOperator<A: Scalar> {
+    /// Returns the dimension of the vectors the operator acts on.
+    fn dim(&self) -> usize;
+
+    /// Applies the operator to a vector of length [`LinearOperator::dim`].
+    fn apply(&self, x: &Array1<A>) -> Array1<A>;
+}
+
+impl<A: Scalar> LinearOperator<A> for Array2<A> {
+    fn dim(&self) -> usize {
+        self.nrows()
+    }
+
+    fn apply(&self, x: &Array1<A>) -> Array1<A> {
+        self.dot(x)
+    }
+}
+
+/// A [`LinearOperator`] defined by a closure, e.g. one contracting a vector with the tensors of
+/// an effective Hamiltonian.
+#[derive(Clone, Debug)]
+pub struct FnOperator<F> {
+    dim: usize,
+    apply: F,
+}
+
+impl<F> FnOperator<F> {
+    /// Creates an operator from its dimension and its action.
+    ///
+    /// # Arguments
+    /// - `dim`: The dimension of the vectors the operator acts on.
+    /// - `apply`: The action of the operator, returning a vector of length `dim`.
+    pub fn new(dim: usize, apply: F) -> Self {
+        FnOperator { dim, apply }
+    }
+}
+
+impl<A: Scalar, F: Fn(&Array1<A>) -> Array1<A>> LinearOperator<A> for FnOperator<F> {
+    fn dim(&self) -> usize {
+        self.dim
+    }
+
+    fn apply(&self, x: &Array1<A>) -> Array1<A> {
+        (self.apply)(x)
+    }
+}
+
+/// Solves `op x = b` with the restarted generalized minimal residual method, GMRES(m).
+///
+/// Each cycle builds an orthonormal Krylov basis of up to `restart` vectors with the Arnoldi
+/// process and picks the vector of the basis that minimizes the residual. GMRES works for any
+/// non-singular operator and its residual never increases, at the cost of storing the basis.
+///
+/// # Arguments
+/// - `op`: The [`LinearOperator`].
+/// - `b`: The right-hand side.
+/// - `x0`: The initial guess, e.g. the solution of the previous sweep step, or `None` to start
+///   from zero.
+/// - `tol`: The tolerance on the residual `||b - op x||` relative to `||b||`.
+/// - `max_iter`: The maximum number of operator applications.
+/// - `restart`: The largest dimension of the Krylov basis before the method restarts.
+///
+/// # Returns
+/// A `Result<Array1<A>, QtnError>` where:
+/// - `Ok(Array1<A>)` contains the solution.
+/// - `Err(QtnError)` contains the error if the dimensions of `b` or `x0` differ from the
+///   dimension of `op` ([`QtnError::InvalidArgument`]), or the residual does not reach `tol`
+///   within `max_iter` applications ([`QtnError::NotConverged`]).
+pub fn gmres<A, O>(
+    op: &O,
+    b: &Array1<A>,
+    x0: Option<&Array1<A>>,
+    tol: f64,
+    max_iter: usize,
+    restart: usize,
+) -> Result<Array1<A>, QtnError>
+where
+    A: Scalar,
+    O: LinearOperator<A> + ?Sized,
+{
+    let mut x = initial_guess(op, b, x0)?;
+    let target = A::real(tol) * norm(b);
+    let restart = restart.max(1);
+    let mut iterations = 0;
+
+    loop {
+        let r = b - &op.apply(&x);
+        let beta = norm(&r);
+        if beta <= target {
+            return Ok(x);
+        }
+        if iterations >= max_iter {
+            return Err(not_converged("GMRES", max_iter));
+        }
+
+        // Arnoldi process with Givens rotations keeping the Hessenberg matrix triangular
+        let mut basis = vec![r.mapv(|v| v / A::from_real(beta))];
+        let mut h: Vec<Vec<A>> = Vec::new();
+        let mut rotations: Vec<(A::Real, A)> = Vec::new();
+        let mut g = vec![A::from_real(beta)];
+
+        while basis.len() <= restart && iterations < max_iter {
+            let j = basis.len() - 1;
+            let mut w = op.apply(&basis[j]);
+            iterations += 1;
+
+            let mut column = Vec::with_capacity(j + 2);
+            for v in &basis {
+                let hij = dot(v, &w);
+                w.scaled_add(-hij, v);
+                column.push(hij);
+            }
+            let next = norm(&w);
+            column.push(A::from_real(next));
+
+            for (i, &(c, s)) in rotations.iter().enumerate() {
+                let (a, b) = (column[i], column[i + 1]);
+                column[i] = a * A::from_real(c) + s * b;
+                column[i + 1] = -s.conj() * a + b * A::from_real(c);
+            }
+            let (c, s, r) = givens(column[j], column[j + 1]);
+            column[j] = r;
+            column[j + 1] = A::zero();
+            rotations.push((c, s));
+            g.push(-s.conj() * g[j]);
+            g[j] *= A::from_real(c);
+            h.push(column);
+
+            let converged = g[j + 1].abs() <= target;
+            if converged || next == A::real(0.0) {
+                break;
+            }
+            basis.push(w.mapv(|v| v / A::from_real(next)));
+        }
+
+        // Back substitution for the coefficients of the basis vectors
+        let k = h.len();
+        let mut y = vec![A::zero(); k];
+        for i in (0..k).rev() {
+            let mut sum = g[i];
+            for l in i + 1..k {
+                sum -= h[l][i] * y[l];
+            }
+            y[i] = sum / h[i][i];
+        }
+        for (yi, v) in y.iter().zip(&basis) {
+            x.scaled_add(*yi, v);
+        }
+    }
+}
+
+/// Solves `op x = b` with the stabilized biconjugate gradient method, BiCGSTAB.
+///
+/// BiCGSTAB needs two operator applications per iteration and a fixed amount of memory, which
+/// makes it the method of choice for very large operators. Its residual is not monotonic and
+/// it may break down; [`gmres`] is the robust alternative.
+///
+/// # Arguments
+/// - `op`: The [`LinearOperator`].
+/// - `b`: The right-hand side.
+/// - `x0`: The initial guess, or `None` to start from zero.
+/// - `tol`: The tolerance on the residual `||b - op x||` relative to `||b||`.
+/// - `max_iter`: The maximum number of iterations.
+///
+/// # Returns
+/// A `Result<Array1<A>, QtnError>` where:
+/// - `Ok(Array1<A>)` contains the solution.
+/// - `Err(QtnError)` contains the error if the dimensions of `b` or `x0` differ from the
+///   dimension of `op` ([`QtnError::InvalidArgument`]), or the method breaks down or does not
+///   reach `tol` within `max_iter` iterations ([`QtnError::NotConverged`]).
+pub fn bicgstab<A, O>(
+    op: &O,
+    b: &Array1<A>,
+    x0: Option<&Array1<A>>,
+    tol: f64,
+    max_iter: usize,
+) -> Result<Array1<A>, QtnError>
+where
+    A: Scalar,
+    O: LinearOperator<A> + ?Sized,
+{
+    let mut x = initial_guess(op, b, x0)?;
+    let target = A::real(tol) * norm(b);
+
+    let mut r = b - &op.apply(&x);
+    if norm(&r) <= target {
+        return Ok(x);
+    }
+    let shadow = r.clone();
+    let mut p = r.clone();
+    let mut rho = dot(&shadow, &r);
+
+    for _ in 0..max_iter {
+        let v = op.apply(&p);
+        let denominator = dot(&shadow, &v);
+        if denominator == A::zero() {
+            break;
+        }
+        let alpha = rho / denominator;
+        let s = &r - &v.mapv(|e| e * alpha);
+        if norm(&s) <= target {
+            x.scaled_add(alpha, &p);
+            return Ok(x);
+        }
+
+        let t = op.apply(&s);
+        let tt = dot(&t, &t);
+        if tt == A::zero() {
+            break;
+        }
+        let omega = dot(&t, &s) / tt;
+        x.scaled_add(alpha, &p);
+        x.scaled_add(omega, &s);
+        r = &s - &t.mapv(|e| e * omega);
+        if norm(&r) <= target {
+            return Ok(x);
+        }
+
+        let next = dot(&shadow, &r);
+        if next == A::zero() || omega == A::zero() {
+            break;
+        }
+        let beta = (next / rho) * (alpha / omega);
+        rho = next;
+        p = &r + &(&p - &v.mapv(|e| e * omega)).mapv(|e| e * beta);
+    }
+
+    Err(not_converged("BiCGSTAB", max_iter))
+}
+
+/// Computes the lowest eigenpairs of a Hermitian operator with the Lanczos method.
+///
+/// The operator is only accessed through `matvec`, which acts on tensors of the shape of
+/// `guess`, so effective Hamiltonians that exist as a network of tensors, as in DMRG, are
+/// diagonalized without ever building the matrix. The Krylov basis is fully re-orthogonalized
+/// in every step, which prevents the spurious copies of converged eigenvalues that plain
+/// Lanczos produces in finite precision.
+///
+/// A single Krylov sequence only sees the component of `guess` in each eigenspace, so a
+/// degenerate eigenvalue is generally returned once. If the Krylov space becomes invariant, e.g.
+/// for a `guess` with a symmetry of the operator, it is extended by a new orthogonal direction,
+/// so eigenvectors orthogonal to that space are found as well.
+///
+/// # Arguments
+/// - `matvec`: The action of the Hermitian operator on a tensor of the shape of `guess`.
+/// - `guess`: The initial vector, e.g. the ground state of the previous sweep step. It must
+///   have a non-zero overlap with the wanted eigenvectors.
+/// - `k`: The number of eigenpairs to compute.
+/// - `tol`: The tolerance on the residual norm `||H x - lambda x||` of every eigenpair.
+/// - `max_iter`: The largest dimension of the Krylov basis, i.e. the maximum number of `matvec`
+///   applications. The basis is stored, so this also bounds the memory of the method.
+///
+/// # Returns
+/// A `Result<(Array1<A::Real>, Vec<ArrayD<A>>), QtnError>` where:
+/// - `Ok((Array1<A::Real>, Vec<ArrayD<A>>))` contains the `k` lowest eigenvalues in ascending
+///   order and the normalized eigenvectors, with the shape of `guess`.
+/// - `Err(QtnError)` contains the error if `k` is zero or larger than the size of `guess` or
+///   `max_iter`, if `guess` is zero or `matvec` changes the shape
+///   ([`QtnError::InvalidArgument`]), if the eigendecomposition of the projected matrix fails
+///   ([`QtnError::LinalgFailure`]), or if the residuals do not reach `tol` within `max_iter`
+///   applications ([`QtnError::NotConverged`], reporting the residuals).
+pub fn lanczos<A, F>(
+    matvec: F,
+    guess: &ArrayD<A>,
+    k: usize,
+    tol: f64,
+    max_iter: usize,
+) -> Result<Eigenpairs<A>, QtnError>
+where
+    A: Scalar,
+    F: Fn(&ArrayD<A>) -> ArrayD<A>,
+{
+    let shape = guess.shape().to_vec();
+    let n = guess.len();
+    let start = krylov_start(guess, k, "Lanczos")?;
+    check_krylov_size(k, max_iter)?;
+
+    let tol = A::real(tol);
+    let mut basis = vec![start];
+    let mut alphas: Vec<A::Real> = Vec::new();
+    let mut betas: Vec<A::Real> = Vec::new();
+
+    loop {
+        let j = basis.len() - 1;
+        let mut w = apply_map(&matvec, &basis[j], &shape)?;
+
+        // Orthogonalizing twice against the whole basis keeps it orthonormal to working precision
+        let mut alpha = A::real(0.0);
+        for _ in 0..2 {
+            for (l, v) in basis.iter().enumerate() {
+                let c = dot(v, &w);
+                w.scaled_add(-c, v);
+                if l == j {
+                    alpha += c.re();
+                }
+            }
+        }
+        alphas.push(alpha);
+        let beta = norm(&w);
+
+        // An invariant subspace may miss eigenvectors orthogonal to it, so it is always extended
+        let m = basis.len();
+        if m >= k && (beta > tol || m == n || m >= max_iter) {
+            let (theta, y) = eigh(&tridiagonal::<A>(&alphas, &betas))?;
+            let residuals: Vec<A::Real> = (0..k).map(|i| beta * y[[m - 1, i]].abs()).collect();
+            if residuals.iter().all(|&r| r <= tol) || m == n {
+                let vectors = (0..k)
+                    .map(|i| {
+                        let mut x = Array1::zeros(n);
+                        for (v, &c) in basis.iter().zip(y.column(i)) {
+                            x.scaled_add(c, v);
+                        }
+                        x.into_shape_with_order(IxDyn(&shape))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                return Ok((theta.slice_move(s![..k]), vectors));
+            }
+            if m >= max_iter {
+                return Err(krylov_not_converged("Lanczos", max_iter, &residuals));
+            }
+        }
+
+        if beta > tol {
+            basis.push(w.mapv(|x| x / A::from_real(beta)));
+            betas.push(beta);
+        } else {
+            basis.push(fresh_direction(&basis));
+            betas.push(A::real(0.0));
+        }
+    }
+}
+
+/// Computes the lowest eigenpairs of a Hermitian operator with the locally optimal block
+/// preconditioned conjugate gradient method (LOBPCG).
+///
+/// Unlike [`lanczos`], LOBPCG iterates a whole block of vectors, one per wanted eigenpair, so
+/// degenerate ground states and clusters of excited states are resolved as well as isolated
+/// eigenvalues. Every iteration applies `matvec` once per vector of the block and minimizes the
+/// Rayleigh quotient over the block, its preconditioned residuals and the previous search
+/// directions.
+///
+/// # Arguments
+/// - `matvec`: The action of the Hermitian operator on a tensor of the shape of the guesses.
+/// - `guess`: The initial block, one tensor per wanted eigenpair. The tensors must have equal
+///   shapes and be linearly independent.
+/// - `preconditioner`: An approximation of the inverse of the shifted operator applied to the
+///   residuals, e.g. the inverse of its diagonal, or `None` to use the residuals as they are.
+/// - `tol`: The tolerance on the residual norm `||H x - lambda x||` of every eigenpair.
+/// - `max_iter`: The maximum number of iterations.
+///
+/// # Returns
+/// A `Result<(Array1<A::Real>, Vec<ArrayD<A>>), QtnError>` where:
+/// - `Ok((Array1<A::Real>, Vec<ArrayD<A>>))` contains the lowest eigenvalues in ascending order
+///   and the orthonormal eigenvectors, with the shape of the guesses.
+/// - `Err(QtnError)` contains the error if `guess` is empty, has tensors of different shapes or
+///   linearly dependent tensors, or if `matvec` or the preconditioner changes the shape
+///   ([`QtnError::InvalidArgument`]), if an eigendecomposition fails
+///   ([`QtnError::LinalgFailure`]), or if the residuals do not reach `tol` within `max_iter`
+///   iterations ([`QtnError::NotConverged`]).
+pub fn lobpcg<A, F>(
+    matvec: F,
+    guess: &[ArrayD<A>],
+    preconditioner: Option<&TensorMap<'_, A>>,
+    tol: f64,
+    max_iter: usize,
+) -> Result<Eigenpairs<A>, QtnError>
+where
+    A: Scalar,
+    F: Fn(&ArrayD<A>) -> ArrayD<A>,
+{
+    let shape = guess
+        .first()
+        .ok_or_else(|| QtnError::InvalidArgument("LOBPCG needs an initial block.".to_string()))?
+        .shape()
+        .to_vec();
+    if guess.iter().any(|g| g.shape() != shape.as_slice()) {
+        return Err(QtnError::InvalidArgument(
+            "The initial tensors of LOBPCG have different shapes.".to_string(),
+        ));
+    }
+    let k = guess.len();
+    let tol = A::real(tol);
+
+    // Orthonormal initial block, rotated onto its Ritz vectors
+    let mut x: Vec<Array1<A>> = guess.iter().map(|g| g.iter().cloned().collect()).collect();
+    let mut ax = x
+        .iter()
+        .map(|v| apply_map(&matvec, v, &shape))
+        .collect::<Result<Vec<_>, _>>()?;
+    orthonormalize(&mut x, &mut ax, 0);
+    if x.len() < k {
+        return Err(QtnError::InvalidArgument(
+            "The initial tensors of LOBPCG are linearly dependent.".to_string(),
+        ));
+    }
+    let (mut theta, c) = rayleigh_ritz(&x, &ax, k)?;
+    x = combine(&x, &c);
+    ax = combine(&ax, &c);
+    let mut p: Vec<Array1<A>> = Vec::new();
+    let mut ap: Vec<Array1<A>> = Vec::new();
+
+    for _ in 0..max_iter {
+        let residuals: Vec<Array1<A>> = x
+            .iter()
+            .zip(&ax)
+            .zip(&theta)
+            .map(|((v, av), &t)| av - &v.mapv(|e| e * A::from_real(t)))
+            .collect();
+        if residuals.iter().all(|r| norm(r) <= tol) {
+            let vectors = x
+                .into_iter()
+                .map(|v| v.into_shape_with_order(IxDyn(&shape)))
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok((theta, vectors));
+        }
+
+        let mut w = match preconditioner {
+            Some(precondition) => residuals
+                .iter()
+                .map(|r| apply_map(precondition, r, &shape))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => residuals,
+        };
+        let mut aw = w
+            .iter()
+            .map(|v| apply_map(&matvec, v, &shape))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Rayleigh-Ritz on the span of the block, the residuals and the previous directions
+        let mut basis: Vec<Array1<A>> = x.iter().cloned().chain(w.drain(..)).chain(p).collect();
+        let mut images: Vec<Array1<A>> = ax.iter().cloned().chain(aw.drain(..)).chain(ap).collect();
+        orthonormalize(&mut basis, &mut images, k);
+        let (values, c) = rayleigh_ritz(&basis, &images, k)?;
+        theta = values;
+
+        let directions = c.slice(s![k.., ..]).to_owned();
+        p = combine(&basis[k..], &directions);
+        ap = combine(&images[k..], &directions);
+        x = combine(&basis, &c);
+        ax = combine(&images, &c);
+    }
+
+    Err(not_converged("LOBPCG", max_iter))
+}
+
+/// The eigenvalues targeted by [`arnoldi`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Which {
+    /// The eigenvalues of largest modulus, e.g. the dominant eigenvalue of a transfer matrix.
+    #[default]
+    LargestMagnitude,
+    /// The eigenvalues of largest real part, e.g. the steady state and slowest decay modes of a
+    /// Liouvillian.
+    LargestReal,
+}
+
+/// Computes eigenpairs of a general, non-Hermitian operator with the Arnoldi method.
+///
+/// This is the counterpart of [`lanczos`] for operators such as MPS transfer matrices and
+/// Liouvillians, whose eigenvalues are complex in general. The operator is only accessed
+/// through `matvec`; the orthonormal Krylov basis is re-orthogonalized in every step and the
+/// eigenvalues of the projected upper Hessenberg matrix approximate those of the operator. For
+/// linear systems with such operators, see [`gmres`] together with [`FnOperator`].
+///
+/// # Arguments
+/// - `matvec`: The action of the operator on a tensor of the shape of `guess`.
+/// - `guess`: The initial vector. It must have a non-zero overlap with the wanted eigenvectors.
+/// - `k`: The number of eigenpairs to compute.
+/// - `which`: The part of the spectrum to compute, see [`Which`].
+/// - `tol`: The tolerance on the residual norm `||A x - lambda x||` of every eigenpair.
+/// - `max_iter`: The largest dimension of the Krylov basis, i.e. the maximum number of `matvec`
+///   applications. The basis and the projected matrix are stored, so this also bounds the
+///   memory and the cost of the projected eigendecompositions.
+///
+/// # Returns
+/// A `Result<(Array1<A::Complex>, Vec<ArrayD<A::Complex>>), QtnError>` where:
+/// - `Ok((Array1<A::Complex>, Vec<ArrayD<A::Complex>>))` contains the `k` eigenvalues, sorted by
+///   decreasing modulus or real part as selected by `which`, and the normalized right
+///   eigenvectors, with the shape of `guess`.
+/// - `Err(QtnError)` contains the error if `k` is zero or larger than the size of `guess` or
+///   `max_iter`, if `guess` is zero or `matvec` changes the shape
+///   ([`QtnError::InvalidArgument`]), if the eigendecomposition of the projected matrix fails
+///   ([`QtnError::LinalgFailure`]), or if the residuals do not reach `tol` within `max_iter`
+///   applications ([`QtnError::NotConverged`], reporting the residuals).
+pub fn arnoldi<A, F>(
+    matvec: F,
+    guess: &ArrayD<A>,
+    k: usize,
+    which: Which,
+    tol: f64,
+    max_iter: usize,
+) -> Result<ComplexEigenpairs<A>, QtnError>
+where
+    A: Scalar,
+    F: Fn(&ArrayD<A>) -> ArrayD<A>,
+{
+    let shape = guess.shape().to_vec();
+    let n = guess.len();
+    let start = krylov_start(guess, k, "Arnoldi")?;
+    check_krylov_size(k, max_iter)?;
+
+    let tol = A::real(tol);
+    let mut basis = vec![start];
+    let mut hessenberg: Vec<Vec<A>> = Vec::new();
+
+    loop {
+        let j = basis.len() - 1;
+        let mut w = apply_map(&matvec, &basis[j], &shape)?;
+
+        let mut column = vec![A::zero(); j + 2];
+        for _ in 0..2 {
+            for (l, v) in basis.iter().enumerate() {
+                let c = dot(v, &w);
+                w.scaled_add(-c, v);
+                column[l] += c;
+            }
+        }
+        let beta = norm(&w);
+        column[j + 1] = A::from_real(beta);
+        hessenberg.push(column);
+
+        let m = basis.len();
+        if m >= k && (beta > tol || m == n || m >= max_iter) {
+            let h = Array2::from_shape_fn((m, m), |(r, c)| {
+                hessenberg[c].get(r).cloned().unwrap_or_else(A::zero)
+            });
+            let (theta, y) = eig(&h)?;
+
+            let mut order: Vec<usize> = (0..m).collect();
+            if which == Which::LargestReal {
+                order.sort_by(|&a, &b| A::total_cmp_real(&theta[b].re(), &theta[a].re()));
+            }
+            let wanted = &order[..k];
+
+            // The residual of a Ritz pair is beta times the last component of its unit vector
+            let length = |i: usize| {
+                let squares = y
+                    .column(i)
+                    .iter()
+                    .fold(A::real(0.0), |acc, x| acc + x.square());
+                ndarray_linalg::Scalar::sqrt(squares)
+            };
+            let residuals: Vec<A::Real> = wanted
+                .iter()
+                .map(|&i| beta * y[[m - 1, i]].abs() / length(i))
+                .collect();
+            if residuals.iter().all(|&r| r <= tol) || m == n {
+                let vectors = wanted
+                    .iter()
+                    .map(|&i| {
+                        let scale = length(i);
+                        let mut x = Array1::zeros(n);
+                        for (v, &c) in basis.iter().zip(y.column(i)) {
+                            let c = c.div_real(scale);
+                            x.zip_mut_with(v, |x, &v| *x += v.as_c() * c);
+                        }
+                        x.into_shape_with_order(IxDyn(&shape))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                return Ok((wanted.iter().map(|&i| theta[i]).collect(), vectors));
+            }
+            if m >= max_iter {
+                return Err(krylov_not_converged("Arnoldi", max_iter, &residuals));
+            }
+        }
+
+        if beta > tol {
+            basis.push(w.mapv(|x| x / A::from_real(beta)));
+        } else {
+            basis.push(fresh_direction(&basis));
+            hessenberg[j][j + 1] = A::zero();
+        }
+    }
+}
+
+/// A linear map acting on tensors, such as the preconditioner of [`lobpcg`].
+type TensorMap<'a, A> = dyn Fn(&ArrayD<A>) -> ArrayD<A> + 'a;
+
+/// Eigenvalues and eigenvectors found by [`lanczos`].
+type Eigenpairs<A> = (Array1<<A as ndarray_linalg::Scalar>::Real>, Vec<ArrayD<A>>);
+
+/// Complex eigenvalues and eigenvectors found by [`arnoldi`].
+type ComplexEigenpairs<A> = (
+    Array1<<A as ndarray_linalg::Scalar>::Complex>,
+    Vec<ArrayD<<A as ndarray_linalg::Scalar>::Complex>>,
+);
+
+/// Checks the dimensions of a linear system and returns the initial guess.
+fn initial_guess<A, O>(op: &O, b: &Array1<A>, x0: Option<&Array1<A>>) -> Result<Array1<A>, QtnError>
+where
+    A: Scalar,
+    O: LinearOperator<A> + ?Sized,
+{
+    if b.len() != op.dim() || x0.is_some_and(|x| x.len() != op.dim()) {
+        return Err(QtnError::InvalidArgument(format!(
+            "Vectors of length {} and {:?} do not match an operator of dimension {}.",
+            b.len(),
+            x0.map(|x| x.len()),
+            op.dim()
+        )));
+    }
+    Ok(x0.cloned().unwrap_or_else(|| Array1::zeros(op.dim())))
+}
+
+/// Checks the number of wanted eigenpairs and returns the normalized initial vector of a
+/// Krylov eigensolver.
+fn krylov_start<A: Scalar>(
+    guess: &ArrayD<A>,
+    k: usize,
+    method: &str,
+) -> Result<Array1<A>, QtnError> {
+    if k == 0 || k > guess.len() {
+        return Err(QtnError::InvalidArgument(format!(
+            "Cannot compute {} eigenpairs of an operator of dimension {}.",
+            k,
+            guess.len()
+        )));
+    }
+    let start: Array1<A> = guess.iter().cloned().collect();
+    let start_norm = norm(&start);
+    if start_norm == A::real(0.0) {
+        return Err(QtnError::InvalidArgument(format!(
+            "The initial vector of {} is zero.",
+            method
+        )));
+    }
+    Ok(start.mapv(|x| x / A::from_real(start_norm)))
+}
+
+/// Checks that a Krylov basis of at most `max_iter` vectors can hold `k` eigenpairs.
+fn check_krylov_size(k: usize, max_iter: usize) -> Result<(), QtnError> {
+    if max_iter < k {
+        return Err(QtnError::InvalidArgument(format!(
+            "Cannot compute {} eigenpairs from a Krylov basis of at most {} vectors.",
+            k, max_iter
+        )));
+    }
+    Ok(())
+}
+
+/// Reports a Krylov eigensolver that did not converge, with the residuals of the wanted pairs.
+fn krylov_not_converged<R: std::fmt::Display>(
+    method: &str,
+    max_iter: usize,
+    residuals: &[R],
+) -> QtnError {
+    let residuals: Vec<String> = residuals.iter().map(|r| r.to_string()).collect();
+    QtnError::NotConverged(format!(
+        "{} did not converge within {} iterations (residuals {}).",
+        method,
+        max_iter,
+        residuals.join(", ")
+    ))
+}
+
+/// Applies a map between tensors of the given shape to a flattened vector.
+fn apply_map<A, F>(matvec: &F, v: &Array1<A>, shape: &[usize]) -> Result<Array1<A>, QtnError>
+where
+    A: Scalar,
+    F: Fn(&ArrayD<A>) -> ArrayD<A> + ?Sized,
+{
+    let output = matvec(&v.clone().into_shape_with_order(IxDyn(shape))?);
+    if output.shape() != shape {
+        return Err(QtnError::InvalidArgument(format!(
+            "Operator maps a tensor of shape {:?} to shape {:?}.",
+            shape,
+            output.shape()
+        )));
+    }
+    Ok(output.iter().cloned().collect())
+}
+
+/// Orthonormalizes vectors by Gram-Schmidt, applying the same operations to their images.
+///
+/// The first `fixed` vectors are assumed to be orthonormal already. Vectors that are linearly
+/// dependent on the previous ones are dropped together with their images.
+fn orthonormalize<A: Scalar>(
+    vectors: &mut Vec<Array1<A>>,
+    images: &mut Vec<Array1<A>>,
+    fixed: usize,
+) {
+    let mut kept = fixed;
+    for i in fixed..vectors.len() {
+        let (mut v, mut av) = (vectors[i].clone(), images[i].clone());
+        let original = norm(&v);
+        for _ in 0..2 {
+            for l in 0..kept {
+                let c = dot(&vectors[l], &v);
+                v.scaled_add(-c, &vectors[l]);
+                av.scaled_add(-c, &images[l]);
+            }
+        }
+        let length = norm(&v);
+        if length > A::real(1e-10) * original {
+            vectors[kept] = v.mapv(|e| e / A::from_real(length));
+            images[kept] = av.mapv(|e| e / A::from_real(length));
+            kept += 1;
+        }
+    }
+    vectors.truncate(kept);
+    images.truncate(kept);
+}
+
+/// Returns the `k` lowest eigenvalues of the operator projected onto an orthonormal basis,
+/// together with the coefficients of the Ritz vectors as the columns of a matrix.
+fn rayleigh_ritz<A: Scalar>(
+    basis: &[Array1<A>],
+    images: &[Array1<A>],
+    k: usize,
+) -> Result<(Array1<A::Real>, Array2<A>), QtnError> {
+    let m = basis.len();
+    let projected = Array2::from_shape_fn((m, m), |(r, c)| {
+        // Symmetrized, so rounding errors cannot make the projection non-Hermitian
+        (dot(&basis[r], &images[c]) + dot(&images[r], &basis[c])) / A::from_real(A::real(2.0))
+    });
+    let (values, vectors) = eigh(&projected)?;
+    Ok((values.slice_move(s![..k]), vectors.slice_move(s![.., ..k])))
+}
+
+/// Returns the linear combinations `sum_l c[l, i] vectors[l]` for every column `i` of `c`.
+fn combine<A: Scalar>(vectors: &[Array1<A>], c: &Array2<A>) -> Vec<Array1<A>> {
+    c.columns()
+        .into_iter()
+        .map(|column| {
+            let mut x = Array1::zeros(vectors[0].len());
+            for (v, &coefficient) in vectors.iter().zip(column) {
+                x.scaled_add(coefficient, v);
+            }
+            x
+        })
+        .collect()
+}
+
+/// Returns the inner product `<a, b>`, conjugating `a`.
+fn dot<A: Scalar>(a: &Array1<A>, b: &Array1<A>) -> A {
+    a.iter()
+        .zip(b)
+        .fold(A::zero(), |acc, (&x, &y)| acc + x.conj() * y)
+}
+
+/// Returns the Euclidean norm of a vector.
+fn norm<A: Scalar>(a: &Array1<A>) -> A::Real {
+    ndarray_linalg::Scalar::sqrt(a.iter().fold(A::real(0.0), |acc, x| acc + x.square()))
+}
+
+/// Returns the rotation `(c, s)` mapping `(a, b)` to `(r, 0)`, together with `r`.
+fn givens<A: Scalar>(a: A, b: A) -> (A::Real, A, A) {
+    let zero = A::real(0.0);
+    if b.abs() == zero {
+        return (A::real(1.0), A::zero(), a);
+    }
+    if a.abs() == zero {
+        return (
+            zero,
+            b.conj() / A::from_real(b.abs()),
+            A::from_real(b.abs()),
+        );
+    }
+    let r = ndarray_linalg::Scalar::sqrt(a.square() + b.square());
+    let phase = a / A::from_real(a.abs());
+    (
+        a.abs() / r,
+        phase * b.conj() / A::from_real(r),
+        phase * A::from_real(r),
+    )
+}
+
+/// Returns the real symmetric tridiagonal matrix with the given diagonal and off-diagonal.
+fn tridiagonal<A: Scalar>(alphas: &[A::Real], betas: &[A::Real]) -> Array2<A> {
+    let m = alphas.len();
+    let mut t = Array2::zeros((m, m));
+    for (i, &alpha) in alphas.iter().enumerate() {
+        t[[i, i]] = A::from_real(alpha);
+    }
+    for (i, &beta) in betas.iter().enumerate().take(m - 1) {
+        t[[i, i + 1]] = A::from_real(beta);
+        t[[i + 1, i]] = A::from_real(beta);
+    }
+    t
+}
+
+/// Returns a unit vector orthogonal to an orthonormal basis that does not span the whole space.
+///
+/// The unit vectors of the standard basis are projected onto the orthogonal complement in turn.
+/// Their squared projections sum to the dimension of the complement, so one of them keeps at
+/// least half of the average and is accepted.
+fn fresh_direction<A: Scalar>(basis: &[Array1<A>]) -> Array1<A> {
+    let n = basis[0].len();
+    let threshold = A::real(0.5 * (n - basis.len()) as f64 / n as f64);
+    let mut best = (A::real(0.0), Array1::zeros(n));
+    for i in 0..n {
+        let mut e = Array1::zeros(n);
+        e[i] = A::one();
+        for _ in 0..2 {
+            for v in basis {
+                let c = dot(v, &e);
+                e.scaled_add(-c, v);
+            }
+        }
+        let length = norm(&e);
+        if length > best.0 {
+            best = (length, e);
+        }
+        if best.0 * best.0 >= threshold {
+            break;
+        }
+    }
+    let (length, e) = best;
+    e.mapv(|x| x / A::from_real(length))
+}
+
+/// Returns the error of an iterative method that ran out of iterations.
+fn not_converged(method: &str, max_iter: usize) -> QtnError {
+    QtnError::NotConverged(format!(
+        "{} did not converge within {} iterations.",
+        method, max_iter
+    ))
+}
@@ -0,0 +1,870 @@
+use crate::error::QtnError;
+use crate::scalar::Scalar;
+use crate::tencon::{contract, indices_validation, rank_validation, shape_validation};
+use crate::tensor::{svd_to_error, svd_truncated, SVDResult};
+use ndarray::{Array2, ArrayD, Axis, IxDyn};
+use std::collections::HashMap;
+
+/// Canonical description of the topology of a network.
+///
+/// Two networks have the same signature exactly when one is obtained from the other by
+/// reordering the tensors and renaming the contracted (positive) indices. The shapes, the axis
+/// each leg is attached to and the labels of the free (negative) legs are part of the signature,
+/// so networks with equal signatures contract to results of the same shape and leg order, and
+/// to the same result whenever the corresponding tensors are equal.
+///
+/// The signature implements `Hash` and `Eq`, so it can key a map of contraction results shared
+/// between identical subnetworks, e.g. the repeated layers of a circuit or the columns of a
+/// translation-invariant lattice.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NetworkSignature(Vec<i64>);
+
+/// A leg of a tensor, as seen from the tensor it belongs to.
+#[derive(Clone, Copy, Debug)]
+enum Leg {
+    /// A free leg with its (negative) label.
+    Free(i32),
+    /// A leg traced with another axis of the same tensor.
+    Trace(usize),
+    /// A leg contracted with the given axis of another tensor.
+    Bond(usize, usize),
+}
+
+/// Computes the signature of a network.
+///
+/// # Arguments
+/// - `shapes`: The shapes of the tensors in the network.
+/// - `contraction_order`: The contraction indices of each tensor, following the convention of
+///   [`crate::tencon::contract`].
+///
+/// # Returns
+/// A `Result<NetworkSignature, QtnError>` containing the signature, or an error under the same
+/// conditions as [`canonicalize`].
+pub fn signature(
+    shapes: &[Vec<usize>],
+    contraction_order: &[&[i32]],
+) -> Result<NetworkSignature, QtnError> {
+    Ok(canonicalize(shapes, contraction_order)?.0)
+}
+
+/// Brings a network into canonical form.
+///
+/// The tensors are first partitioned by colour refinement: every tensor starts with a colour
+/// given by its shape and its free and traced legs, and is repeatedly recoloured by the colours
+/// of its neighbours on each axis until the partition is stable. Tensors that still share a
+/// colour are told apart by trying each of them as the first of its class and keeping the
+/// ordering with the smallest description. The search only branches on tensors that refinement
+/// cannot distinguish; for typical lattices and circuits fixing one tensor distinguishes all
+/// others, while networks made of many interchangeable, disconnected copies of the same
+/// component are expensive.
+///
+/// # Arguments
+/// - `shapes`: The shapes of the tensors in the network.
+/// - `contraction_order`: The contraction indices of each tensor, following the convention of
+///   [`crate::tencon::contract`].
+///
+/// # Returns
+/// A `Result<(NetworkSignature, Vec<usize>), QtnError>` where:
+/// - `Ok((NetworkSignature, Vec<usize>))` contains the signature and the canonical ordering of
+///   the tensors: entry `k` is the index of the tensor at canonical position `k`.
+/// - `Err(QtnError)` contains the error if the network is invalid.
+///
+/// # Errors
+/// This function may return an error if:
+/// - The network has no tensors ([`QtnError::EmptyNetwork`]).
+/// - The number of shapes differs from the number of index lists
+///   ([`QtnError::OrderCountMismatch`]), or a shape does not match its index list
+///   ([`QtnError::RankMismatch`]).
+/// - An index is zero or appears the wrong number of times
+///   ([`QtnError::InvalidContractionOrder`]).
+/// - Two legs sharing a positive index have different dimensions ([`QtnError::ShapeMismatch`]).
+pub fn canonicalize(
+    shapes: &[Vec<usize>],
+    contraction_order: &[&[i32]],
+) -> Result<(NetworkSignature, Vec<usize>), QtnError> {
+    let order: Vec<Vec<i32>> = contraction_order.iter().map(|o| o.to_vec()).collect();
+    rank_validation(shapes, &order)?;
+    indices_validation(&order)?;
+    shape_validation(shapes, &order)?;
+
+    let legs = network_legs(&order);
+    let colors = refine(&legs, initial_colors(shapes, &legs));
+    let (encoding, perm) = search(shapes, &legs, colors);
+    Ok((NetworkSignature(encoding), perm))
+}
+
+/// Finds a correspondence between the tensors of two networks with the same topology.
+///
+/// # Arguments
+/// - `shapes`, `contraction_order`: The first network.
+/// - `other_shapes`, `other_order`: The second network.
+///
+/// # Returns
+/// A `Result<Option<Vec<usize>>, QtnError>` where:
+/// - `Ok(Some(map))` means the networks are isomorphic, and tensor `k` of the first network
+///   takes the place of tensor `map[k]` of the second.
+/// - `Ok(None)` means the networks differ in their topology, shapes or free legs.
+/// - `Err(QtnError)` contains the error if either network is invalid, as for [`canonicalize`].
+pub fn find_isomorphism(
+    shapes: &[Vec<usize>],
+    contraction_order: &[&[i32]],
+    other_shapes: &[Vec<usize>],
+    other_order: &[&[i32]],
+) -> Result<Option<Vec<usize>>, QtnError> {
+    let (signature, perm) = canonicalize(shapes, contraction_order)?;
+    let (other_signature, other_perm) = canonicalize(other_shapes, other_order)?;
+    if signature != other_signature {
+        return Ok(None);
+    }
+
+    let mut map = vec![0; perm.len()];
+    for (&k, &other) in perm.iter().zip(&other_perm) {
+        map[k] = other;
+    }
+    Ok(Some(map))
+}
+
+/// One layer of a network with repeated structure.
+///
+/// A layer is a small network in the convention of [`crate::tencon::contract`] whose free legs
+/// are split into input legs, output legs and the remaining open legs. Stacking layers connects
+/// the output legs of every layer to the input legs of the next, so a brick-wall circuit layer,
+/// a column of a transfer matrix or a Trotter step is written once and wired automatically.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layer {
+    shapes: Vec<Vec<usize>>,
+    order: Vec<Vec<i32>>,
+    inputs: Vec<i32>,
+    outputs: Vec<i32>,
+}
+
+/// A network generated from a repeated [`Layer`].
+///
+/// The tensors are listed layer by layer, so tensor `t` of layer `r` is tensor
+/// `r * layer_len + t` of the network.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayeredNetwork {
+    /// The shapes of the tensors.
+    pub shapes: Vec<Vec<usize>>,
+    /// The contraction indices of each tensor, ready for [`crate::tencon::contract`].
+    pub order: Vec<Vec<i32>>,
+}
+
+impl LayeredNetwork {
+    /// Returns the contraction order as the slice of index lists taken by the contraction
+    /// functions.
+    pub fn order(&self) -> Vec<&[i32]> {
+        self.order.iter().map(|o| o.as_slice()).collect()
+    }
+}
+
+impl Layer {
+    /// Creates a layer from its tensors and the roles of its free legs.
+    ///
+    /// # Arguments
+    /// - `shapes`: The shapes of the tensors of the layer.
+    /// - `contraction_order`: The contraction indices of each tensor, following the convention
+    ///   of [`crate::tencon::contract`]. Positive indices are bonds within the layer.
+    /// - `inputs`: The free (negative) indices joined to the previous layer, in order.
+    /// - `outputs`: The free (negative) indices joined to the next layer, in the order matching
+    ///   `inputs`. Output `k` of one layer is contracted with input `k` of the next.
+    ///
+    /// # Returns
+    /// A `Result<Layer, QtnError>` where:
+    /// - `Ok(Layer)` contains the layer definition.
+    /// - `Err(QtnError)` contains the error if the layer is invalid.
+    ///
+    /// # Errors
+    /// This function may return the same errors as [`canonicalize`] for the network of the
+    /// layer, and [`QtnError::InvalidArgument`] if `inputs` and `outputs` differ in length, list
+    /// an index that is not a free index of the layer or list one twice, or if an output leg
+    /// has a different dimension than the matching input leg.
+    pub fn new(
+        shapes: &[Vec<usize>],
+        contraction_order: &[&[i32]],
+        inputs: &[i32],
+        outputs: &[i32],
+    ) -> Result<Self, QtnError> {
+        let order: Vec<Vec<i32>> = contraction_order.iter().map(|o| o.to_vec()).collect();
+        rank_validation(shapes, &order)?;
+        indices_validation(&order)?;
+        shape_validation(shapes, &order)?;
+
+        if inputs.len() != outputs.len() {
+            return Err(QtnError::InvalidArgument(format!(
+                "Layer has {} inputs but {} outputs.",
+                inputs.len(),
+                outputs.len()
+            )));
+        }
+        let dims: HashMap<i32, usize> = order
+            .iter()
+            .zip(shapes)
+            .flat_map(|(o, s)| o.iter().cloned().zip(s.iter().cloned()))
+            .filter(|&(index, _)| index < 0)
+            .collect();
+        let mut seen = Vec::new();
+        for &index in inputs.iter().chain(outputs) {
+            if !dims.contains_key(&index) || seen.contains(&index) {
+                return Err(QtnError::InvalidArgument(format!(
+                    "Index {} is not a distinct free index of the layer.",
+                    index
+                )));
+            }
+            seen.push(index);
+        }
+        for (&input, &output) in inputs.iter().zip(outputs) {
+            if dims[&input] != dims[&output] {
+                return Err(QtnError::InvalidArgument(format!(
+                    "Output {} has dimension {}, but input {} has dimension {}.",
+                    output, dims[&output], input, dims[&input]
+                )));
+            }
+        }
+
+        Ok(Layer {
+            shapes: shapes.to_vec(),
+            order,
+            inputs: inputs.to_vec(),
+            outputs: outputs.to_vec(),
+        })
+    }
+
+    /// Returns the number of tensors in the layer.
+    pub fn len(&self) -> usize {
+        self.shapes.len()
+    }
+
+    /// Returns `true` if the layer has no tensors, which [`Layer::new`] does not allow.
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty()
+    }
+
+    /// Stacks copies of the layer, joining the outputs of each layer to the inputs of the next.
+    ///
+    /// The bonds within each copy and between consecutive copies receive fresh positive
+    /// indices. The free legs of the network are numbered `-1, -2, ...` in the following order:
+    /// the inputs of the first layer, the other open legs of every layer (layer by layer, each
+    /// in the order `-1, -2, ...` of the layer definition) and the outputs of the last layer.
+    ///
+    /// # Arguments
+    /// - `count`: The number of layers, at least one.
+    ///
+    /// # Returns
+    /// A `Result<LayeredNetwork, QtnError>` containing the network, or
+    /// [`QtnError::InvalidArgument`] if `count` is zero.
+    pub fn repeat(&self, count: usize) -> Result<LayeredNetwork, QtnError> {
+        self.stack(count, false)
+    }
+
+    /// Stacks copies of the layer and also joins the outputs of the last layer to the inputs of
+    /// the first, as in the trace of a product of transfer matrices or a periodic lattice.
+    ///
+    /// The free legs are numbered as in [`Layer::repeat`], without inputs and outputs.
+    ///
+    /// # Arguments
+    /// - `count`: The number of layers, at least one.
+    ///
+    /// # Returns
+    /// A `Result<LayeredNetwork, QtnError>` containing the network, or
+    /// [`QtnError::InvalidArgument`] if `count` is zero.
+    pub fn periodic(&self, count: usize) -> Result<LayeredNetwork, QtnError> {
+        self.stack(count, true)
+    }
+
+    /// Generates the wiring of `count` stacked layers.
+    fn stack(&self, count: usize, periodic: bool) -> Result<LayeredNetwork, QtnError> {
+        if count == 0 {
+            return Err(QtnError::InvalidArgument(
+                "A layered network needs at least one layer.".to_string(),
+            ));
+        }
+
+        let mut bonds: Vec<i32> = self
+            .order
+            .iter()
+            .flatten()
+            .cloned()
+            .filter(|&i| i > 0)
+            .collect();
+        bonds.sort_unstable();
+        bonds.dedup();
+        let mut open: Vec<i32> = self
+            .order
+            .iter()
+            .flatten()
+            .cloned()
+            .filter(|i| *i < 0 && !self.inputs.contains(i) && !self.outputs.contains(i))
+            .collect();
+        open.sort_unstable_by(|a, b| b.cmp(a));
+
+        // Positive labels: the bonds of every layer, then the links between consecutive layers
+        let links = self.inputs.len() as i32;
+        let inner = bonds.len() as i32;
+        let link = |r: usize, k: usize| inner * count as i32 + links * r as i32 + k as i32 + 1;
+
+        let mut free = 0;
+        let mut next_free = || {
+            free -= 1;
+            free
+        };
+        let input_labels: Vec<i32> = (0..self.inputs.len())
+            .map(|k| {
+                if periodic {
+                    link(count - 1, k)
+                } else {
+                    next_free()
+                }
+            })
+            .collect();
+        let open_labels: Vec<Vec<i32>> = (0..count)
+            .map(|_| open.iter().map(|_| next_free()).collect())
+            .collect();
+        let output_labels: Vec<i32> = (0..self.outputs.len())
+            .map(|k| {
+                if periodic {
+                    link(count - 1, k)
+                } else {
+                    next_free()
+                }
+            })
+            .collect();
+
+        let mut order = Vec::with_capacity(count * self.len());
+        for (r, layer_open) in open_labels.iter().enumerate() {
+            let relabel = |index: i32| {
+                if index > 0 {
+                    let b = bonds.binary_search(&index).expect("Bonds are listed") as i32;
+                    return inner * r as i32 + b + 1;
+                }
+                if let Some(k) = self.inputs.iter().position(|&i| i == index) {
+                    return if r == 0 {
+                        input_labels[k]
+                    } else {
+                        link(r - 1, k)
+                    };
+                }
+                if let Some(k) = self.outputs.iter().position(|&i| i == index) {
+                    return if r + 1 == count {
+                        output_labels[k]
+                    } else {
+                        link(r, k)
+                    };
+                }
+                let k = open
+                    .iter()
+                    .position(|&i| i == index)
+                    .expect("Open legs are listed");
+                layer_open[k]
+            };
+            order.extend(
+                self.order
+                    .iter()
+                    .map(|o| o.iter().map(|&index| relabel(index)).collect::<Vec<i32>>()),
+            );
+        }
+
+        Ok(LayeredNetwork {
+            shapes: (0..count).flat_map(|_| self.shapes.clone()).collect(),
+            order,
+        })
+    }
+}
+
+/// A network of tensors that can be modified node by node.
+///
+/// Every node is a tensor together with its contraction indices, following the convention of
+/// [`crate::tencon::contract`]. Besides contracting the whole network, a region of nodes can be
+/// replaced by its contraction, a node can be split in two by an SVD, and legs can be rewired.
+/// These are the steps of coarse-graining schemes and of the construction of environments.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TensorNetwork<A: Scalar = f64> {
+    tensors: Vec<ArrayD<A>>,
+    order: Vec<Vec<i32>>,
+}
+
+impl<A: Scalar> Default for TensorNetwork<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Scalar> TensorNetwork<A> {
+    /// Creates an empty network.
+    pub fn new() -> Self {
+        TensorNetwork {
+            tensors: Vec::new(),
+            order: Vec::new(),
+        }
+    }
+
+    /// Adds a node to the network.
+    ///
+    /// # Arguments
+    /// - `tensor`: The tensor of the node.
+    /// - `indices`: The contraction index of every axis of `tensor`.
+    ///
+    /// # Returns
+    /// A `Result<usize, QtnError>` containing the position of the new node, or an error under
+    /// the same conditions as [`TensorNetwork::rewire`] for any of its legs, and
+    /// [`QtnError::RankMismatch`] if the number of indices differs from the rank of `tensor`.
+    pub fn add(&mut self, tensor: ArrayD<A>, indices: &[i32]) -> Result<usize, QtnError> {
+        if tensor.ndim() != indices.len() {
+            return Err(QtnError::RankMismatch {
+                tensor: self.len(),
+                rank: tensor.ndim(),
+                order_len: indices.len(),
+            });
+        }
+        self.tensors.push(tensor);
+        self.order.push(vec![0; indices.len()]);
+        let node = self.len() - 1;
+
+        for (axis, &index) in indices.iter().enumerate() {
+            if let Err(err) = self.rewire(node, axis, index) {
+                self.tensors.pop();
+                self.order.pop();
+                return Err(err);
+            }
+        }
+        Ok(node)
+    }
+
+    /// Returns the number of nodes.
+    pub fn len(&self) -> usize {
+        self.tensors.len()
+    }
+
+    /// Returns `true` if the network has no nodes.
+    pub fn is_empty(&self) -> bool {
+        self.tensors.is_empty()
+    }
+
+    /// Returns the tensors of the nodes.
+    pub fn tensors(&self) -> &[ArrayD<A>] {
+        &self.tensors
+    }
+
+    /// Returns the contraction indices of the nodes, as taken by the contraction functions.
+    pub fn order(&self) -> Vec<&[i32]> {
+        self.order.iter().map(|o| o.as_slice()).collect()
+    }
+
+    /// Contracts the whole network.
+    ///
+    /// # Returns
+    /// A `Result<ArrayD<A>, QtnError>` containing the result, or the same errors as
+    /// [`crate::tencon::contract`].
+    pub fn contract(&self) -> Result<ArrayD<A>, QtnError> {
+        contract(&self.tensors, &self.order())
+    }
+
+    /// Replaces a region of nodes by the result of contracting it.
+    ///
+    /// The legs of the new node are the legs of the region that are not contracted within it,
+    /// in the order in which they appear on the nodes of the region. The new node takes the
+    /// position of the first node of the region, and the other nodes of the region are removed,
+    /// so the positions of the nodes after them decrease.
+    ///
+    /// # Arguments
+    /// - `nodes`: The positions of the nodes forming the region.
+    ///
+    /// # Returns
+    /// A `Result<usize, QtnError>` containing the position of the new node, or an error if the
+    /// region lists a missing node or a node twice ([`QtnError::InvalidArgument`]), or if the
+    /// region cannot be contracted (as for [`crate::tencon::contract`]).
+    pub fn contract_region(&mut self, nodes: &[usize]) -> Result<usize, QtnError> {
+        let mut region = nodes.to_vec();
+        region.sort_unstable();
+        region.dedup();
+        if region.len() != nodes.len() || region.iter().any(|&k| k >= self.len()) {
+            return Err(QtnError::InvalidArgument(format!(
+                "Region {:?} does not list distinct nodes of a network of {} nodes.",
+                nodes,
+                self.len()
+            )));
+        }
+
+        // Legs leaving the region become the free legs -1, -2, ... of the subnetwork
+        let labels: Vec<i32> = nodes.iter().flat_map(|&k| self.order[k].clone()).collect();
+        let open: Vec<i32> = labels
+            .iter()
+            .cloned()
+            .filter(|&l| l < 0 || labels.iter().filter(|&&x| x == l).count() == 1)
+            .collect();
+        let order: Vec<Vec<i32>> = nodes
+            .iter()
+            .map(|&k| {
+                self.order[k]
+                    .iter()
+                    .map(|l| match open.iter().position(|x| x == l) {
+                        Some(p) => -(p as i32) - 1,
+                        None => *l,
+                    })
+                    .collect()
+            })
+            .collect();
+        let tensors: Vec<_> = nodes.iter().map(|&k| self.tensors[k].view()).collect();
+        let refs: Vec<&[i32]> = order.iter().map(|o| o.as_slice()).collect();
+        let result = contract(&tensors, &refs)?;
+
+        let first = region[0];
+        self.tensors[first] = result;
+        self.order[first] = open;
+        for &k in region[1..].iter().rev() {
+            self.tensors.remove(k);
+            self.order.remove(k);
+        }
+        Ok(first)
+    }
+
+    /// Splits a node in two with a singular value decomposition.
+    ///
+    /// The tensor is reshaped into a matrix with the legs in `left_axes` as rows and the
+    /// remaining legs as columns, and factorized as `U (S V^T)`. The node keeps the isometry
+    /// `U` on its left legs followed by the new bond, and a new node holding `S V^T`, with the
+    /// new bond followed by the right legs, is appended to the network. At most `max_bond`
+    /// singular values are kept, so the split is exact only if the bond is not truncated.
+    ///
+    /// # Arguments
+    /// - `node`: The position of the node to split.
+    /// - `left_axes`: The axes of the node that stay on the left factor, in their new order.
+    /// - `max_bond`: The largest dimension of the new bond, or `None` to keep all singular
+    ///   values.
+    ///
+    /// # Returns
+    /// A `Result<(usize, i32), QtnError>` containing the position of the new node and the index
+    /// of the new bond, or an error if `node` or `left_axes` is invalid
+    /// ([`QtnError::InvalidArgument`]) or the SVD fails ([`QtnError::LinalgFailure`]).
+    pub fn split(
+        &mut self,
+        node: usize,
+        left_axes: &[usize],
+        max_bond: Option<usize>,
+    ) -> Result<(usize, i32), QtnError> {
+        let (new, index, _) = self.split_with(node, left_axes, |matrix| {
+            svd_truncated(matrix, max_bond, 0.0, false)
+        })?;
+        Ok((new, index))
+    }
+
+    /// Splits a node in two, with a bond just large enough to meet a target truncation error.
+    ///
+    /// This is [`TensorNetwork::split`] with the bond dimension chosen by
+    /// [`svd_to_error`]: the fewest singular values are kept whose discarded weight stays below
+    /// `target_error`, but never more than `max_bond`.
+    ///
+    /// # Arguments
+    /// - `node`: The position of the node to split.
+    /// - `left_axes`: The axes of the node that stay on the left factor, in their new order.
+    /// - `target_error`: The largest acceptable discarded weight, relative to the squared norm
+    ///   of the node.
+    /// - `max_bond`: The hard cap on the dimension of the new bond, or `None` for no cap.
+    ///
+    /// # Returns
+    /// A `Result<(usize, i32, A::Real), QtnError>` containing the position of the new node, the
+    /// index of the new bond and the discarded weight, or the errors of
+    /// [`TensorNetwork::split`].
+    pub fn split_to_error(
+        &mut self,
+        node: usize,
+        left_axes: &[usize],
+        target_error: f64,
+        max_bond: Option<usize>,
+    ) -> Result<(usize, i32, A::Real), QtnError> {
+        self.split_with(node, left_axes, |matrix| {
+            svd_to_error(matrix, target_error, max_bond, false)
+        })
+    }
+
+    /// Splits a node in two with the given truncated SVD, see [`TensorNetwork::split`].
+    fn split_with<F>(
+        &mut self,
+        node: usize,
+        left_axes: &[usize],
+        decompose: F,
+    ) -> Result<(usize, i32, A::Real), QtnError>
+    where
+        F: FnOnce(Array2<A>) -> Result<(SVDResult<A>, A::Real), QtnError>,
+    {
+        let rank = self.tensors.get(node).map(|t| t.ndim()).ok_or_else(|| {
+            QtnError::InvalidArgument(format!(
+                "Node {} is missing in a network of {} nodes.",
+                node,
+                self.len()
+            ))
+        })?;
+        let mut seen = left_axes.to_vec();
+        seen.sort_unstable();
+        seen.dedup();
+        if seen.len() != left_axes.len() || seen.iter().any(|&a| a >= rank) {
+            return Err(QtnError::InvalidArgument(format!(
+                "Axes {:?} are not distinct axes of node {} of rank {}.",
+                left_axes, node, rank
+            )));
+        }
+
+        let right_axes: Vec<usize> = (0..rank).filter(|a| !left_axes.contains(a)).collect();
+        let tensor = &self.tensors[node];
+        let left_shape: Vec<usize> = left_axes.iter().map(|&a| tensor.shape()[a]).collect();
+        let right_shape: Vec<usize> = right_axes.iter().map(|&a| tensor.shape()[a]).collect();
+        let (m, n): (usize, usize) = (left_shape.iter().product(), right_shape.iter().product());
+
+        let perm: Vec<usize> = left_axes.iter().chain(&right_axes).cloned().collect();
+        let matrix = tensor
+            .view()
+            .permuted_axes(IxDyn(&perm))
+            .to_shape((m, n))?
+            .into_owned();
+        let (result, discarded) = decompose(matrix)?;
+
+        let bond = result.sigma.len();
+        let u = result.u;
+        let mut svt = result.vt;
+        for (mut row, &s) in svt.axis_iter_mut(Axis(0)).zip(&result.sigma) {
+            row.mapv_inplace(|x| x * A::from_real(s));
+        }
+
+        let index = self
+            .order
+            .iter()
+            .flatten()
+            .cloned()
+            .max()
+            .unwrap_or(0)
+            .max(0)
+            + 1;
+        let left_order = left_axes.iter().map(|&a| self.order[node][a]);
+        let right_order = right_axes.iter().map(|&a| self.order[node][a]);
+        let right_order: Vec<i32> = std::iter::once(index).chain(right_order).collect();
+        let left_order: Vec<i32> = left_order.chain(std::iter::once(index)).collect();
+
+        self.tensors[node] = u
+            .to_shape(IxDyn(&[left_shape, vec![bond]].concat()))?
+            .into_owned();
+        self.order[node] = left_order;
+        self.tensors.push(
+            svt.to_shape(IxDyn(&[vec![bond], right_shape].concat()))?
+                .into_owned(),
+        );
+        self.order.push(right_order);
+        Ok((self.len() - 1, index, discarded))
+    }
+
+    /// Attaches a leg of a node to another index.
+    ///
+    /// Giving two legs the same positive index connects them, and giving a leg a negative index
+    /// makes it a free leg of the network. A leg that was connected before leaves its former
+    /// partner unconnected until that leg is rewired as well.
+    ///
+    /// # Arguments
+    /// - `node`: The position of the node.
+    /// - `axis`: The axis of the leg.
+    /// - `index`: The new index of the leg.
+    ///
+    /// # Returns
+    /// A `Result<(), QtnError>` where:
+    /// - `Ok(())` indicates that the leg was rewired.
+    /// - `Err(QtnError)` contains the error if the leg cannot be rewired.
+    ///
+    /// # Errors
+    /// This function may return an error if:
+    /// - `node` or `axis` does not exist ([`QtnError::InvalidArgument`]).
+    /// - `index` is zero, or already used by two other legs (or one, if it is negative)
+    ///   ([`QtnError::InvalidContractionOrder`]).
+    /// - `index` is positive and the leg it joins has a different dimension
+    ///   ([`QtnError::ShapeMismatch`]).
+    pub fn rewire(&mut self, node: usize, axis: usize, index: i32) -> Result<(), QtnError> {
+        if node >= self.len() || axis >= self.order[node].len() {
+            return Err(QtnError::InvalidArgument(format!(
+                "Node {} has no axis {}.",
+                node, axis
+            )));
+        }
+
+        let others: Vec<(usize, usize)> = (0..self.len())
+            .flat_map(|k| (0..self.order[k].len()).map(move |a| (k, a)))
+            .filter(|&(k, a)| (k, a) != (node, axis) && self.order[k][a] == index)
+            .collect();
+        let allowed = if index > 0 { 1 } else { 0 };
+        if index == 0 || others.len() > allowed {
+            return Err(QtnError::InvalidContractionOrder {
+                index,
+                count: others.len() + 1,
+            });
+        }
+        if let Some(&(other, other_axis)) = others.first() {
+            let dim = self.tensors[node].shape()[axis];
+            let other_dim = self.tensors[other].shape()[other_axis];
+            if dim != other_dim {
+                return Err(QtnError::ShapeMismatch {
+                    tensor: node,
+                    axis,
+                    dim,
+                    other_tensor: other,
+                    other_axis,
+                    other_dim,
+                });
+            }
+        }
+
+        self.order[node][axis] = index;
+        Ok(())
+    }
+}
+
+/// Resolves the indices of a valid contraction order into the legs of every tensor.
+fn network_legs(order: &[Vec<i32>]) -> Vec<Vec<Leg>> {
+    let mut ends: HashMap<i32, Vec<(usize, usize)>> = HashMap::new();
+    for (k, indices) in order.iter().enumerate() {
+        for (axis, &index) in indices.iter().enumerate() {
+            ends.entry(index).or_default().push((k, axis));
+        }
+    }
+
+    order
+        .iter()
+        .enumerate()
+        .map(|(k, indices)| {
+            indices
+                .iter()
+                .enumerate()
+                .map(|(axis, &index)| {
+                    if index < 0 {
+                        return Leg::Free(index);
+                    }
+                    let &(t, b) = ends[&index]
+                        .iter()
+                        .find(|&&end| end != (k, axis))
+                        .expect("Positive indices appear twice");
+                    if t == k {
+                        Leg::Trace(b)
+                    } else {
+                        Leg::Bond(t, b)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Colours the tensors by their shape and their free and traced legs.
+fn initial_colors(shapes: &[Vec<usize>], legs: &[Vec<Leg>]) -> Vec<usize> {
+    let keys: Vec<Vec<i64>> = shapes
+        .iter()
+        .zip(legs)
+        .map(|(shape, tensor_legs)| {
+            let mut key = vec![shape.len() as i64];
+            key.extend(shape.iter().map(|&d| d as i64));
+            for leg in tensor_legs {
+                key.extend(match *leg {
+                    Leg::Free(label) => [0, label as i64],
+                    Leg::Trace(b) => [1, b as i64],
+                    Leg::Bond(_, b) => [2, b as i64],
+                });
+            }
+            key
+        })
+        .collect();
+    rank(&keys)
+}
+
+/// Refines a colouring by the colours of the neighbours of every tensor until it is stable.
+fn refine(legs: &[Vec<Leg>], mut colors: Vec<usize>) -> Vec<usize> {
+    let mut classes = count_classes(&colors);
+    loop {
+        let keys: Vec<Vec<i64>> = legs
+            .iter()
+            .enumerate()
+            .map(|(k, tensor_legs)| {
+                let neighbours = tensor_legs.iter().map(|leg| match *leg {
+                    Leg::Bond(t, _) => colors[t] as i64,
+                    _ => -1,
+                });
+                std::iter::once(colors[k] as i64)
+                    .chain(neighbours)
+                    .collect()
+            })
+            .collect();
+        let refined = rank(&keys);
+        let refined_classes = count_classes(&refined);
+
+        // The refined partition is never coarser, so an unchanged count means it is stable
+        if refined_classes == classes {
+            return refined;
+        }
+        colors = refined;
+        classes = refined_classes;
+    }
+}
+
+/// Returns the smallest description of the network over all orderings compatible with the
+/// colouring, together with the ordering that produces it.
+fn search(shapes: &[Vec<usize>], legs: &[Vec<Leg>], colors: Vec<usize>) -> (Vec<i64>, Vec<usize>) {
+    // The first colour class with more than one tensor is the one to break up
+    let mut sizes: HashMap<usize, usize> = HashMap::new();
+    for &c in &colors {
+        *sizes.entry(c).or_default() += 1;
+    }
+    let cell = sizes.iter().filter(|&(_, &n)| n > 1).map(|(&c, _)| c).min();
+
+    let Some(cell) = cell else {
+        let mut perm: Vec<usize> = (0..colors.len()).collect();
+        perm.sort_by_key(|&k| colors[k]);
+        return (encode(shapes, legs, &perm), perm);
+    };
+
+    (0..colors.len())
+        .filter(|&v| colors[v] == cell)
+        .map(|v| {
+            // `v` comes first within its class, the others after it
+            let individualized = colors
+                .iter()
+                .enumerate()
+                .map(|(k, &c)| 2 * c + usize::from(c == cell && k != v))
+                .collect();
+            search(shapes, legs, refine(legs, individualized))
+        })
+        .min()
+        .expect("A colour class is not empty")
+}
+
+/// Describes the network with the tensors in the given order.
+fn encode(shapes: &[Vec<usize>], legs: &[Vec<Leg>], perm: &[usize]) -> Vec<i64> {
+    let mut position = vec![0; perm.len()];
+    for (p, &k) in perm.iter().enumerate() {
+        position[k] = p;
+    }
+
+    let mut encoding = Vec::new();
+    for &k in perm {
+        encoding.push(shapes[k].len() as i64);
+        encoding.extend(shapes[k].iter().map(|&d| d as i64));
+        for leg in &legs[k] {
+            match *leg {
+                Leg::Free(label) => encoding.extend([0, label as i64]),
+                Leg::Trace(b) => encoding.extend([1, b as i64]),
+                Leg::Bond(t, b) => encoding.extend([2, position[t] as i64, b as i64]),
+            }
+        }
+    }
+    encoding
+}
+
+/// Replaces every key by its rank among the distinct keys.
+fn rank(keys: &[Vec<i64>]) -> Vec<usize> {
+    let mut distinct: Vec<&Vec<i64>> = keys.iter().collect();
+    distinct.sort();
+    distinct.dedup();
+    keys.iter()
+        .map(|key| distinct.binary_search(&key).expect("Every key is listed"))
+        .collect()
+}
+
+/// Returns the number of distinct colours.
+fn count_classes(colors: &[usize]) -> usize {
+    let mut distinct = colors.to_vec();
+    distinct.sort_unstable();
+    distinct.dedup();
+    distinct.len()
+}
@@ -0,0 +1,166 @@
+use crate::error::QtnError;
+use crate::scalar::Scalar;
+use crate::tencon::plan;
+use ndarray::ArrayD;
+
+/// A tensor whose entries are linear expressions in named parameters.
+///
+/// The tensor stands for `T(p) = T_0 + p_1 T_1 + p_2 T_2 + ...`, where `T_0` is the constant
+/// part and every named parameter `p_k` (e.g. a coupling constant) multiplies a coefficient
+/// tensor `T_k` of the same shape. Hamiltonians with a linear dependence on their couplings, such
+/// as `H = J H_zz + h H_x`, are written this way, so that a network containing them can be
+/// contracted once per coefficient and then evaluated for any parameter values with
+/// [`ParamTensor::evaluate`], instead of contracting the whole network again for every point of
+/// a parameter sweep.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParamTensor<A: Scalar = f64> {
+    constant: ArrayD<A>,
+    terms: Vec<(String, ArrayD<A>)>,
+}
+
+impl<A: Scalar> ParamTensor<A> {
+    /// Creates a tensor without parameter dependence.
+    ///
+    /// # Arguments
+    /// - `constant`: The constant part `T_0`, which fixes the shape of the tensor.
+    ///
+    /// # Returns
+    /// A new `ParamTensor` without parameters.
+    pub fn new(constant: ArrayD<A>) -> Self {
+        ParamTensor {
+            constant,
+            terms: Vec::new(),
+        }
+    }
+
+    /// Returns the tensor with a term linear in the given parameter added.
+    ///
+    /// If the parameter already has a term, the coefficients are summed.
+    ///
+    /// # Arguments
+    /// - `name`: The name of the parameter.
+    /// - `coefficient`: The coefficient tensor multiplying the parameter.
+    ///
+    /// # Returns
+    /// A `Result<ParamTensor<A>, QtnError>` containing the extended tensor, or
+    /// [`QtnError::InvalidArgument`] if the shape of `coefficient` differs from the shape of the
+    /// tensor.
+    pub fn with_term(mut self, name: &str, coefficient: ArrayD<A>) -> Result<Self, QtnError> {
+        if coefficient.shape() != self.constant.shape() {
+            return Err(QtnError::InvalidArgument(format!(
+                "Coefficient of '{}' has shape {:?}, but the tensor has shape {:?}.",
+                name,
+                coefficient.shape(),
+                self.constant.shape()
+            )));
+        }
+
+        match self.terms.iter_mut().find(|(n, _)| n == name) {
+            Some((_, existing)) => *existing += &coefficient,
+            None => self.terms.push((name.to_string(), coefficient)),
+        }
+        Ok(self)
+    }
+
+    /// Returns the shape of the tensor.
+    pub fn shape(&self) -> &[usize] {
+        self.constant.shape()
+    }
+
+    /// Returns the constant part `T_0`.
+    pub fn constant(&self) -> &ArrayD<A> {
+        &self.constant
+    }
+
+    /// Returns the coefficient tensor of a parameter, or `None` if the tensor does not depend on
+    /// it.
+    pub fn coefficient(&self, name: &str) -> Option<&ArrayD<A>> {
+        self.terms.iter().find(|(n, _)| n == name).map(|(_, c)| c)
+    }
+
+    /// Returns the names of the parameters, in the order in which they were added.
+    pub fn parameters(&self) -> Vec<&str> {
+        self.terms.iter().map(|(n, _)| n.as_str()).collect()
+    }
+
+    /// Returns `true` if the tensor does not depend on any parameter.
+    pub fn is_constant(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Evaluates the tensor for the given parameter values.
+    ///
+    /// # Arguments
+    /// - `values`: The value of every parameter of the tensor, as `(name, value)` pairs. Values
+    ///   of parameters the tensor does not depend on are ignored.
+    ///
+    /// # Returns
+    /// A `Result<ArrayD<A>, QtnError>` containing `T_0 + sum_k p_k T_k`, or
+    /// [`QtnError::InvalidArgument`] if a parameter of the tensor has no value.
+    pub fn evaluate(&self, values: &[(&str, A)]) -> Result<ArrayD<A>, QtnError> {
+        let mut result = self.constant.clone();
+        for (name, coefficient) in &self.terms {
+            let value = values
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|&(_, v)| v)
+                .ok_or_else(|| {
+                    QtnError::InvalidArgument(format!("No value given for parameter '{}'.", name))
+                })?;
+            result.scaled_add(value, coefficient);
+        }
+        Ok(result)
+    }
+}
+
+/// Contracts a network in which the tensors may depend linearly on named parameters.
+///
+/// The contraction is planned once for the shapes of the network and executed for the constant
+/// parts and once for every coefficient tensor, with all other tensors at their constant part.
+/// The result is again a [`ParamTensor`], whose evaluation for any parameter values equals the
+/// contraction of the evaluated network.
+///
+/// Linearity is only preserved if at most one tensor of the network depends on parameters;
+/// otherwise the result would contain products of parameters.
+///
+/// # Arguments
+/// - `tensors`: A slice of [`ParamTensor`] values forming the network.
+/// - `contraction_order`: A vector of vectors containing integers that specify the order of
+///   contraction, following the same convention as [`crate::tencon::contract`].
+///
+/// # Returns
+/// A `Result<ParamTensor<A>, QtnError>` where:
+/// - `Ok(ParamTensor<A>)` contains the contracted tensor and its parameter dependence.
+/// - `Err(QtnError)` contains the error if the contraction fails.
+///
+/// # Errors
+/// This function may return the same errors as [`crate::tencon::contract`], and
+/// [`QtnError::InvalidArgument`] if more than one tensor depends on parameters.
+pub fn contract_param<A: Scalar>(
+    tensors: &[ParamTensor<A>],
+    contraction_order: &[&[i32]],
+) -> Result<ParamTensor<A>, QtnError> {
+    let parametric: Vec<usize> = (0..tensors.len())
+        .filter(|&k| !tensors[k].is_constant())
+        .collect();
+    if parametric.len() > 1 {
+        return Err(QtnError::InvalidArgument(format!(
+            "Tensors {:?} all depend on parameters, so the result is not linear in them.",
+            parametric
+        )));
+    }
+
+    let shapes: Vec<Vec<usize>> = tensors.iter().map(|t| t.shape().to_vec()).collect();
+    let plan = plan(&shapes, contraction_order)?;
+
+    let mut network: Vec<_> = tensors.iter().map(|t| t.constant.view()).collect();
+    let mut result = ParamTensor::new(plan.execute(&network)?);
+    if let Some(&k) = parametric.first() {
+        for (name, coefficient) in &tensors[k].terms {
+            network[k] = coefficient.view();
+            result.terms.push((name.clone(), plan.execute(&network)?));
+        }
+    }
+
+    Ok(result)
+}
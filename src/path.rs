@@ -0,0 +1,1047 @@
+use crate::error::QtnError;
+use crate::scalar::Scalar;
+use crate::tencon::greedy_map;
+use crate::tendot::tensor_dot;
+use ndarray::ArrayD;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::thread;
+use std::time::Instant;
+
+/// Cost model used to compare contraction paths.
+///
+/// The cost of contracting two tensors is modelled as
+/// `overhead + flop * m * k * n + element * (m * k + k * n + m * n)`, where `m` and `n` are the
+/// sizes of the free legs of the two tensors and `k` is the size of the contracted legs. The
+/// default model counts multiply-adds only. [`calibrate`] measures the three constants on the
+/// current machine, so that paths with many small contractions or large permutations are
+/// penalized according to their actual runtime.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CostModel {
+    /// Cost of one multiply-add.
+    pub flop: f64,
+    /// Fixed cost of one pairwise contraction (validation, permutation setup, allocation).
+    pub overhead: f64,
+    /// Cost of moving one element of the inputs or the output through memory.
+    pub element: f64,
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        CostModel {
+            flop: 1.0,
+            overhead: 0.0,
+            element: 0.0,
+        }
+    }
+}
+
+impl CostModel {
+    /// Estimates the cost of contracting an `m x k` matrix with a `k x n` matrix.
+    ///
+    /// # Arguments
+    /// - `m`: The product of the free dimensions of the first tensor.
+    /// - `k`: The product of the contracted dimensions.
+    /// - `n`: The product of the free dimensions of the second tensor.
+    ///
+    /// # Returns
+    /// The estimated cost in the units of the model.
+    pub fn pair_cost(&self, m: usize, k: usize, n: usize) -> f64 {
+        self.scaled_cost(m as f64, k as f64, n as f64, 1.0)
+    }
+
+    /// Estimates the cost of a pairwise contraction that only needs a fraction of the
+    /// multiply-adds of a dense GEMM.
+    fn scaled_cost(&self, m: f64, k: f64, n: f64, flop_fraction: f64) -> f64 {
+        self.overhead
+            + self.flop * flop_fraction * m * k * n
+            + self.element * (m * k + k * n + m * n)
+    }
+}
+
+/// Structure of a tensor that the planner and the executor can exploit.
+///
+/// `Diagonal` and `Identity` describe square matrices. They are exploited when they are
+/// contracted with another tensor over exactly one of their two legs, which is how bond weights
+/// and identities appear in networks; contracted over both legs they are treated as dense.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TensorStructure {
+    /// No structure, every entry may be nonzero.
+    #[default]
+    Dense,
+    /// A square matrix whose only nonzero entries lie on its diagonal, such as the bond weights
+    /// of a canonical form. Contracting it rescales the other tensor instead of calling a GEMM.
+    Diagonal,
+    /// The identity matrix. Contracting it only relabels a leg of the other tensor, and its
+    /// entries are never read.
+    Identity,
+    /// A tensor with the given fraction of nonzero entries. The planner scales the multiply-adds
+    /// of its contractions by `density`, but the contraction itself is executed densely.
+    Sparse {
+        /// The fraction of nonzero entries, in `(0, 1]`.
+        density: f64,
+    },
+}
+
+/// Kernel used to execute a pairwise contraction of an `m x k` and a `k x n` matrix.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum PairKernel {
+    /// A dense matrix product.
+    Gemm,
+    /// The second operand is a diagonal `k x k` matrix that rescales the columns of the first.
+    ScaleColumns,
+    /// The first operand is a diagonal `k x k` matrix that rescales the rows of the second.
+    ScaleRows,
+    /// The second operand is the identity, the product is the first operand.
+    Left,
+    /// The first operand is the identity, the product is the second operand.
+    Right,
+}
+
+/// Chooses the kernel of a pairwise contraction from the structure of its operands.
+///
+/// The sizes are given as floating-point numbers, so the planner can weigh intermediates whose
+/// number of elements would overflow `usize`.
+///
+/// # Returns
+/// The kernel, the fraction of the multiply-adds of a dense GEMM it performs, and the structure
+/// of the result.
+pub(crate) fn pair_kernel(
+    a: TensorStructure,
+    b: TensorStructure,
+    (m, k, n): (f64, f64, f64),
+) -> (PairKernel, f64, TensorStructure) {
+    use TensorStructure::*;
+
+    // A square matrix contracted over one leg has as many free as contracted elements
+    let absorbed_a = matches!(a, Diagonal | Identity) && m == k;
+    let absorbed_b = matches!(b, Diagonal | Identity) && k == n;
+
+    match (a, b) {
+        (_, Identity) if absorbed_b => (PairKernel::Left, 0.0, a),
+        (Identity, _) if absorbed_a => (PairKernel::Right, 0.0, b),
+        (Diagonal, Diagonal) if absorbed_a && absorbed_b => {
+            (PairKernel::ScaleColumns, 1.0 / k, Diagonal)
+        }
+        (Sparse { .. }, Diagonal) if absorbed_b => (PairKernel::ScaleColumns, 1.0 / k, a),
+        (_, Diagonal) if absorbed_b => (PairKernel::ScaleColumns, 1.0 / k, Dense),
+        (Diagonal, Sparse { .. }) if absorbed_a => (PairKernel::ScaleRows, 1.0 / k, b),
+        (Diagonal, _) if absorbed_a => (PairKernel::ScaleRows, 1.0 / k, Dense),
+        _ => {
+            let density = |s| match s {
+                Sparse { density } => density,
+                _ => 1.0,
+            };
+            (PairKernel::Gemm, density(a).min(density(b)), Dense)
+        }
+    }
+}
+
+/// Strategy used to choose the sequence of pairwise contractions of a network.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Optimizer {
+    /// The ratio-based greedy planner of [`crate::tencon::contract_map`].
+    #[default]
+    Greedy,
+    /// Repeated greedy searches with randomly perturbed choices, keeping the cheapest path.
+    ///
+    /// Each trial contracts, at every step, the pair that minimizes the size of the result minus
+    /// the sizes of its inputs, after adding Gumbel noise scaled by `temperature` to the scores.
+    /// The first trial runs without noise. Trials are spread over the available threads and the
+    /// path with the lowest estimated cost under the [`CostModel`] is returned. With the same
+    /// `seed`, the result does not depend on the number of threads.
+    RandomGreedy {
+        /// The number of greedy searches to run. At least one search is always run.
+        trials: usize,
+        /// The strength of the perturbation. Zero gives a single deterministic greedy search.
+        temperature: f64,
+        /// The seed of the random number generators of the trials.
+        seed: u64,
+    },
+    /// Depth-first search over contraction sequences, pruned by the best cost found so far.
+    ///
+    /// The search starts from the greedy path as the initial bound and tries the candidate pairs
+    /// of each step in order of their greedy score, abandoning any partial path that is already
+    /// more expensive than the best complete one. It returns the optimal path under the
+    /// [`CostModel`] when the search finishes within `max_nodes` expanded partial paths, and the
+    /// best path found so far otherwise.
+    BranchAndBound {
+        /// The maximum number of partial paths to expand before the search stops.
+        max_nodes: usize,
+    },
+    /// The greedy path, refined by reconfiguring its subtrees.
+    ///
+    /// Starting from the path of a deterministic cost-based greedy search, the contraction tree
+    /// is improved locally: for every intermediate tensor, the subtree below it is cut at up to
+    /// `subtree_size` tensors, the cheapest way to contract those tensors is found by
+    /// branch-and-bound, and the subtree is replaced if the whole tree becomes cheaper. Sweeps
+    /// over the tree are repeated until no subtree improves or `rounds` sweeps have run. Each
+    /// reconfiguration is cheap, so this scales to networks far beyond the reach of
+    /// [`Optimizer::BranchAndBound`], and removes the large intermediates that greedy searches
+    /// tend to create late in the path. See [`reconfigure_path`] to refine any other path.
+    Reconfigure {
+        /// The largest number of tensors in a reconfigured subtree, at least three.
+        subtree_size: usize,
+        /// The maximum number of sweeps over the tree.
+        rounds: usize,
+    },
+    /// A path supplied by the caller, in the format returned by
+    /// [`crate::tencon::contract_map`], e.g. one read with [`from_opt_einsum`].
+    Path(Vec<Vec<usize>>),
+}
+
+/// Finds a contraction path for a network without touching any tensor data.
+///
+/// # Arguments
+/// - `shapes`: The shapes of the tensors in the network.
+/// - `contraction_order`: The contraction indices of each tensor, following the convention of
+///   [`crate::tencon::contract`].
+/// - `optimizer`: The [`Optimizer`] used to search for the path.
+/// - `model`: The [`CostModel`] used to compare candidate paths.
+///
+/// # Returns
+/// A `Result<Vec<Vec<usize>>, QtnError>` containing the path in the format returned by
+/// [`crate::tencon::contract_map`], or an error if the number of shapes differs from the number
+/// of index lists ([`QtnError::OrderCountMismatch`]) or a shape does not match its index list
+/// ([`QtnError::RankMismatch`]).
+pub fn optimize_path(
+    shapes: &[Vec<usize>],
+    contraction_order: &[&[i32]],
+    optimizer: &Optimizer,
+    model: &CostModel,
+) -> Result<Vec<Vec<usize>>, QtnError> {
+    optimize_structured_path(shapes, contraction_order, &[], optimizer, model)
+}
+
+/// Finds a contraction path for a network whose tensors may be diagonal, identities or sparse.
+///
+/// The cost-based optimizers ([`Optimizer::RandomGreedy`] and [`Optimizer::BranchAndBound`])
+/// price the contractions of structured tensors by the work of their specialized kernels, so
+/// they prefer to absorb diagonal and identity matrices into their neighbours as reweightings
+/// or relabelings. The ratio-based [`Optimizer::Greedy`] ignores the structure.
+///
+/// # Arguments
+/// - `shapes`: The shapes of the tensors in the network.
+/// - `contraction_order`: The contraction indices of each tensor, following the convention of
+///   [`crate::tencon::contract`].
+/// - `structure`: The [`TensorStructure`] of each tensor, or an empty slice if all tensors are
+///   dense. `Diagonal` and `Identity` tensors must be square matrices.
+/// - `optimizer`: The [`Optimizer`] used to search for the path.
+/// - `model`: The [`CostModel`] used to compare candidate paths.
+///
+/// # Returns
+/// A `Result<Vec<Vec<usize>>, QtnError>` containing the path, or an error under the same
+/// conditions as [`optimize_path`], and [`QtnError::InvalidArgument`] if `structure` does not
+/// describe every tensor or flags a tensor that is not a square matrix as diagonal or identity.
+pub fn optimize_structured_path(
+    shapes: &[Vec<usize>],
+    contraction_order: &[&[i32]],
+    structure: &[TensorStructure],
+    optimizer: &Optimizer,
+    model: &CostModel,
+) -> Result<Vec<Vec<usize>>, QtnError> {
+    let network = Network::new(shapes, contraction_order)?.with_structure(structure)?;
+
+    match *optimizer {
+        Optimizer::Greedy => {
+            let shapes: Vec<Vec<i32>> = shapes
+                .iter()
+                .map(|s| s.iter().map(|&d| d as i32).collect())
+                .collect();
+            let orders: Vec<Vec<i32>> = contraction_order.iter().map(|o| o.to_vec()).collect();
+            Ok(greedy_map(shapes, &orders))
+        }
+        Optimizer::RandomGreedy {
+            trials,
+            temperature,
+            seed,
+        } => Ok(random_greedy(
+            &network,
+            trials.max(1),
+            temperature,
+            seed,
+            model,
+        )),
+        Optimizer::BranchAndBound { max_nodes } => Ok(branch_and_bound(&network, max_nodes, model)),
+        Optimizer::Reconfigure {
+            subtree_size,
+            rounds,
+        } => {
+            let mut rng = StdRng::seed_from_u64(0);
+            let (_, path) = greedy_trial(network.clone(), 0.0, &mut rng, model);
+            Ok(reconfigure(&network, &path, subtree_size, rounds, model))
+        }
+        Optimizer::Path(ref path) => {
+            validate_path(shapes.len(), path)?;
+            Ok(path.clone())
+        }
+    }
+}
+
+/// Measures the cost model constants on the current machine.
+///
+/// A set of pairwise contractions covering compute-bound, memory-bound and overhead-bound shapes
+/// is timed with [`tensor_dot`] for the scalar type `A`. The returned model is normalized so that
+/// `flop == 1.0`, i.e. overhead and memory traffic are expressed as equivalent multiply-adds and
+/// the costs remain comparable with the default FLOP-counting model.
+///
+/// # Arguments
+/// - `repeats`: The number of timings per shape. The fastest run is used. Must be at least one.
+///
+/// # Returns
+/// A calibrated [`CostModel`].
+pub fn calibrate<A: Scalar>(repeats: usize) -> CostModel {
+    let repeats = repeats.max(1);
+
+    // Tiny contraction: dominated by the fixed cost of a call
+    let overhead = time_pair::<A>(1, 1, 1, repeats);
+
+    // Large square GEMM: dominated by multiply-adds
+    let (m, k, n) = (192, 192, 192);
+    let flop =
+        ((time_pair::<A>(m, k, n, repeats) - overhead) / (m * k * n) as f64).max(f64::MIN_POSITIVE);
+
+    // Outer-product-like shape: few multiply-adds per element, dominated by memory traffic
+    let (m, k, n) = (512, 1, 512);
+    let element = ((time_pair::<A>(m, k, n, repeats) - overhead - flop * (m * k * n) as f64)
+        / (m * k + k * n + m * n) as f64)
+        .max(0.0);
+
+    CostModel {
+        flop: 1.0,
+        overhead: overhead / flop,
+        element: element / flop,
+    }
+}
+
+/// Estimates the cost of a contraction path without touching any tensor data.
+///
+/// # Arguments
+/// - `shapes`: The shapes of the tensors in the network.
+/// - `contraction_order`: The contraction indices of each tensor, following the convention of
+///   [`crate::tencon::contract`].
+/// - `path`: The sequence of pairwise contractions, in the format returned by
+///   [`crate::tencon::contract_map`]: each step `[i, j]` contracts the current tensors `i` and
+///   `j`, stores the result at position `i` and removes position `j`.
+/// - `model`: The [`CostModel`] used to price each step.
+///
+/// # Returns
+/// A `Result<f64, QtnError>` containing the total estimated cost, or
+/// [`QtnError::InvalidPath`] if a step refers to a missing tensor or the path does not reduce
+/// the network to a single tensor.
+pub fn path_cost(
+    shapes: &[Vec<usize>],
+    contraction_order: &[&[i32]],
+    path: &[Vec<usize>],
+    model: &CostModel,
+) -> Result<f64, QtnError> {
+    let mut network = Network::new(shapes, contraction_order)?;
+    validate_path(shapes.len(), path)?;
+
+    Ok(path
+        .iter()
+        .map(|pair| network.contract_pair(pair[0], pair[1], model))
+        .sum())
+}
+
+/// Refines a contraction path by reconfiguring its subtrees.
+///
+/// The path is read as a contraction tree and improved as described for
+/// [`Optimizer::Reconfigure`], so a path from any source, e.g. one read with
+/// [`from_opt_einsum`], can be post-processed. The result is never more expensive than `path`
+/// under the [`CostModel`].
+///
+/// # Arguments
+/// - `shapes`: The shapes of the tensors in the network.
+/// - `contraction_order`: The contraction indices of each tensor, following the convention of
+///   [`crate::tencon::contract`].
+/// - `path`: The path to refine, in the format returned by [`crate::tencon::contract_map`].
+/// - `subtree_size`: The largest number of tensors in a reconfigured subtree, at least three.
+/// - `rounds`: The maximum number of sweeps over the tree.
+/// - `model`: The [`CostModel`] used to compare subtrees.
+///
+/// # Returns
+/// A `Result<Vec<Vec<usize>>, QtnError>` containing the refined path, or an error under the same
+/// conditions as [`path_cost`].
+pub fn reconfigure_path(
+    shapes: &[Vec<usize>],
+    contraction_order: &[&[i32]],
+    path: &[Vec<usize>],
+    subtree_size: usize,
+    rounds: usize,
+    model: &CostModel,
+) -> Result<Vec<Vec<usize>>, QtnError> {
+    let network = Network::new(shapes, contraction_order)?;
+    validate_path(shapes.len(), path)?;
+    Ok(reconfigure(&network, path, subtree_size, rounds, model))
+}
+
+/// Converts a contraction path to the pair list format of `opt_einsum` and `cotengra`.
+///
+/// In that format, each step `(i, j)` removes tensors `i` and `j` from the list and appends the
+/// result at the end, whereas this crate stores the result at position `i`. The conversion
+/// renumbers the steps accordingly, so that a path planned here can be inspected or reused by
+/// the Python tools.
+///
+/// # Arguments
+/// - `path`: The path, in the format returned by [`crate::tencon::contract_map`].
+/// - `num_tensors`: The number of tensors in the network.
+///
+/// # Returns
+/// A `Result<String, QtnError>` containing the path written as `[(0, 1), (0, 2), ...]`, or
+/// [`QtnError::InvalidPath`] if `path` does not reduce `num_tensors` tensors to one.
+pub fn to_opt_einsum(path: &[Vec<usize>], num_tensors: usize) -> Result<String, QtnError> {
+    validate_path(num_tensors, path)?;
+
+    let mut ours: Vec<usize> = (0..num_tensors).collect();
+    let mut theirs = ours.clone();
+    let mut pairs = Vec::with_capacity(path.len());
+
+    for (step, pair) in path.iter().enumerate() {
+        let (a, b) = (ours[pair[0]], ours[pair[1]]);
+        let p = theirs.iter().position(|&t| t == a).unwrap();
+        let q = theirs.iter().position(|&t| t == b).unwrap();
+        pairs.push(format!("({}, {})", p.min(q), p.max(q)));
+
+        // Label the intermediate after the inputs, as the step number is unique
+        let result = num_tensors + step;
+        ours[pair[0]] = result;
+        ours.remove(pair[1]);
+        theirs.retain(|&t| t != a && t != b);
+        theirs.push(result);
+    }
+
+    Ok(format!("[{}]", pairs.join(", ")))
+}
+
+/// Converts a contraction path from the pair list format of `opt_einsum` and `cotengra`.
+///
+/// This is the inverse of [`to_opt_einsum`]. The returned path can be evaluated with
+/// [`path_cost`] or executed with [`Optimizer::Path`], so that paths optimized with the Python
+/// tools can be run in Rust.
+///
+/// # Arguments
+/// - `path`: The path written as `[(0, 1), (0, 2), ...]`. Square brackets are accepted for the
+///   pairs as well, and whitespace is ignored.
+/// - `num_tensors`: The number of tensors in the network.
+///
+/// # Returns
+/// A `Result<Vec<Vec<usize>>, QtnError>` where:
+/// - `Ok(Vec<Vec<usize>>)` contains the path in the format returned by
+///   [`crate::tencon::contract_map`].
+/// - `Err(QtnError)` contains the error if the path cannot be read.
+///
+/// # Errors
+/// This function may return an error if:
+/// - `path` is not a list of pairs of integers ([`QtnError::InvalidArgument`]).
+/// - A step refers to a missing tensor, or the path does not reduce the network to a single
+///   tensor ([`QtnError::InvalidPath`]).
+pub fn from_opt_einsum(path: &str, num_tensors: usize) -> Result<Vec<Vec<usize>>, QtnError> {
+    let theirs_path = parse_pair_list(path)?;
+    if theirs_path.len() + 1 != num_tensors.max(1) {
+        return Err(QtnError::InvalidPath {
+            step: theirs_path.len(),
+        });
+    }
+
+    let mut ours: Vec<usize> = (0..num_tensors).collect();
+    let mut theirs = ours.clone();
+    let mut converted = Vec::with_capacity(theirs_path.len());
+
+    for (step, pair) in theirs_path.iter().enumerate() {
+        if pair.len() != 2 || pair[0] == pair[1] || pair.iter().any(|&p| p >= theirs.len()) {
+            return Err(QtnError::InvalidPath { step });
+        }
+        let (a, b) = (theirs[pair[0]], theirs[pair[1]]);
+        let i = ours.iter().position(|&t| t == a).unwrap();
+        let j = ours.iter().position(|&t| t == b).unwrap();
+        let (i, j) = (i.min(j), i.max(j));
+        converted.push(vec![i, j]);
+
+        let result = num_tensors + step;
+        ours[i] = result;
+        ours.remove(j);
+        theirs.retain(|&t| t != a && t != b);
+        theirs.push(result);
+    }
+
+    Ok(converted)
+}
+
+/// Reads a list of integer groups such as `[(0, 1), (0, 2)]`.
+fn parse_pair_list(text: &str) -> Result<Vec<Vec<usize>>, QtnError> {
+    let invalid = || QtnError::InvalidArgument(format!("Cannot read contraction path {:?}.", text));
+    let text: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let inner = text
+        .strip_prefix('[')
+        .and_then(|t| t.strip_suffix(']'))
+        .ok_or_else(invalid)?;
+
+    let mut groups = Vec::new();
+    let mut rest = inner;
+    while !rest.is_empty() {
+        let close = match rest.chars().next() {
+            Some('(') => ')',
+            Some('[') => ']',
+            _ => return Err(invalid()),
+        };
+        let end = rest.find(close).ok_or_else(invalid)?;
+        let group = rest[1..end]
+            .split(',')
+            .filter(|n| !n.is_empty())
+            .map(|n| n.parse::<usize>().map_err(|_| invalid()))
+            .collect::<Result<Vec<usize>, QtnError>>()?;
+        groups.push(group);
+
+        rest = &rest[end + 1..];
+        rest = rest.strip_prefix(',').unwrap_or(rest);
+    }
+
+    Ok(groups)
+}
+
+/// Checks that a path contracts `num_tensors` tensors down to one, one valid pair at a time.
+fn validate_path(num_tensors: usize, path: &[Vec<usize>]) -> Result<(), QtnError> {
+    if path.len() + 1 != num_tensors.max(1) {
+        return Err(QtnError::InvalidPath { step: path.len() });
+    }
+    for (step, pair) in path.iter().enumerate() {
+        let remaining = num_tensors - step;
+        if pair.len() != 2 || pair[0] == pair[1] || pair.iter().any(|&p| p >= remaining) {
+            return Err(QtnError::InvalidPath { step });
+        }
+    }
+    Ok(())
+}
+
+/// Runs the trials of [`Optimizer::RandomGreedy`] in parallel and returns the cheapest path.
+fn random_greedy(
+    network: &Network,
+    trials: usize,
+    temperature: f64,
+    seed: u64,
+    model: &CostModel,
+) -> Vec<Vec<usize>> {
+    let threads = thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(trials);
+
+    // Ties are broken by the trial number, so the result does not depend on the scheduling
+    let best = |a: (f64, usize, Vec<Vec<usize>>), b: (f64, usize, Vec<Vec<usize>>)| {
+        if (b.0, b.1) < (a.0, a.1) {
+            b
+        } else {
+            a
+        }
+    };
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|t| {
+                scope.spawn(move || {
+                    (t..trials)
+                        .step_by(threads)
+                        .map(|trial| {
+                            let mut rng = StdRng::seed_from_u64(seed.wrapping_add(trial as u64));
+                            let noise = if trial == 0 { 0.0 } else { temperature };
+                            let (cost, path) =
+                                greedy_trial(network.clone(), noise, &mut rng, model);
+                            (cost, trial, path)
+                        })
+                        .reduce(best)
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .filter_map(|h| h.join().expect("Path search thread panicked"))
+            .reduce(best)
+            .map(|(_, _, path)| path)
+            .unwrap_or_default()
+    })
+}
+
+/// Runs one perturbed greedy search and returns its estimated cost and path.
+///
+/// The candidates and their scores are those of [`Network::candidates`]. Scores are normalized by the magnitude of the best score of the
+/// step, so `temperature` does not depend on the dimensions of the network.
+fn greedy_trial(
+    mut network: Network,
+    temperature: f64,
+    rng: &mut StdRng,
+    model: &CostModel,
+) -> (f64, Vec<Vec<usize>>) {
+    let mut path = Vec::new();
+    let mut cost = 0.0;
+
+    while network.len() > 1 {
+        let (candidates, scores) = network.candidates();
+        let lowest = scores.iter().cloned().fold(f64::INFINITY, f64::min);
+        let scale = lowest.abs().max(1.0);
+
+        let (pick, _) = candidates
+            .iter()
+            .zip(&scores)
+            .map(|(&pair, &score)| {
+                let mut key = (score - lowest) / scale;
+                if temperature > 0.0 {
+                    let u: f64 = rng.random::<f64>().max(f64::MIN_POSITIVE);
+                    key += temperature * (-u.ln()).ln();
+                }
+                (pair, key)
+            })
+            .fold(
+                ((0, 1), f64::INFINITY),
+                |acc, x| if x.1 < acc.1 { x } else { acc },
+            );
+
+        cost += network.contract_pair(pick.0, pick.1, model);
+        path.push(vec![pick.0, pick.1]);
+    }
+
+    (cost, path)
+}
+
+/// Runs the search of [`Optimizer::BranchAndBound`] and returns the cheapest path found.
+fn branch_and_bound(network: &Network, max_nodes: usize, model: &CostModel) -> Vec<Vec<usize>> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut best = greedy_trial(network.clone(), 0.0, &mut rng, model);
+    let mut nodes = 0;
+
+    bound_search(
+        network,
+        0.0,
+        &mut Vec::new(),
+        &mut best,
+        &mut nodes,
+        max_nodes,
+        model,
+    );
+    best.1
+}
+
+/// Extends a partial path by every candidate pair that keeps it cheaper than the best path.
+fn bound_search(
+    network: &Network,
+    cost: f64,
+    path: &mut Vec<Vec<usize>>,
+    best: &mut (f64, Vec<Vec<usize>>),
+    nodes: &mut usize,
+    max_nodes: usize,
+    model: &CostModel,
+) {
+    if network.len() == 1 {
+        if cost < best.0 {
+            *best = (cost, path.clone());
+        }
+        return;
+    }
+    if *nodes >= max_nodes {
+        return;
+    }
+    *nodes += 1;
+
+    // Trying the most promising pairs first tightens the bound early
+    let (candidates, scores) = network.candidates();
+    let mut ranked: Vec<_> = candidates.into_iter().zip(scores).collect();
+    ranked.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+    for ((i, j), _) in ranked {
+        let next_cost = cost + network.pair_cost(i, j, model);
+        if next_cost >= best.0 {
+            continue;
+        }
+
+        let mut next = network.clone();
+        next.contract_pair(i, j, model);
+        path.push(vec![i, j]);
+        bound_search(&next, next_cost, path, best, nodes, max_nodes, model);
+        path.pop();
+    }
+}
+
+/// Number of partial paths the search of a single subtree may expand.
+const SUBTREE_SEARCH_NODES: usize = 10_000;
+
+/// Runs the sweeps of [`Optimizer::Reconfigure`] on a path and returns the refined path.
+fn reconfigure(
+    network: &Network,
+    path: &[Vec<usize>],
+    subtree_size: usize,
+    rounds: usize,
+    model: &CostModel,
+) -> Vec<Vec<usize>> {
+    let leaves = network.len();
+    if leaves < 3 {
+        return path.to_vec();
+    }
+    let subtree_size = subtree_size.max(3);
+
+    // Internal node `leaves + s` is the result of step `s`; the root keeps its id throughout
+    let mut children = tree_children(leaves, path);
+    let (mut legs, mut structure, costs) = tree_nodes(network, &children, model);
+    let mut total: f64 = costs.iter().sum();
+
+    for _ in 0..rounds {
+        let mut improved = false;
+        for root in tree_order(leaves, &children) {
+            // Cut the subtree at its largest tensors until the frontier is full
+            let mut frontier = vec![root];
+            let mut inner = Vec::new();
+            while frontier.len() < subtree_size {
+                let size = |&&node: &&usize| network.size(legs[node].iter());
+                let Some(&largest) = frontier
+                    .iter()
+                    .filter(|&&v| v >= leaves)
+                    .max_by(|a, b| size(a).total_cmp(&size(b)))
+                else {
+                    break;
+                };
+                frontier.retain(|&v| v != largest);
+                frontier.extend(children[largest - leaves]);
+                inner.push(largest);
+            }
+            if inner.len() < 2 {
+                continue;
+            }
+
+            let sub = Network {
+                legs: frontier.iter().map(|&v| legs[v].clone()).collect(),
+                dims: network.dims.clone(),
+                structure: frontier.iter().map(|&v| structure[v]).collect(),
+            };
+            let sub_path = branch_and_bound(&sub, SUBTREE_SEARCH_NODES, model);
+
+            // Reuse the ids of the replaced nodes, with the subtree root produced last
+            let mut candidate = children.clone();
+            let mut ids = inner[1..].iter().chain(&inner[..1]);
+            let mut list = frontier;
+            for pair in &sub_path {
+                let id = *ids
+                    .next()
+                    .expect("A subtree of k tensors takes k - 1 steps");
+                candidate[id - leaves] = [list[pair[0]], list[pair[1]]];
+                list[pair[0]] = id;
+                list.remove(pair[1]);
+            }
+
+            let (candidate_legs, candidate_structure, candidate_costs) =
+                tree_nodes(network, &candidate, model);
+            let candidate_total: f64 = candidate_costs.iter().sum();
+            if candidate_total < total * (1.0 - 1e-12) {
+                children = candidate;
+                legs = candidate_legs;
+                structure = candidate_structure;
+                total = candidate_total;
+                improved = true;
+            }
+        }
+        if !improved {
+            break;
+        }
+    }
+
+    tree_path(leaves, &children)
+}
+
+/// Converts a path into the children of the internal nodes of its contraction tree.
+fn tree_children(leaves: usize, path: &[Vec<usize>]) -> Vec<[usize; 2]> {
+    let mut list: Vec<usize> = (0..leaves).collect();
+    path.iter()
+        .enumerate()
+        .map(|(s, pair)| {
+            let node = [list[pair[0]], list[pair[1]]];
+            list[pair[0]] = leaves + s;
+            list.remove(pair[1]);
+            node
+        })
+        .collect()
+}
+
+/// Returns the internal nodes of a contraction tree with every node after its children.
+fn tree_order(leaves: usize, children: &[[usize; 2]]) -> Vec<usize> {
+    let mut order = Vec::with_capacity(children.len());
+    let mut stack = vec![(leaves + children.len() - 1, false)];
+    while let Some((node, expanded)) = stack.pop() {
+        if node < leaves {
+            continue;
+        }
+        if expanded {
+            order.push(node);
+        } else {
+            stack.push((node, true));
+            stack.extend(children[node - leaves].iter().rev().map(|&c| (c, false)));
+        }
+    }
+    order
+}
+
+/// Converts a contraction tree back into a path, contracting the nodes in [`tree_order`].
+fn tree_path(leaves: usize, children: &[[usize; 2]]) -> Vec<Vec<usize>> {
+    let mut list: Vec<usize> = (0..leaves).collect();
+    let position = |list: &[usize], node| list.iter().position(|&v| v == node).unwrap();
+    tree_order(leaves, children)
+        .into_iter()
+        .map(|node| {
+            let [a, b] = children[node - leaves];
+            let (i, j) = (position(&list, a), position(&list, b));
+            list[i] = node;
+            list.remove(j);
+            vec![i, j]
+        })
+        .collect()
+}
+
+/// Returns the legs and the structure of every node of a contraction tree, and the cost of
+/// every internal node.
+fn tree_nodes(network: &Network, children: &[[usize; 2]], model: &CostModel) -> TreeNodes {
+    let leaves = network.len();
+    let mut legs = network.legs.clone();
+    legs.resize(leaves + children.len(), Vec::new());
+    let mut structure = network.structure.clone();
+    structure.resize(leaves + children.len(), TensorStructure::Dense);
+    let mut costs = vec![0.0; children.len()];
+
+    for node in tree_order(leaves, children) {
+        let [a, b] = children[node - leaves];
+        let (la, lb) = (&legs[a], &legs[b]);
+        let k = network.size(la.iter().filter(|l| lb.contains(l)));
+        let m = network.size(la.iter().filter(|l| !lb.contains(l)));
+        let n = network.size(lb.iter().filter(|l| !la.contains(l)));
+        let (_, fraction, merged) = pair_kernel(structure[a], structure[b], (m, k, n));
+
+        costs[node - leaves] = model.scaled_cost(m, k, n, fraction);
+        legs[node] = la
+            .iter()
+            .filter(|l| !lb.contains(l))
+            .chain(lb.iter().filter(|l| !la.contains(l)))
+            .cloned()
+            .collect();
+        structure[node] = merged;
+    }
+    (legs, structure, costs)
+}
+
+/// Legs, structure and cost of the nodes of a contraction tree, as returned by [`tree_nodes`].
+type TreeNodes = (Vec<Vec<i32>>, Vec<TensorStructure>, Vec<f64>);
+
+/// Leg bookkeeping of a network during path planning.
+///
+/// Only the labels of the legs and their dimensions are tracked, so planners can try many
+/// contraction sequences cheaply. Pairwise contractions follow the same convention as
+/// [`crate::tencon::contract`]: the result replaces the first tensor, keeps its free legs
+/// followed by those of the second tensor, and the second tensor is removed.
+#[derive(Clone, Debug)]
+pub(crate) struct Network {
+    legs: Vec<Vec<i32>>,
+    dims: HashMap<i32, usize>,
+    structure: Vec<TensorStructure>,
+}
+
+impl Network {
+    /// Builds the bookkeeping for a network from tensor shapes and contraction indices.
+    pub(crate) fn new(
+        shapes: &[Vec<usize>],
+        contraction_order: &[&[i32]],
+    ) -> Result<Self, QtnError> {
+        if shapes.len() != contraction_order.len() {
+            return Err(QtnError::OrderCountMismatch {
+                tensors: shapes.len(),
+                orders: contraction_order.len(),
+            });
+        }
+
+        let mut dims = HashMap::new();
+        for (k, (shape, order)) in shapes.iter().zip(contraction_order).enumerate() {
+            if shape.len() != order.len() {
+                return Err(QtnError::RankMismatch {
+                    tensor: k,
+                    rank: shape.len(),
+                    order_len: order.len(),
+                });
+            }
+            for (&index, &dim) in order.iter().zip(shape) {
+                dims.insert(index, dim);
+            }
+        }
+
+        // Legs traced within a single tensor are removed before any pairwise contraction
+        let legs = contraction_order
+            .iter()
+            .map(|o| {
+                o.iter()
+                    .filter(|&l| o.iter().filter(|&x| x == l).count() == 1)
+                    .cloned()
+                    .collect()
+            })
+            .collect();
+
+        Ok(Network {
+            structure: vec![TensorStructure::Dense; shapes.len()],
+            legs,
+            dims,
+        })
+    }
+
+    /// Attaches the structure of the tensors to the network.
+    ///
+    /// An empty `structure` leaves every tensor dense. Diagonal and identity tensors that are
+    /// traced within themselves are treated as dense.
+    pub(crate) fn with_structure(
+        mut self,
+        structure: &[TensorStructure],
+    ) -> Result<Self, QtnError> {
+        if structure.is_empty() {
+            return Ok(self);
+        }
+        if structure.len() != self.len() {
+            return Err(QtnError::InvalidArgument(format!(
+                "Structure describes {} tensors, but the network has {}.",
+                structure.len(),
+                self.len()
+            )));
+        }
+
+        for (k, &s) in structure.iter().enumerate() {
+            self.structure[k] = match s {
+                TensorStructure::Sparse { density } if !(density > 0.0 && density <= 1.0) => {
+                    return Err(QtnError::InvalidArgument(format!(
+                        "Tensor {} has density {}, which is not in (0, 1].",
+                        k, density
+                    )));
+                }
+                TensorStructure::Diagonal | TensorStructure::Identity => {
+                    // Extra legs of dimension one do not change the structure
+                    let dims: Vec<usize> = self.legs[k]
+                        .iter()
+                        .map(|l| self.dims[l])
+                        .filter(|&d| d > 1)
+                        .collect();
+                    if dims.len() > 2 || (dims.len() == 2 && dims[0] != dims[1]) {
+                        return Err(QtnError::InvalidArgument(format!(
+                            "Tensor {} is flagged as {:?} but is not a square matrix.",
+                            k, s
+                        )));
+                    }
+                    if dims.len() == 2 {
+                        s
+                    } else {
+                        TensorStructure::Dense
+                    }
+                }
+                _ => s,
+            };
+        }
+        Ok(self)
+    }
+
+    /// Returns the number of tensors left in the network.
+    pub(crate) fn len(&self) -> usize {
+        self.legs.len()
+    }
+
+    /// Returns the `(m, k, n)` sizes of contracting tensors `i` and `j`, without modifying the network.
+    pub(crate) fn pair_sizes(&self, i: usize, j: usize) -> (f64, f64, f64) {
+        let (a, b) = (&self.legs[i], &self.legs[j]);
+
+        let k = self.size(a.iter().filter(|l| b.contains(l)));
+        let m = self.size(a.iter().filter(|l| !b.contains(l)));
+        let n = self.size(b.iter().filter(|l| !a.contains(l)));
+        (m, k, n)
+    }
+
+    /// Returns the number of elements of tensor `i`.
+    pub(crate) fn tensor_size(&self, i: usize) -> f64 {
+        self.size(self.legs[i].iter())
+    }
+
+    /// Returns `true` if tensors `i` and `j` have at least one leg in common.
+    pub(crate) fn shares_leg(&self, i: usize, j: usize) -> bool {
+        self.legs[i].iter().any(|l| self.legs[j].contains(l))
+    }
+
+    /// Returns the pairs worth contracting next, together with their greedy scores.
+    ///
+    /// Only pairs sharing at least one leg are returned, unless no such pair exists, in which
+    /// case every pair is a candidate. The score of a pair is the size of the result minus the
+    /// sizes of the two inputs, so lower is better.
+    pub(crate) fn candidates(&self) -> (Vec<(usize, usize)>, Vec<f64>) {
+        let pairs: Vec<(usize, usize)> = (0..self.len())
+            .flat_map(|i| (i + 1..self.len()).map(move |j| (i, j)))
+            .collect();
+        let connected: Vec<(usize, usize)> = pairs
+            .iter()
+            .cloned()
+            .filter(|&(i, j)| self.shares_leg(i, j))
+            .collect();
+        let candidates = if connected.is_empty() {
+            pairs
+        } else {
+            connected
+        };
+
+        let scores = candidates
+            .iter()
+            .map(|&(i, j)| {
+                let (m, _, n) = self.pair_sizes(i, j);
+                m * n - self.tensor_size(i) - self.tensor_size(j)
+            })
+            .collect();
+        (candidates, scores)
+    }
+
+    /// Returns the product of the dimensions of the given legs.
+    ///
+    /// The product is formed in floating point, since the intermediates of a poor contraction
+    /// order on a large network can have more elements than `usize` can count.
+    fn size<'a>(&self, legs: impl Iterator<Item = &'a i32>) -> f64 {
+        legs.map(|l| self.dims[l] as f64).product()
+    }
+
+    /// Returns the estimated cost of contracting tensors `i` and `j`, without modifying the
+    /// network.
+    pub(crate) fn pair_cost(&self, i: usize, j: usize, model: &CostModel) -> f64 {
+        let (m, k, n) = self.pair_sizes(i, j);
+        let (_, fraction, _) = pair_kernel(self.structure[i], self.structure[j], (m, k, n));
+        model.scaled_cost(m, k, n, fraction)
+    }
+
+    /// Contracts tensors `i` and `j` and returns the estimated cost of the step.
+    pub(crate) fn contract_pair(&mut self, i: usize, j: usize, model: &CostModel) -> f64 {
+        let (m, k, n) = self.pair_sizes(i, j);
+        let (_, fraction, structure) = pair_kernel(self.structure[i], self.structure[j], (m, k, n));
+
+        let b = self.legs[j].clone();
+        let a = &self.legs[i];
+        let merged: Vec<i32> = a
+            .iter()
+            .filter(|l| !b.contains(l))
+            .chain(b.iter().filter(|l| !a.contains(l)))
+            .cloned()
+            .collect();
+
+        self.legs[i] = merged;
+        self.legs.remove(j);
+        self.structure[i] = structure;
+        self.structure.remove(j);
+        model.scaled_cost(m, k, n, fraction)
+    }
+}
+
+/// Returns the fastest of `repeats` timings (in seconds) of an `m x k` by `k x n` tensor dot.
+fn time_pair<A: Scalar>(m: usize, k: usize, n: usize, repeats: usize) -> f64 {
+    let a = ArrayD::<A>::from_elem(vec![m, k], A::one());
+    let b = ArrayD::<A>::from_elem(vec![k, n], A::one());
+
+    (0..repeats)
+        .map(|_| {
+            let start = Instant::now();
+            let out = tensor_dot(&a, &b, vec![1, 0]).expect("Calibration shapes are compatible");
+            let elapsed = start.elapsed().as_secs_f64();
+            std::hint::black_box(out);
+            elapsed
+        })
+        .fold(f64::INFINITY, f64::min)
+}
@@ -0,0 +1,20 @@
+//! The types and functions needed for most contractions and decompositions, for a single glob
+//! import with `use qua_ten_net::prelude::*;`.
+//!
+//! The modules remain the place for everything else, such as the path optimizers, slicing
+//! options and the channel and superoperator tools.
+
+pub use crate::diag::DiagTensor;
+pub use crate::einsum::{contract_labeled, einsum};
+pub use crate::error::QtnError;
+pub use crate::linop::{arnoldi, lanczos, FnOperator, LinearOperator};
+pub use crate::network::TensorNetwork;
+pub use crate::scalar::Scalar;
+pub use crate::tencon::{contract, contract_with, ContractOptions, ContractionContext};
+pub use crate::tendot::{outer, tensor_dot, Axes};
+pub use crate::tensor::{
+    eig, eigh, identity, lq, ones, qr, random, rq, svd, svd_to_error, svd_truncated, zeros,
+    SVDResult,
+};
+pub use crate::trace::trace;
+pub use ndarray_linalg::{c32, c64};
@@ -0,0 +1,164 @@
+use ndarray::LinalgScalar;
+use ndarray_linalg::{c32, c64, Lapack};
+use std::cmp::Ordering;
+
+/// Element type of the tensors handled by this crate.
+///
+/// The trait bundles everything the tensor operations need from a number type: the arithmetic
+/// required by `ndarray` for matrix products (`LinalgScalar`), the real/complex helpers of
+/// `ndarray_linalg` (conjugation, absolute values, the associated `Real` type) and the LAPACK
+/// bindings used by the decompositions. It is implemented for the four LAPACK types `f32`, `f64`,
+/// `Complex32` and `Complex64`, so every generic function in the crate can run in single or
+/// double precision, with real or complex entries, without duplicating code. Scalars are also
+/// `Send + Sync`, so tensors can be split across threads.
+pub trait Scalar: LinalgScalar + ndarray_linalg::Scalar + Lapack + Send + Sync {
+    /// Builds a scalar from its real and imaginary parts. Real types ignore the imaginary part.
+    fn from_parts(re: Self::Real, im: Self::Real) -> Self;
+
+    /// Compares two real numbers with the IEEE 754 total order, which also orders NaN, so sorts
+    /// by eigenvalues or residuals never panic or become inconsistent.
+    fn total_cmp_real(a: &Self::Real, b: &Self::Real) -> Ordering;
+
+    /// The number of bits of the significand of the real type, including the implicit bit.
+    const MANTISSA_DIGITS: u32;
+}
+
+impl Scalar for f32 {
+    const MANTISSA_DIGITS: u32 = f32::MANTISSA_DIGITS;
+
+    fn from_parts(re: f32, _im: f32) -> Self {
+        re
+    }
+
+    fn total_cmp_real(a: &f32, b: &f32) -> Ordering {
+        a.total_cmp(b)
+    }
+}
+
+impl Scalar for f64 {
+    const MANTISSA_DIGITS: u32 = f64::MANTISSA_DIGITS;
+
+    fn from_parts(re: f64, _im: f64) -> Self {
+        re
+    }
+
+    fn total_cmp_real(a: &f64, b: &f64) -> Ordering {
+        a.total_cmp(b)
+    }
+}
+
+impl Scalar for c32 {
+    const MANTISSA_DIGITS: u32 = f32::MANTISSA_DIGITS;
+
+    fn from_parts(re: f32, im: f32) -> Self {
+        c32::new(re, im)
+    }
+
+    fn total_cmp_real(a: &f32, b: &f32) -> Ordering {
+        a.total_cmp(b)
+    }
+}
+
+impl Scalar for c64 {
+    const MANTISSA_DIGITS: u32 = f64::MANTISSA_DIGITS;
+
+    fn from_parts(re: f64, im: f64) -> Self {
+        c64::new(re, im)
+    }
+
+    fn total_cmp_real(a: &f64, b: &f64) -> Ordering {
+        a.total_cmp(b)
+    }
+}
+
+/// Accumulator of a sum of products in double-word ("double-double") arithmetic.
+///
+/// The real and imaginary parts are each kept as an unevaluated sum `hi + lo` of two numbers of
+/// the working precision. Every product is split into its rounded value and its exact rounding
+/// error (Dekker's algorithm) and both are added with an error-free transformation (Knuth's
+/// TwoSum), so the sum is accumulated with about twice the working precision and is only
+/// rounded once, in [`ExtendedSum::value`].
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ExtendedSum<A: Scalar> {
+    re: (A::Real, A::Real),
+    im: (A::Real, A::Real),
+    splitter: A::Real,
+}
+
+impl<A: Scalar> ExtendedSum<A> {
+    /// Creates an empty sum.
+    ///
+    /// # Arguments
+    /// - `splitter`: The constant of Dekker's splitting, as returned by [`splitter`].
+    pub(crate) fn new(splitter: A::Real) -> Self {
+        let zero = A::real(0.0);
+        ExtendedSum {
+            re: (zero, zero),
+            im: (zero, zero),
+            splitter,
+        }
+    }
+
+    /// Adds a single term to the sum.
+    pub(crate) fn add(&mut self, x: A) {
+        add_word(&mut self.re, x.re(), A::real(0.0));
+        add_word(&mut self.im, x.im(), A::real(0.0));
+    }
+
+    /// Adds the product `x y` to the sum.
+    pub(crate) fn add_product(&mut self, x: A, y: A) {
+        let (xr, xi, yr, yi) = (x.re(), x.im(), y.re(), y.im());
+        let (p, e) = two_product(xr, yr, self.splitter);
+        add_word(&mut self.re, p, e);
+        if xi != A::real(0.0) || yi != A::real(0.0) {
+            let (p, e) = two_product(-xi, yi, self.splitter);
+            add_word(&mut self.re, p, e);
+            let (p, e) = two_product(xr, yi, self.splitter);
+            add_word(&mut self.im, p, e);
+            let (p, e) = two_product(xi, yr, self.splitter);
+            add_word(&mut self.im, p, e);
+        }
+    }
+
+    /// Returns the sum, rounded to the working precision.
+    pub(crate) fn value(&self) -> A {
+        A::from_parts(self.re.0 + self.re.1, self.im.0 + self.im.1)
+    }
+}
+
+/// Returns the machine epsilon `2^(1 - p)` of the real type of `A`, where `p` is the number of
+/// bits of its significand.
+pub(crate) fn epsilon<A: Scalar>() -> A::Real {
+    ndarray_linalg::Scalar::powi(A::real(2.0), 1 - A::MANTISSA_DIGITS as i32)
+}
+
+/// Returns the constant `2^ceil(p / 2) + 1` of Dekker's splitting for the real type of `A`,
+/// where `p` is the number of bits of its significand.
+pub(crate) fn splitter<A: Scalar>() -> A::Real {
+    ndarray_linalg::Scalar::powi(A::real(2.0), A::MANTISSA_DIGITS.div_ceil(2) as i32) + A::real(1.0)
+}
+
+/// Adds the exact sum `p + e` of a product and its error to the double word `(hi, lo)`.
+fn add_word<R: ndarray_linalg::Scalar>(word: &mut (R, R), p: R, e: R) {
+    let (hi, lo) = *word;
+    let sum = hi + p;
+    let virtual_p = sum - hi;
+    let error = (hi - (sum - virtual_p)) + (p - virtual_p);
+    *word = (sum, lo + error + e);
+}
+
+/// Returns the rounded product `a b` and its exact rounding error.
+fn two_product<R: ndarray_linalg::Scalar>(a: R, b: R, splitter: R) -> (R, R) {
+    let p = a * b;
+    let (ah, al) = split(a, splitter);
+    let (bh, bl) = split(b, splitter);
+    let e = ((ah * bh - p) + ah * bl + al * bh) + al * bl;
+    (p, e)
+}
+
+/// Splits a number into two halves with non-overlapping significands.
+fn split<R: ndarray_linalg::Scalar>(a: R, splitter: R) -> (R, R) {
+    let c = splitter * a;
+    let hi = c - (c - a);
+    (hi, a - hi)
+}
@@ -0,0 +1,317 @@
+use crate::error::QtnError;
+use crate::scalar::Scalar;
+use crate::tensor::{dagger, kron};
+use ndarray::{Array1, Array2};
+use ndarray_linalg::{c64, Eig, SVD};
+
+/// Vectorizes an operator by stacking its columns.
+///
+/// The element `op[[i, j]]` is stored at position `i + j * rows` of the output vector, which is
+/// the convention used by all superoperator helpers in this module.
+///
+/// # Arguments
+/// - `op`: A reference to an `Array2` representing the operator.
+///
+/// # Returns
+/// An `Array1` containing the column-stacked entries of `op`.
+pub fn vectorize<A: Scalar>(op: &Array2<A>) -> Array1<A> {
+    op.t().iter().cloned().collect()
+}
+
+/// Reshapes a column-stacked vector back into an operator.
+///
+/// # Arguments
+/// - `vec`: A reference to an `Array1` produced by [`vectorize`].
+/// - `rows`: The number of rows of the operator.
+/// - `cols`: The number of columns of the operator.
+///
+/// # Returns
+/// A `Result<Array2, QtnError>` containing the operator, or an error if the length of
+/// `vec` does not equal `rows * cols`.
+pub fn unvectorize<A: Scalar>(
+    vec: &Array1<A>,
+    rows: usize,
+    cols: usize,
+) -> Result<Array2<A>, QtnError> {
+    if vec.len() != rows * cols {
+        return Err(QtnError::InvalidArgument(format!(
+            "Vector of length {} cannot be reshaped into a {}x{} operator.",
+            vec.len(),
+            rows,
+            cols
+        )));
+    }
+
+    Ok(Array2::from_shape_fn((rows, cols), |(i, j)| {
+        vec[i + j * rows]
+    }))
+}
+
+/// Builds the superoperator of the map `rho -> a * rho * b`.
+///
+/// With column stacking this is `vec(a rho b) = (b^T ⊗ a) vec(rho)`.
+///
+/// # Arguments
+/// - `a`: The operator multiplying from the left.
+/// - `b`: The operator multiplying from the right.
+///
+/// # Returns
+/// An `Array2` representing the superoperator acting on vectorized operators.
+pub fn sandwich<A: Scalar>(a: &Array2<A>, b: &Array2<A>) -> Array2<A> {
+    kron(&b.t().to_owned(), a)
+}
+
+/// Builds the superoperator of left multiplication, `rho -> a * rho`.
+///
+/// # Arguments
+/// - `a`: A square operator.
+///
+/// # Returns
+/// An `Array2` equal to `I ⊗ a`.
+pub fn left_multiplication<A: Scalar>(a: &Array2<A>) -> Array2<A> {
+    sandwich(a, &Array2::eye(a.ncols()))
+}
+
+/// Builds the superoperator of right multiplication, `rho -> rho * b`.
+///
+/// # Arguments
+/// - `b`: A square operator.
+///
+/// # Returns
+/// An `Array2` equal to `b^T ⊗ I`.
+pub fn right_multiplication<A: Scalar>(b: &Array2<A>) -> Array2<A> {
+    sandwich(&Array2::eye(b.nrows()), b)
+}
+
+/// Builds the superoperator of a channel given by its Kraus operators.
+///
+/// The channel `rho -> sum_k K_k rho K_k^†` is represented as `sum_k conj(K_k) ⊗ K_k`.
+///
+/// # Arguments
+/// - `kraus`: A slice of Kraus operators, all with the same shape.
+///
+/// # Returns
+/// A `Result<Array2, QtnError>` containing the superoperator, or an error if the list is
+/// empty or the Kraus operators have different shapes.
+pub fn kraus_to_superoperator<A: Scalar>(kraus: &[Array2<A>]) -> Result<Array2<A>, QtnError> {
+    let shape = kraus
+        .first()
+        .ok_or_else(|| {
+            QtnError::InvalidArgument("Channel needs at least one Kraus operator.".to_string())
+        })?
+        .dim();
+
+    let mut superop = Array2::zeros((shape.0 * shape.0, shape.1 * shape.1));
+    for (k, op) in kraus.iter().enumerate() {
+        if op.dim() != shape {
+            return Err(QtnError::InvalidArgument(format!(
+                "Kraus operator {} has shape {:?}, expected {:?}.",
+                k,
+                op.dim(),
+                shape
+            )));
+        }
+        superop = superop + sandwich(op, &dagger(op));
+    }
+
+    Ok(superop)
+}
+
+/// Composes a sequence of superoperators into a single superoperator.
+///
+/// # Arguments
+/// - `superops`: A slice of superoperators, in the order in which they act on the state
+///   (the first element is applied first).
+///
+/// # Returns
+/// A `Result<Array2, QtnError>` containing the product `S_n ... S_2 S_1`, or an error if
+/// the list is empty or two consecutive superoperators have incompatible dimensions.
+pub fn compose<A: Scalar>(superops: &[Array2<A>]) -> Result<Array2<A>, QtnError> {
+    let mut result = superops
+        .first()
+        .ok_or_else(|| {
+            QtnError::InvalidArgument("Composition needs at least one superoperator.".to_string())
+        })?
+        .clone();
+
+    for (k, s) in superops.iter().enumerate().skip(1) {
+        if s.ncols() != result.nrows() {
+            return Err(QtnError::InvalidArgument(format!(
+                "Superoperator {} expects dimension {}, but the previous map produces {}.",
+                k,
+                s.ncols(),
+                result.nrows()
+            )));
+        }
+        result = s.dot(&result);
+    }
+
+    Ok(result)
+}
+
+/// Applies a superoperator to an operator.
+///
+/// # Arguments
+/// - `superop`: The superoperator acting on column-stacked operators.
+/// - `rho`: The square operator the map is applied to.
+///
+/// # Returns
+/// A `Result<Array2, QtnError>` containing the transformed operator, or an error if the
+/// dimensions of `superop` and `rho` do not match.
+pub fn apply_superoperator<A: Scalar>(
+    superop: &Array2<A>,
+    rho: &Array2<A>,
+) -> Result<Array2<A>, QtnError> {
+    if superop.ncols() != rho.len() {
+        return Err(QtnError::InvalidArgument(format!(
+            "Superoperator acts on dimension {}, but the operator has {} entries.",
+            superop.ncols(),
+            rho.len()
+        )));
+    }
+
+    let out = superop.dot(&vectorize(rho));
+    let dim = (out.len() as f64).sqrt().round() as usize;
+    unvectorize(&out, dim, dim)
+}
+
+/// Builds the Liouvillian of a Lindblad master equation.
+///
+/// The generator is
+/// `L(rho) = -i [H, rho] + sum_k (L_k rho L_k^† - 1/2 {L_k^† L_k, rho})`,
+/// returned as a superoperator acting on column-stacked density matrices.
+///
+/// # Arguments
+/// - `hamiltonian`: The square Hamiltonian `H`.
+/// - `jumps`: A slice of jump operators `L_k`, each with the same shape as `H`.
+///
+/// # Returns
+/// A `Result<Array2<c64>, QtnError>` containing the Liouvillian, or an error if the
+/// Hamiltonian is not square or a jump operator has the wrong shape.
+pub fn liouvillian(
+    hamiltonian: &Array2<c64>,
+    jumps: &[Array2<c64>],
+) -> Result<Array2<c64>, QtnError> {
+    let n = hamiltonian.nrows();
+    if hamiltonian.ncols() != n {
+        return Err(QtnError::InvalidArgument(format!(
+            "Hamiltonian must be square, found shape {:?}.",
+            hamiltonian.dim()
+        )));
+    }
+
+    let minus_i = c64::new(0.0, -1.0);
+    let mut l = (left_multiplication(hamiltonian) - right_multiplication(hamiltonian)) * minus_i;
+
+    for (k, jump) in jumps.iter().enumerate() {
+        if jump.dim() != (n, n) {
+            return Err(QtnError::InvalidArgument(format!(
+                "Jump operator {} has shape {:?}, expected ({}, {}).",
+                k,
+                jump.dim(),
+                n,
+                n
+            )));
+        }
+        let decay = dagger(jump).dot(jump) * c64::new(0.5, 0.0);
+        l = l + sandwich(jump, &dagger(jump))
+            - left_multiplication(&decay)
+            - right_multiplication(&decay);
+    }
+
+    Ok(l)
+}
+
+/// Computes the spectrum of a Liouvillian.
+///
+/// # Arguments
+/// - `liouvillian`: A square superoperator, e.g. the output of [`liouvillian`].
+///
+/// # Returns
+/// A `Result<Array1<c64>, QtnError>` containing the eigenvalues sorted by decreasing real part, so
+/// the steady-state eigenvalue (zero for a valid Lindbladian) comes first, or an error if
+/// the eigenvalue decomposition fails.
+pub fn liouvillian_spectrum(liouvillian: &Array2<c64>) -> Result<Array1<c64>, QtnError> {
+    let (eigvals, _) = liouvillian
+        .eig()
+        .map_err(|err| QtnError::LinalgFailure(format!("Eigenvalue error: {:?}", err)))?;
+
+    let mut eigvals = eigvals.to_vec();
+    eigvals.sort_by(|a, b| b.re.total_cmp(&a.re));
+
+    Ok(Array1::from(eigvals))
+}
+
+/// Computes the steady state of a Lindbladian, i.e. the density matrix annihilated by it.
+///
+/// The steady state is the right singular vector belonging to the smallest singular value of the
+/// Liouvillian. It is reshaped into a matrix, made Hermitian and normalized to unit trace. If the
+/// Lindbladian has several steady states, an arbitrary one from that subspace is returned.
+///
+/// # Arguments
+/// - `liouvillian`: A square superoperator, e.g. the output of [`liouvillian`].
+/// - `tol`: The largest accepted residual `||L vec(rho)||` relative to the largest singular value.
+///
+/// # Returns
+/// A `Result<Array2<c64>, QtnError>` containing the steady-state density matrix, or
+/// - [`QtnError::InvalidArgument`] if the Liouvillian is empty, its dimension is not a perfect
+///   square or the null vector has vanishing trace,
+/// - [`QtnError::LinalgFailure`] if the SVD fails,
+/// - [`QtnError::NotConverged`] if the residual exceeds `tol`.
+pub fn steady_state(liouvillian: &Array2<c64>, tol: f64) -> Result<Array2<c64>, QtnError> {
+    let n = liouvillian.nrows();
+    let dim = (n as f64).sqrt().round() as usize;
+    if n == 0 || liouvillian.ncols() != n || dim * dim != n {
+        return Err(QtnError::InvalidArgument(format!(
+            "Liouvillian of shape {:?} does not act on square operators.",
+            liouvillian.dim()
+        )));
+    }
+
+    let (_, sigma, vt) = liouvillian
+        .svd(false, true)
+        .map_err(|err| QtnError::LinalgFailure(format!("SVD error: {:?}", err)))?;
+    let vt = vt.ok_or_else(|| QtnError::LinalgFailure("VT matrix is None".to_string()))?;
+
+    let largest = sigma.iter().cloned().fold(0.0_f64, f64::max);
+    let smallest = sigma[n - 1];
+    if smallest > tol * largest.max(f64::MIN_POSITIVE) {
+        return Err(QtnError::NotConverged(format!(
+            "Liouvillian has no steady state within tolerance (smallest singular value {}).",
+            smallest
+        )));
+    }
+
+    let null = vt.row(n - 1).mapv(|x| x.conj());
+    let rho = unvectorize(&null, dim, dim)?;
+    let rho = (&rho + &dagger(&rho)) * c64::new(0.5, 0.0);
+
+    let trace = rho.diag().sum();
+    if trace.norm() == 0.0 {
+        return Err(QtnError::InvalidArgument(
+            "Steady state has vanishing trace.".to_string(),
+        ));
+    }
+
+    Ok(rho / trace)
+}
+
+/// Computes the expectation value `Tr(rho * op)` of an operator in a given state.
+///
+/// # Arguments
+/// - `rho`: The density matrix.
+/// - `op`: The operator to measure, with the same shape as `rho`.
+///
+/// # Returns
+/// A `Result` containing the expectation value, or an error if the shapes differ.
+pub fn expectation_value<A: Scalar>(rho: &Array2<A>, op: &Array2<A>) -> Result<A, QtnError> {
+    if rho.dim() != op.dim() {
+        return Err(QtnError::InvalidArgument(format!(
+            "Operator of shape {:?} does not match density matrix of shape {:?}.",
+            op.dim(),
+            rho.dim()
+        )));
+    }
+
+    Ok(rho.dot(op).diag().sum())
+}
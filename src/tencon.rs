@@ -1,7 +1,18 @@
+use crate::error::QtnError;
+use crate::path::{
+    optimize_structured_path, pair_kernel, CostModel, Optimizer, PairKernel, TensorStructure,
+};
+use crate::scalar::{epsilon, Scalar};
 use crate::tendot::*;
 use crate::trace::*;
-use ndarray::{Array2, ArrayD, IxDyn};
+use ndarray::{
+    Array2, ArrayBase, ArrayD, ArrayView2, ArrayViewD, ArrayViewMut2, Axis, CowArray, Data, IxDyn,
+};
+use rand::Rng;
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::thread;
+use std::time::{Duration, Instant};
 
 /// Contracts a list of tensors according to a specified contraction order.
 ///
@@ -12,47 +23,2079 @@ use std::collections::{HashMap, HashSet};
 /// a single tensor that is the result of all specified contractions.
 ///
 /// # Arguments
-/// - `tensors`: A vector of `ArrayD<f64>` representing the tensors to be contracted.
+/// - `tensors`: A slice of `ArrayD<A>` representing the tensors to be contracted. The element type
+///   `A` can be any `ndarray` linear algebra scalar, e.g. `f64` or `Complex64`. Views
+///   (`ArrayViewD<A>`) and shared arrays are accepted as well; the inputs are never copied unless
+///   they are traced.
 /// - `contraction_order`: A vector of vectors containing integers that specify the order of contraction.
 ///   Each integer should appear exactly twice for shared indices (for contraction) and at most once for
 ///   unique indices (for non-contracted dimensions).
 ///
 /// # Returns
-/// A `Result<ArrayD<f64>, String>` where:
-/// - `Ok(ArrayD<f64>)` contains the resulting tensor after all contractions are performed.
-/// - `Err(String)` contains an error message if the contraction order is invalid or if any other error occurs.
+/// A `Result<ArrayD<A>, QtnError>` where:
+/// - `Ok(ArrayD<A>)` contains the resulting tensor after all contractions are performed. Its axes
+///   are the free legs ordered as `-1, -2, ...`; use [`contract_with`] to choose another layout.
+/// - `Err(QtnError)` contains the error if the contraction order is invalid or if any other error occurs.
 ///
 /// # Errors
 /// This function may return an error if:
-/// - The indices in `contraction_order` are not valid (e.g., an index appears the wrong number of times).
-/// - There are issues during tensor operations such as shape mismatches or invalid contractions.
-pub fn contract(
-    tensors: &[ArrayD<f64>],
+/// - `tensors` is empty ([`QtnError::EmptyNetwork`]).
+/// - The number of index lists in `contraction_order` differs from the number of tensors
+///   ([`QtnError::OrderCountMismatch`]), or a list's length differs from the rank of its tensor
+///   ([`QtnError::RankMismatch`]).
+/// - The indices in `contraction_order` are not valid, i.e. an index is zero or appears the wrong
+///   number of times ([`QtnError::InvalidContractionOrder`]).
+/// - Two legs sharing a positive index have different dimensions ([`QtnError::ShapeMismatch`]).
+pub fn contract<A, S>(
+    tensors: &[ArrayBase<S, IxDyn>],
     contraction_order: &[&[i32]],
-) -> Result<ArrayD<f64>, String> {
-    let mut tensor_list: Vec<ArrayD<f64>> = tensors.iter().cloned().collect();
+) -> Result<ArrayD<A>, QtnError>
+where
+    A: Scalar,
+    S: Data<Elem = A>,
+{
+    contract_with(tensors, contraction_order, &ContractOptions::default())
+}
+
+/// Options controlling how [`contract_with`] executes a contraction.
+///
+/// The default options reproduce [`contract`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ContractOptions {
+    /// The free (negative) indices in the order in which they should appear as axes of the
+    /// result, as in the `forder` argument of `ncon`. `None` keeps the default layout `-1, -2, ...`.
+    pub out_order: Option<Vec<i32>>,
+    /// The strategy used to choose the sequence of pairwise contractions.
+    pub optimizer: Optimizer,
+    /// The cost model used by the optimizer to compare candidate paths.
+    pub cost_model: CostModel,
+    /// Guarantees bitwise-identical results across runs, thread counts and BLAS libraries.
+    ///
+    /// The pairwise products are then computed with a kernel that accumulates every element in
+    /// a fixed order instead of a GEMM call, which is noticeably slower for large tensors.
+    pub deterministic: bool,
+    /// Accumulates the pairwise products and traces in double-word ("double-double")
+    /// arithmetic.
+    ///
+    /// Every element is then computed as if in twice the working precision and rounded once,
+    /// which keeps the extra digits needed for small differences of large numbers, such as
+    /// energy gaps or fidelity susceptibilities. The kernel has a fixed summation order, so
+    /// results are also deterministic, but it is considerably slower than a GEMM call.
+    pub extended_precision: bool,
+    /// The structure of each tensor, or an empty list if all tensors are dense.
+    ///
+    /// Contractions that absorb a diagonal or identity matrix over one of its legs are executed
+    /// as a rescaling or a relabeling instead of a GEMM, and the cost-based optimizers plan with
+    /// the reduced cost, see [`crate::path::optimize_structured_path`].
+    pub structure: Vec<TensorStructure>,
+    /// The largest number of elements any intermediate tensor may have, or `None` for no limit.
+    ///
+    /// If the planned path exceeds the limit, contracted indices are sliced: the contraction is
+    /// repeated for every value of the sliced indices on the corresponding slices of the inputs,
+    /// and the partial results are summed. Slicing trades memory for repeated work; the indices
+    /// are chosen by `slice_strategy`.
+    pub memory_limit: Option<usize>,
+    /// The heuristic used to choose the sliced indices.
+    pub slice_strategy: SliceStrategy,
+    /// Evaluates the slices of a sliced contraction on all available threads.
+    ///
+    /// The partial results are always summed in the order of the slices, so the result does not
+    /// depend on the number of threads and deterministic execution is preserved.
+    pub parallel_slices: bool,
+    /// Contracts independent branches of the contraction tree on separate threads.
+    ///
+    /// Pairwise steps that do not depend on each other's results, e.g. the two halves of a wide
+    /// network, are then executed concurrently. Every step is still computed by a single kernel
+    /// call, so the result is the same as with sequential execution.
+    pub parallel_branches: bool,
+    /// The number of randomly chosen elements of every result that are recomputed with a naive
+    /// reference contraction, or 0 to switch the self-test off.
+    ///
+    /// Each sampled element is evaluated by fixing the free legs of the inputs and contracting
+    /// the resulting small slices tensor by tensor, independently of the planned path, slicing
+    /// and kernels. A mismatch beyond the rounding error is reported as
+    /// [`QtnError::SelfTestFailed`], which catches index bookkeeping and permutation errors
+    /// without computing the whole contraction twice.
+    pub self_test: usize,
+}
+
+impl ContractOptions {
+    /// Returns the options with the given output leg ordering.
+    ///
+    /// # Arguments
+    /// - `out_order`: The free indices of the network in the desired axis order of the result.
+    pub fn out_order(mut self, out_order: &[i32]) -> Self {
+        self.out_order = Some(out_order.to_vec());
+        self
+    }
+
+    /// Returns the options with the given path optimizer.
+    ///
+    /// # Arguments
+    /// - `optimizer`: The [`Optimizer`] used to plan the contraction.
+    pub fn optimizer(mut self, optimizer: Optimizer) -> Self {
+        self.optimizer = optimizer;
+        self
+    }
+
+    /// Returns the options with the given cost model, e.g. one measured by
+    /// [`crate::path::calibrate`].
+    ///
+    /// # Arguments
+    /// - `cost_model`: The [`CostModel`] used to compare candidate paths.
+    pub fn cost_model(mut self, cost_model: CostModel) -> Self {
+        self.cost_model = cost_model;
+        self
+    }
+
+    /// Returns the options with deterministic execution switched on or off.
+    ///
+    /// # Arguments
+    /// - `deterministic`: Whether results must be bitwise reproducible, see the field of the
+    ///   same name.
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Returns the options with extended-precision accumulation switched on or off.
+    ///
+    /// # Arguments
+    /// - `extended_precision`: Whether products and traces are accumulated in double-word
+    ///   arithmetic, see the field of the same name.
+    pub fn extended_precision(mut self, extended_precision: bool) -> Self {
+        self.extended_precision = extended_precision;
+        self
+    }
+
+    /// Returns the options with the given tensor structure.
+    ///
+    /// # Arguments
+    /// - `structure`: The [`TensorStructure`] of each tensor, in the order of the tensors.
+    pub fn structure(mut self, structure: &[TensorStructure]) -> Self {
+        self.structure = structure.to_vec();
+        self
+    }
+
+    /// Returns the options with the given memory limit.
+    ///
+    /// # Arguments
+    /// - `memory_limit`: The largest number of elements of any intermediate tensor.
+    pub fn memory_limit(mut self, memory_limit: usize) -> Self {
+        self.memory_limit = Some(memory_limit);
+        self
+    }
+
+    /// Returns the options with the given heuristic for choosing sliced indices.
+    ///
+    /// # Arguments
+    /// - `slice_strategy`: The [`SliceStrategy`] used when the memory limit requires slicing.
+    pub fn slice_strategy(mut self, slice_strategy: SliceStrategy) -> Self {
+        self.slice_strategy = slice_strategy;
+        self
+    }
+
+    /// Returns the options with parallel evaluation of slices switched on or off.
+    ///
+    /// # Arguments
+    /// - `parallel_slices`: Whether slices are contracted on several threads.
+    pub fn parallel_slices(mut self, parallel_slices: bool) -> Self {
+        self.parallel_slices = parallel_slices;
+        self
+    }
+
+    /// Returns the options with parallel execution of independent branches switched on or off.
+    ///
+    /// # Arguments
+    /// - `parallel_branches`: Whether independent pairwise steps run concurrently.
+    pub fn parallel_branches(mut self, parallel_branches: bool) -> Self {
+        self.parallel_branches = parallel_branches;
+        self
+    }
+
+    /// Returns the options with the given number of self-tested elements.
+    ///
+    /// # Arguments
+    /// - `self_test`: The number of result elements checked against a reference contraction,
+    ///   see the field of the same name.
+    pub fn self_test(mut self, self_test: usize) -> Self {
+        self.self_test = self_test;
+        self
+    }
+}
+
+/// Heuristic choosing the indices a contraction is sliced over, see
+/// [`ContractOptions::memory_limit`].
+///
+/// Indices are sliced one at a time until every intermediate fits the limit. Each sliced index
+/// multiplies the work of the steps that do not carry it by its dimension, so good choices are
+/// indices that run through many of the large intermediates of the contraction tree.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SliceStrategy {
+    /// Among the indices that reduce the memory above the limit, slices the one adding the
+    /// fewest multiply-adds over all slices, which keeps the slicing overhead small.
+    #[default]
+    Overhead,
+    /// Slices the index giving the smallest largest intermediate, with ties broken by the
+    /// multiply-adds. Tends to reach the limit with fewer slices.
+    Memory,
+    /// Slices the index carried by the most intermediates above the limit in the contraction
+    /// tree, weighted by the logarithm of their excess size. This only inspects the current tree
+    /// instead of trying every index, so it is the cheapest to evaluate for large networks.
+    Betweenness,
+}
+
+/// Contracts a list of tensors according to a specified contraction order and options.
+///
+/// This is [`contract`] with additional control over the execution, see [`ContractOptions`].
+///
+/// # Arguments
+/// - `tensors`: A slice of `ArrayD<A>` or `ArrayViewD<A>` representing the tensors to be
+///   contracted.
+/// - `contraction_order`: A vector of vectors containing integers that specify the order of
+///   contraction, following the same convention as [`contract`].
+/// - `options`: A reference to the [`ContractOptions`] to apply.
+///
+/// # Returns
+/// A `Result<ArrayD<A>, QtnError>` where:
+/// - `Ok(ArrayD<A>)` contains the resulting tensor after all contractions are performed.
+/// - `Err(QtnError)` contains the error if the inputs or options are invalid.
+///
+/// # Errors
+/// This function may return the same errors as [`contract`], and:
+/// - [`QtnError::InvalidOutputOrder`] if `options.out_order` is not a permutation of the free
+///   indices of the network.
+/// - [`QtnError::MemoryLimitExceeded`] if no choice of sliced indices brings every intermediate
+///   below `options.memory_limit`, e.g. because the result itself is larger.
+pub fn contract_with<A, S>(
+    tensors: &[ArrayBase<S, IxDyn>],
+    contraction_order: &[&[i32]],
+    options: &ContractOptions,
+) -> Result<ArrayD<A>, QtnError>
+where
+    A: Scalar,
+    S: Data<Elem = A>,
+{
+    let shapes: Vec<Vec<usize>> = tensors.iter().map(|t| t.shape().to_vec()).collect();
+    compile(&shapes, contraction_order, options)?.execute(tensors)
+}
+
+/// Contracts a list of tensors and reports where the time was spent.
+///
+/// This is [`contract_with`], additionally returning a [`ContractionReport`] with the wall time,
+/// the number of multiply-adds and the size of the intermediate of every pairwise step. The
+/// report does not include the time spent planning the contraction.
+///
+/// # Arguments
+/// - `tensors`: A slice of `ArrayD<A>` or `ArrayViewD<A>` representing the tensors to be
+///   contracted.
+/// - `contraction_order`: A vector of vectors containing integers that specify the order of
+///   contraction, following the same convention as [`contract`].
+/// - `options`: A reference to the [`ContractOptions`] to apply.
+///
+/// # Returns
+/// A `Result<(ArrayD<A>, ContractionReport), QtnError>` where:
+/// - `Ok((ArrayD<A>, ContractionReport))` contains the resulting tensor and the profile.
+/// - `Err(QtnError)` contains the error if the inputs or options are invalid.
+///
+/// # Errors
+/// This function may return the same errors as [`contract_with`].
+pub fn contract_profiled<A, S>(
+    tensors: &[ArrayBase<S, IxDyn>],
+    contraction_order: &[&[i32]],
+    options: &ContractOptions,
+) -> Result<(ArrayD<A>, ContractionReport), QtnError>
+where
+    A: Scalar,
+    S: Data<Elem = A>,
+{
+    let shapes: Vec<Vec<usize>> = tensors.iter().map(|t| t.shape().to_vec()).collect();
+    compile(&shapes, contraction_order, options)?.execute_profiled(tensors)
+}
+
+/// Contracts a list of tensors and bounds the floating-point error of the result.
+///
+/// This is [`contract_with`], additionally returning a rigorous (to first order in the unit
+/// roundoff) bound on the absolute rounding error of every element of the result, see
+/// [`ContractionPlan::execute_with_bounds`].
+///
+/// # Arguments
+/// - `tensors`: A slice of `ArrayD<A>` or `ArrayViewD<A>` representing the tensors to be
+///   contracted, which are taken to be exact.
+/// - `contraction_order`: A vector of vectors containing integers that specify the order of
+///   contraction, following the same convention as [`contract`].
+/// - `options`: A reference to the [`ContractOptions`] to apply.
+///
+/// # Returns
+/// A `Result<(ArrayD<A>, ArrayD<A::Real>), QtnError>` where:
+/// - `Ok((ArrayD<A>, ArrayD<A::Real>))` contains the resulting tensor and the error bounds.
+/// - `Err(QtnError)` contains the error if the inputs or options are invalid.
+///
+/// # Errors
+/// This function may return the same errors as [`contract_with`].
+pub fn contract_with_bounds<A, S>(
+    tensors: &[ArrayBase<S, IxDyn>],
+    contraction_order: &[&[i32]],
+    options: &ContractOptions,
+) -> Result<(ArrayD<A>, ArrayD<A::Real>), QtnError>
+where
+    A: Scalar,
+    S: Data<Elem = A>,
+{
+    let shapes: Vec<Vec<usize>> = tensors.iter().map(|t| t.shape().to_vec()).collect();
+    compile(&shapes, contraction_order, options)?.execute_with_bounds(tensors)
+}
+
+/// Contracts a list of tensors and writes the result into an existing tensor.
+///
+/// This is [`contract`] without allocating the result, see [`ContractionPlan::execute_into`].
+/// In loops over contractions of fixed shapes, compiling the plan once with [`plan`] and calling
+/// `execute_into` also avoids planning on every iteration.
+///
+/// # Arguments
+/// - `out`: The tensor receiving the result, with the shape of the result of [`contract`]. Its
+///   previous entries are overwritten.
+/// - `tensors`: A slice of `ArrayD<A>` or `ArrayViewD<A>` representing the tensors to be
+///   contracted.
+/// - `contraction_order`: A vector of vectors containing integers that specify the order of
+///   contraction, following the same convention as [`contract`].
+///
+/// # Returns
+/// A `Result<(), QtnError>` which is `Ok(())` once `out` holds the result.
+///
+/// # Errors
+/// This function may return the same errors as [`contract`], and
+/// [`QtnError::InvalidArgument`] if `out` does not have the shape of the result.
+pub fn contract_into<A, S>(
+    out: &mut ArrayD<A>,
+    tensors: &[ArrayBase<S, IxDyn>],
+    contraction_order: &[&[i32]],
+) -> Result<(), QtnError>
+where
+    A: Scalar,
+    S: Data<Elem = A>,
+{
+    let shapes: Vec<Vec<usize>> = tensors.iter().map(|t| t.shape().to_vec()).collect();
+    plan(&shapes, contraction_order)?.execute_into(tensors, out)
+}
+
+/// A contraction planned and compiled for fixed tensor shapes, see [`plan`].
+///
+/// Compiling resolves everything that only depends on the shapes: the contraction path, the
+/// traces within each tensor, and for every pairwise step the axis permutations of both operands,
+/// the sizes of the matrices passed to the GEMM and the shape of the intermediate. Executing the
+/// plan then only permutes, reshapes and multiplies, which removes the planning and shape
+/// bookkeeping from contractions that are repeated many times with the same dimensions, as in
+/// sweeping algorithms.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContractionPlan {
+    shapes: Vec<Vec<usize>>,
+    path: Vec<Vec<usize>>,
+    traces: Vec<Vec<[usize; 2]>>,
+    steps: Vec<PlanStep>,
+    output_axes: Vec<usize>,
+    output_shape: Vec<usize>,
+    deterministic: bool,
+    extended_precision: bool,
+    slice_dims: Vec<usize>,
+    slice_axes: Vec<Vec<(usize, usize)>>,
+    parallel_slices: bool,
+    parallel_branches: bool,
+    labels: Vec<Vec<i32>>,
+    output_labels: Vec<i32>,
+    self_test: usize,
+}
+
+/// Cost and memory estimate of a contraction, see [`contract_info`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContractInfo {
+    /// The planned sequence of pairwise contractions, in the format returned by [`contract_map`].
+    pub path: Vec<Vec<usize>>,
+    /// The total number of multiply-adds of the pairwise contractions.
+    pub flops: f64,
+    /// The number of elements of the largest tensor produced by a pairwise contraction, or of
+    /// the output if there is none.
+    pub largest_intermediate: usize,
+    /// The number of slices the contraction is split into, 1 if it is not sliced.
+    ///
+    /// With slicing, `flops` counts the multiply-adds of all slices and `largest_intermediate`
+    /// refers to a single slice.
+    pub slices: usize,
+    /// The shape of the result.
+    pub output_shape: Vec<usize>,
+}
+
+/// Profile of an executed contraction, see [`contract_profiled`].
+///
+/// Printing the report with `{}` gives one line per pairwise step.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContractionReport {
+    /// The pairwise steps, in execution order.
+    pub steps: Vec<StepReport>,
+    /// The number of slices the contraction was split into, 1 if it is not sliced.
+    pub slices: usize,
+    /// The wall time of the whole execution, including traces, slicing and the final
+    /// permutation of the result.
+    pub total: Duration,
+}
+
+/// Profile of one pairwise step of a contraction, see [`ContractionReport`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct StepReport {
+    /// The positions of the two operands in the list of remaining tensors, as in the path.
+    pub pair: [usize; 2],
+    /// The time spent in the step, summed over all slices.
+    pub time: Duration,
+    /// The number of multiply-adds of the step, summed over all slices.
+    pub flops: f64,
+    /// The shape of the intermediate produced by the step, for a single slice.
+    pub shape: Vec<usize>,
+}
+
+impl fmt::Display for ContractionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>4}  {:>8}  {:>12}  {:>12}  {:>12}  shape",
+            "step", "pair", "time [s]", "flops", "elements"
+        )?;
+        for (k, step) in self.steps.iter().enumerate() {
+            writeln!(
+                f,
+                "{:>4}  {:>8}  {:>12.6}  {:>12.3e}  {:>12}  {:?}",
+                k,
+                format!("{},{}", step.pair[0], step.pair[1]),
+                step.time.as_secs_f64(),
+                step.flops,
+                step.shape.iter().product::<usize>(),
+                step.shape
+            )?;
+        }
+        write!(
+            f,
+            "total {:.6} s over {} slice(s)",
+            self.total.as_secs_f64(),
+            self.slices
+        )
+    }
+}
+
+/// The contracted slice and the time spent in each of its steps.
+type SliceResult<A> = Result<(ArrayD<A>, Vec<Duration>), QtnError>;
+
+/// One pairwise contraction of a [`ContractionPlan`].
+///
+/// The operands at positions `pair[0]` and `pair[1]` are permuted by `perm_a` and `perm_b`,
+/// multiplied as `m x k` and `k x n` matrices, and the product is reshaped to `shape`, stored at
+/// position `pair[0]` while position `pair[1]` is removed. In the contraction tree, the operands
+/// are the nodes `nodes[0]` and `nodes[1]`, where the inputs are the nodes `0..n` and step `s`
+/// produces node `n + s`. The product is computed with
+/// `kernel`, which performs `flops` multiply-adds. For a sliced contraction, the sizes refer to a
+/// single slice.
+#[derive(Clone, Debug, PartialEq)]
+struct PlanStep {
+    pair: [usize; 2],
+    nodes: [usize; 2],
+    perm_a: Vec<usize>,
+    perm_b: Vec<usize>,
+    m: usize,
+    k: usize,
+    n: usize,
+    shape: Vec<usize>,
+    kernel: PairKernel,
+    flops: f64,
+}
+
+/// Estimates the cost of a contraction without touching any data.
+///
+/// The contraction is planned as by [`contract`], and the planned pairwise sequence, its number
+/// of multiply-adds, the size of the largest intermediate tensor and the shape of the result are
+/// returned. This makes it possible to check whether a contraction is feasible before allocating
+/// the tensors.
+///
+/// # Arguments
+/// - `shapes`: The shapes of the tensors to be contracted.
+/// - `contraction_order`: A vector of vectors containing integers that specify the order of
+///   contraction, following the same convention as [`contract`].
+///
+/// # Returns
+/// A `Result<ContractInfo, QtnError>` where:
+/// - `Ok(ContractInfo)` contains the estimate.
+/// - `Err(QtnError)` contains the error if the inputs are invalid.
+///
+/// # Errors
+/// This function may return the same errors as [`contract`].
+pub fn contract_info(
+    shapes: &[Vec<usize>],
+    contraction_order: &[&[i32]],
+) -> Result<ContractInfo, QtnError> {
+    Ok(plan(shapes, contraction_order)?.info())
+}
+
+/// Plans a contraction for tensors of the given shapes.
+///
+/// Sweeping algorithms evaluate the same contraction many times with tensors of unchanged
+/// dimensions. Planning once and calling [`ContractionPlan::execute`] on every iteration avoids
+/// running the path optimizer and the shape bookkeeping again.
+///
+/// # Arguments
+/// - `shapes`: The shapes of the tensors to be contracted.
+/// - `contraction_order`: A vector of vectors containing integers that specify the order of
+///   contraction, following the same convention as [`contract`].
+///
+/// # Returns
+/// A `Result<ContractionPlan, QtnError>` where:
+/// - `Ok(ContractionPlan)` contains the plan, executed with the same result as [`contract`].
+/// - `Err(QtnError)` contains the error if the inputs are invalid.
+///
+/// # Errors
+/// This function may return the same errors as [`contract`].
+pub fn plan(
+    shapes: &[Vec<usize>],
+    contraction_order: &[&[i32]],
+) -> Result<ContractionPlan, QtnError> {
+    compile(shapes, contraction_order, &ContractOptions::default())
+}
+
+/// Plans a contraction for tensors of the given shapes with the given options.
+///
+/// This is [`plan`] with additional control over the path and the execution, see
+/// [`ContractOptions`].
+///
+/// # Arguments
+/// - `shapes`: The shapes of the tensors to be contracted.
+/// - `contraction_order`: A vector of vectors containing integers that specify the order of
+///   contraction, following the same convention as [`contract`].
+/// - `options`: A reference to the [`ContractOptions`] to apply.
+///
+/// # Returns
+/// A `Result<ContractionPlan, QtnError>` where:
+/// - `Ok(ContractionPlan)` contains the plan, to be run with [`ContractionPlan::execute`].
+/// - `Err(QtnError)` contains the error if the inputs or options are invalid.
+///
+/// # Errors
+/// This function may return the same errors as [`contract_with`].
+pub fn compile(
+    shapes: &[Vec<usize>],
+    contraction_order: &[&[i32]],
+    options: &ContractOptions,
+) -> Result<ContractionPlan, QtnError> {
     let mut order: Vec<Vec<i32>> = contraction_order.iter().map(|&arr| arr.to_vec()).collect();
 
+    rank_validation(shapes, &order)?;
     indices_validation(&order)?;
-    prepare_contraction_data(&mut tensor_list, &mut order);
+    shape_validation(shapes, &order)?;
+    if let Some(out_order) = &options.out_order {
+        output_validation(&order, out_order)?;
+    }
+
+    // Generate a contraction path with the requested optimizer
+    let mut plan_shapes = shapes.to_vec();
+    let mut plan_order = order.clone();
+    prepare_contraction_data(&mut plan_shapes, &mut plan_order);
+    let order_refs: Vec<&[i32]> = plan_order.iter().map(|o| o.as_slice()).collect();
+    let path = optimize_structured_path(
+        &plan_shapes,
+        &order_refs,
+        &options.structure,
+        &options.optimizer,
+        &options.cost_model,
+    )?;
+
+    let mut dims: HashMap<i32, usize> = HashMap::new();
+    for (shape, indices) in shapes.iter().zip(&order) {
+        for (&index, &dim) in indices.iter().zip(shape) {
+            dims.insert(index, dim);
+        }
+    }
+    let size = |legs: &[i32]| legs.iter().map(|l| dims[l]).product::<usize>();
+    let position = |legs: &[i32], leg: &i32| legs.iter().position(|l| l == leg).unwrap();
+
+    // The self-test contracts the untouched network independently of the plan
+    let labels = order.clone();
+
+    // Indices repeated on a single tensor are traced before any pairwise contraction
+    let traces: Vec<Vec<[usize; 2]>> = order.iter_mut().map(trace_pairs).collect();
 
-    // Generate a contraction plan using a greedy algorithm
-    let contraction_plan = contract_map(&tensor_list, &order);
+    // Slice contracted indices until the intermediates fit the memory limit
+    let sliced = match options.memory_limit {
+        Some(limit) => slice_selection(&order, &dims, &path, limit, options.slice_strategy)?,
+        None => Vec::new(),
+    };
+    let mut slice_axes: Vec<Vec<(usize, usize)>> = vec![Vec::new(); order.len()];
+    for (s, leg) in sliced.iter().enumerate() {
+        for (axes, legs) in slice_axes.iter_mut().zip(&order) {
+            if let Some(axis) = legs.iter().position(|l| l == leg) {
+                axes.push((axis, s));
+            }
+        }
+    }
+    for (axes, legs) in slice_axes.iter_mut().zip(order.iter_mut()) {
+        // Axes are removed from the back, so the remaining positions stay valid
+        axes.sort_by(|a, b| b.cmp(a));
+        for &(axis, _) in axes.iter() {
+            legs.remove(axis);
+        }
+    }
 
-    for pair in contraction_plan {
-        for &i in &pair {
-            trace_check(&mut tensor_list[i], &mut order[i])?;
+    // Traced and sliced tensors lose their structure
+    let mut structure = if options.structure.is_empty() {
+        vec![TensorStructure::Dense; shapes.len()]
+    } else {
+        options.structure.clone()
+    };
+    for ((s, pairs), axes) in structure.iter_mut().zip(&traces).zip(&slice_axes) {
+        if !pairs.is_empty() || !axes.is_empty() {
+            *s = TensorStructure::Dense;
         }
+    }
+
+    // Resolve the operand permutations and matrix sizes of every pairwise contraction
+    let mut steps = Vec::with_capacity(path.len());
+    let mut nodes: Vec<usize> = (0..shapes.len()).collect();
+    for pair in &path {
+        let (a, b) = (&order[pair[0]], &order[pair[1]]);
+        let shared: Vec<i32> = a.iter().filter(|l| b.contains(l)).cloned().collect();
+        let free_a: Vec<i32> = a.iter().filter(|l| !b.contains(l)).cloned().collect();
+        let free_b: Vec<i32> = b.iter().filter(|l| !a.contains(l)).cloned().collect();
+        let (m, k, n) = (size(&free_a), size(&shared), size(&free_b));
+        let (kernel, fraction, result) = pair_kernel(
+            structure[pair[0]],
+            structure[pair[1]],
+            (m as f64, k as f64, n as f64),
+        );
 
-        let axes = order_to_index(&order, &pair);
-        let contraction = tensor_dot(&tensor_list[pair[0]], &tensor_list[pair[1]], axes)?;
+        let step = PlanStep {
+            pair: [pair[0], pair[1]],
+            nodes: [nodes[pair[0]], nodes[pair[1]]],
+            perm_a: free_a
+                .iter()
+                .chain(&shared)
+                .map(|l| position(a, l))
+                .collect(),
+            perm_b: shared
+                .iter()
+                .chain(&free_b)
+                .map(|l| position(b, l))
+                .collect(),
+            m,
+            k,
+            n,
+            shape: free_a.iter().chain(&free_b).map(|l| dims[l]).collect(),
+            kernel,
+            flops: fraction * m as f64 * k as f64 * n as f64,
+        };
 
-        tensor_list[pair[0]] = contraction;
-        tensor_list.remove(pair[1]);
-        order_reformat(&mut order, &pair);
+        order[pair[0]] = [free_a, free_b].concat();
+        order.remove(pair[1]);
+        structure[pair[0]] = result;
+        structure.remove(pair[1]);
+        nodes[pair[0]] = shapes.len() + steps.len();
+        nodes.remove(pair[1]);
+        steps.push(step);
     }
 
-    Ok(final_order(tensor_list.remove(0), order))
+    let output = match &options.out_order {
+        Some(out_order) => out_order.clone(),
+        None => {
+            let mut sorted = order[0].clone();
+            sorted.sort_by(|a, b| b.cmp(a));
+            sorted
+        }
+    };
+
+    Ok(ContractionPlan {
+        shapes: shapes.to_vec(),
+        path,
+        traces,
+        output_axes: output.iter().map(|x| position(&order[0], x)).collect(),
+        output_shape: output.iter().map(|x| dims[x]).collect(),
+        steps,
+        deterministic: options.deterministic,
+        extended_precision: options.extended_precision,
+        slice_dims: sliced.iter().map(|l| dims[l]).collect(),
+        slice_axes,
+        parallel_slices: options.parallel_slices,
+        parallel_branches: options.parallel_branches,
+        labels,
+        output_labels: output,
+        self_test: options.self_test,
+    })
+}
+
+impl ContractionPlan {
+    /// Returns the tensor shapes the plan was compiled for.
+    pub fn shapes(&self) -> &[Vec<usize>] {
+        &self.shapes
+    }
+
+    /// Returns the contraction path, in the format returned by [`contract_map`].
+    pub fn path(&self) -> &[Vec<usize>] {
+        &self.path
+    }
+
+    /// Returns the shape of the result.
+    pub fn output_shape(&self) -> &[usize] {
+        &self.output_shape
+    }
+
+    /// Returns the cost and memory estimate of the plan, see [`contract_info`].
+    pub fn info(&self) -> ContractInfo {
+        let output_size = self.output_shape.iter().product();
+        let slices: usize = self.slice_dims.iter().product();
+
+        ContractInfo {
+            path: self.path.clone(),
+            flops: slices as f64 * self.steps.iter().map(|s| s.flops).sum::<f64>(),
+            largest_intermediate: self
+                .steps
+                .iter()
+                .map(|s| s.m * s.n)
+                .max()
+                .unwrap_or(output_size),
+            slices,
+            output_shape: self.output_shape.clone(),
+        }
+    }
+
+    /// Contracts a list of tensors with the compiled plan.
+    ///
+    /// # Arguments
+    /// - `tensors`: The tensors to be contracted, with the shapes the plan was compiled for. Owned
+    ///   arrays and views are accepted.
+    ///
+    /// # Returns
+    /// A `Result<ArrayD<A>, QtnError>` where:
+    /// - `Ok(ArrayD<A>)` contains the resulting tensor, with the same layout as [`contract_with`].
+    /// - `Err(QtnError)` contains the error if the tensors do not match the plan.
+    ///
+    /// # Errors
+    /// This function may return an error if:
+    /// - The number of tensors differs from the number of compiled shapes
+    ///   ([`QtnError::OrderCountMismatch`]).
+    /// - A tensor has a different shape than it was compiled for ([`QtnError::PlanShapeMismatch`]).
+    pub fn execute<A, S>(&self, tensors: &[ArrayBase<S, IxDyn>]) -> Result<ArrayD<A>, QtnError>
+    where
+        A: Scalar,
+        S: Data<Elem = A>,
+    {
+        Ok(self.execute_profiled(tensors)?.0)
+    }
+
+    /// Contracts a list of tensors with the compiled plan and reports where the time was spent.
+    ///
+    /// # Arguments
+    /// - `tensors`: The tensors to be contracted, with the shapes the plan was compiled for.
+    ///
+    /// # Returns
+    /// A `Result<(ArrayD<A>, ContractionReport), QtnError>` containing the resulting tensor, as
+    /// returned by [`ContractionPlan::execute`], and the profile of the execution.
+    ///
+    /// # Errors
+    /// This function may return the same errors as [`ContractionPlan::execute`].
+    pub fn execute_profiled<A, S>(
+        &self,
+        tensors: &[ArrayBase<S, IxDyn>],
+    ) -> Result<(ArrayD<A>, ContractionReport), QtnError>
+    where
+        A: Scalar,
+        S: Data<Elem = A>,
+    {
+        let start = Instant::now();
+        let tensor_list = self.traced_inputs(tensors)?;
+
+        let mut times = vec![Duration::ZERO; self.steps.len()];
+        let tensor = if self.slice_dims.is_empty() {
+            self.run_steps(tensor_list, &mut times)?
+        } else {
+            self.run_slices(&tensor_list, &mut times)?
+        };
+        let tensor = tensor.permuted_axes(IxDyn(&self.output_axes));
+
+        let slices: usize = self.slice_dims.iter().product();
+        let report = ContractionReport {
+            steps: self
+                .steps
+                .iter()
+                .zip(times)
+                .map(|(step, time)| StepReport {
+                    pair: step.pair,
+                    time,
+                    flops: slices as f64 * step.flops,
+                    shape: step.shape.clone(),
+                })
+                .collect(),
+            slices,
+            total: start.elapsed(),
+        };
+        self.verify(tensors, tensor.view())?;
+        Ok((tensor, report))
+    }
+
+    /// Contracts a list of tensors with the compiled plan and writes the result into an existing
+    /// tensor.
+    ///
+    /// In sweeping algorithms the result of every iteration has the same shape, and for small bond
+    /// dimensions allocating it again dominates. Here the product of the last pairwise step is
+    /// written directly into `out` when its layout allows it (e.g. when `out` is in standard
+    /// layout and the output legs keep the order of the last product); otherwise it is copied
+    /// into `out`. Sliced contractions and parallel branches always copy.
+    ///
+    /// # Arguments
+    /// - `tensors`: The tensors to be contracted, with the shapes the plan was compiled for.
+    /// - `out`: The tensor receiving the result, with the shape returned by
+    ///   [`ContractionPlan::output_shape`]. Its previous entries are overwritten.
+    ///
+    /// # Returns
+    /// A `Result<(), QtnError>` which is `Ok(())` once `out` holds the result.
+    ///
+    /// # Errors
+    /// This function may return the same errors as [`ContractionPlan::execute`], and
+    /// [`QtnError::InvalidArgument`] if `out` does not have the output shape.
+    pub fn execute_into<A, S>(
+        &self,
+        tensors: &[ArrayBase<S, IxDyn>],
+        out: &mut ArrayD<A>,
+    ) -> Result<(), QtnError>
+    where
+        A: Scalar,
+        S: Data<Elem = A>,
+    {
+        if out.shape() != self.output_shape.as_slice() {
+            return Err(QtnError::InvalidArgument(format!(
+                "Output has shape {:?}, but the contraction has shape {:?}.",
+                out.shape(),
+                self.output_shape
+            )));
+        }
+
+        let Some((last, rest)) = self.steps.split_last() else {
+            out.assign(&self.execute(tensors)?);
+            return Ok(());
+        };
+        if !self.slice_dims.is_empty() || self.parallel_branches {
+            out.assign(&self.execute(tensors)?);
+            return Ok(());
+        }
+
+        let mut tensor_list = self.traced_inputs(tensors)?;
+        for step in rest {
+            let [i, j] = step.pair;
+            let product = self.run_step(step, &tensor_list[i], &tensor_list[j])?;
+            tensor_list[i] = CowArray::from(product);
+            tensor_list.remove(j);
+        }
+
+        // The result is the product of the last step with its axes permuted
+        let mut inverse = vec![0; self.output_axes.len()];
+        for (q, &axis) in self.output_axes.iter().enumerate() {
+            inverse[axis] = q;
+        }
+        let target = out.view_mut().permuted_axes(IxDyn(&inverse));
+        let [i, j] = last.pair;
+        if last.kernel == PairKernel::Gemm && target.is_standard_layout() {
+            let a = matrix(
+                tensor_list[i].view().permuted_axes(IxDyn(&last.perm_a)),
+                (last.m, last.k),
+            );
+            let b = matrix(
+                tensor_list[j].view().permuted_axes(IxDyn(&last.perm_b)),
+                (last.k, last.n),
+            );
+            let target = target.into_shape_with_order((last.m, last.n))?;
+            self.gemm_into(&a.view(), &b.view(), target);
+        } else {
+            let mut target = target;
+            target.assign(&self.run_step(last, &tensor_list[i], &tensor_list[j])?);
+        }
+        self.verify(tensors, out.view())
+    }
+
+    /// Multiplies two matrices into `out` with the kernel selected by the options of the plan.
+    fn gemm_into<A: Scalar>(&self, a: &ArrayView2<A>, b: &ArrayView2<A>, out: ArrayViewMut2<A>) {
+        if self.extended_precision {
+            extended_matmul_into(a, b, out);
+        } else if self.deterministic {
+            deterministic_matmul_into(a, b, out);
+        } else {
+            matmul_into(a, b, out);
+        }
+    }
+
+    /// Recomputes randomly chosen elements of a result with a reference contraction, as
+    /// requested by [`ContractOptions::self_test`].
+    ///
+    /// # Arguments
+    /// - `tensors`: The contracted tensors.
+    /// - `result`: The result of the plan for `tensors`.
+    ///
+    /// # Returns
+    /// A `Result<(), QtnError>` which is [`QtnError::SelfTestFailed`] if a sampled element
+    /// differs from the reference by more than the square root of the machine epsilon, relative
+    /// to the contraction of the absolute values.
+    fn verify<A, S>(
+        &self,
+        tensors: &[ArrayBase<S, IxDyn>],
+        result: ArrayViewD<A>,
+    ) -> Result<(), QtnError>
+    where
+        A: Scalar,
+        S: Data<Elem = A>,
+    {
+        if self.self_test == 0 || result.is_empty() {
+            return Ok(());
+        }
+        let tolerance = ndarray_linalg::Scalar::sqrt(epsilon::<A>());
+        let mut rng = rand::rng();
+        for _ in 0..self.self_test {
+            let index: Vec<usize> = result
+                .shape()
+                .iter()
+                .map(|&dim| rng.random_range(0..dim))
+                .collect();
+            let fixed: HashMap<i32, usize> = self
+                .output_labels
+                .iter()
+                .cloned()
+                .zip(index.iter().cloned())
+                .collect();
+            let expected = reference_element(tensors, &self.labels, &fixed, false)?;
+            let scale = reference_element(tensors, &self.labels, &fixed, true)?;
+            let found = result[IxDyn(&index)];
+            if (found - expected).abs() > tolerance * scale.re() {
+                return Err(QtnError::SelfTestFailed {
+                    index,
+                    found: found.to_string(),
+                    expected: expected.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the inputs against the compiled shapes and applies the traces.
+    ///
+    /// # Arguments
+    /// - `tensors`: The tensors to be contracted.
+    ///
+    /// # Returns
+    /// A `Result<Vec<CowArray<A, IxDyn>>, QtnError>` containing the traced inputs, which are
+    /// only copied if they are traced, or the errors documented on [`ContractionPlan::execute`].
+    fn traced_inputs<'a, A, S>(
+        &self,
+        tensors: &'a [ArrayBase<S, IxDyn>],
+    ) -> Result<Vec<CowArray<'a, A, IxDyn>>, QtnError>
+    where
+        A: Scalar,
+        S: Data<Elem = A>,
+    {
+        if tensors.len() != self.shapes.len() {
+            return Err(QtnError::OrderCountMismatch {
+                tensors: tensors.len(),
+                orders: self.shapes.len(),
+            });
+        }
+        for (k, (tensor, shape)) in tensors.iter().zip(&self.shapes).enumerate() {
+            if tensor.shape() != shape.as_slice() {
+                return Err(QtnError::PlanShapeMismatch {
+                    tensor: k,
+                    expected: shape.clone(),
+                    found: tensor.shape().to_vec(),
+                });
+            }
+        }
+
+        // Inputs are only copied when they are traced or need a permutation
+        let mut tensor_list: Vec<CowArray<A, IxDyn>> = Vec::with_capacity(tensors.len());
+        for (tensor, pairs) in tensors.iter().zip(&self.traces) {
+            let mut tensor = CowArray::from(tensor.view());
+            for &[i, j] in pairs {
+                tensor = CowArray::from(trace_with(&tensor, vec![i, j], self.extended_precision)?);
+            }
+            tensor_list.push(tensor);
+        }
+        Ok(tensor_list)
+    }
+
+    /// Runs the pairwise contractions on the (traced and sliced) inputs.
+    ///
+    /// # Arguments
+    /// - `tensor_list`: The operands of the first step, in the order of the tensors.
+    /// - `times`: The time spent in each step, to which this run is added.
+    ///
+    /// # Returns
+    /// A `Result<ArrayD<A>, QtnError>` containing the result before the output permutation.
+    fn run_steps<A: Scalar>(
+        &self,
+        mut tensor_list: Vec<CowArray<A, IxDyn>>,
+        times: &mut [Duration],
+    ) -> Result<ArrayD<A>, QtnError> {
+        if self.parallel_branches && self.steps.len() > 1 {
+            // Spawn threads down to the depth at which every thread has a branch of its own
+            let threads = thread::available_parallelism().map_or(1, |t| t.get());
+            let depth = usize::BITS - (threads - 1).leading_zeros();
+            let root = tensor_list.len() + self.steps.len() - 1;
+            let tensor = self.run_node(root, &tensor_list, depth, times)?;
+            return Ok(tensor.into_owned());
+        }
+
+        for (step, time) in self.steps.iter().zip(times.iter_mut()) {
+            let start = Instant::now();
+            let [i, j] = step.pair;
+            let product = self.run_step(step, &tensor_list[i], &tensor_list[j])?;
+            tensor_list[i] = CowArray::from(product);
+            tensor_list.remove(j);
+            *time += start.elapsed();
+        }
+
+        Ok(tensor_list.remove(0).into_owned())
+    }
+
+    /// Computes a node of the contraction tree, contracting independent branches concurrently.
+    ///
+    /// The branch of the first operand is run on a new thread while the current thread runs the
+    /// branch of the second operand, as long as `depth` is positive and both operands are
+    /// intermediates.
+    ///
+    /// # Arguments
+    /// - `node`: The node to compute, an input for `node < inputs.len()` and the result of step
+    ///   `node - inputs.len()` otherwise.
+    /// - `inputs`: The traced and sliced inputs.
+    /// - `depth`: The number of further levels of the tree at which new threads may be spawned.
+    /// - `times`: The time spent in each step, to which this run is added.
+    ///
+    /// # Returns
+    /// A `Result<CowArray<A, IxDyn>, QtnError>` containing the tensor of the node.
+    fn run_node<'a, A: Scalar>(
+        &self,
+        node: usize,
+        inputs: &'a [CowArray<A, IxDyn>],
+        depth: u32,
+        times: &mut [Duration],
+    ) -> Result<CowArray<'a, A, IxDyn>, QtnError> {
+        if node < inputs.len() {
+            return Ok(CowArray::from(inputs[node].view()));
+        }
+
+        let s = node - inputs.len();
+        let step = &self.steps[s];
+        let [left, right] = step.nodes;
+        let (a, b) = if depth > 0 && left >= inputs.len() && right >= inputs.len() {
+            thread::scope(|scope| {
+                let handle = scope.spawn(|| {
+                    let mut times = vec![Duration::ZERO; self.steps.len()];
+                    self.run_node(left, inputs, depth - 1, &mut times)
+                        .map(|a| (a, times))
+                });
+                let b = self.run_node(right, inputs, depth - 1, times);
+                let (a, branch_times) = handle.join().expect("Branch contraction panicked")?;
+                for (time, branch_time) in times.iter_mut().zip(branch_times) {
+                    *time += branch_time;
+                }
+                Ok::<_, QtnError>((a, b?))
+            })?
+        } else {
+            let a = self.run_node(left, inputs, depth, times)?;
+            let b = self.run_node(right, inputs, depth, times)?;
+            (a, b)
+        };
+
+        let start = Instant::now();
+        let product = self.run_step(step, &a, &b)?;
+        times[s] += start.elapsed();
+        Ok(CowArray::from(product))
+    }
+
+    /// Computes the product of one pairwise step.
+    ///
+    /// # Arguments
+    /// - `step`: The step to run.
+    /// - `a`: The operand at position `step.pair[0]`.
+    /// - `b`: The operand at position `step.pair[1]`.
+    ///
+    /// # Returns
+    /// A `Result<ArrayD<A>, QtnError>` containing the intermediate, with shape `step.shape`.
+    fn run_step<A: Scalar>(
+        &self,
+        step: &PlanStep,
+        a: &CowArray<A, IxDyn>,
+        b: &CowArray<A, IxDyn>,
+    ) -> Result<ArrayD<A>, QtnError> {
+        let a = matrix(
+            a.view().permuted_axes(IxDyn(&step.perm_a)),
+            (step.m, step.k),
+        );
+        let b = matrix(
+            b.view().permuted_axes(IxDyn(&step.perm_b)),
+            (step.k, step.n),
+        );
+        let (a, b) = (a.view(), b.view());
+        let product = match step.kernel {
+            PairKernel::Gemm if self.extended_precision => extended_matmul(&a, &b),
+            PairKernel::Gemm if self.deterministic => deterministic_matmul(&a, &b),
+            PairKernel::Gemm => matmul(&a, &b),
+            PairKernel::ScaleColumns => &a * &b.diag(),
+            PairKernel::ScaleRows => &b * &a.diag().insert_axis(Axis(1)),
+            PairKernel::Left => a.to_owned(),
+            PairKernel::Right => b.to_owned(),
+        };
+        Ok(product.into_shape_clone(step.shape.clone())?)
+    }
+
+    /// Contracts every slice of the inputs and sums the partial results.
+    ///
+    /// The slices are enumerated with the last sliced index running fastest. When slices are
+    /// evaluated in parallel, each batch of one slice per thread is contracted concurrently and
+    /// the partial results are added in slice order, which keeps the summation order fixed.
+    ///
+    /// # Arguments
+    /// - `tensor_list`: The traced inputs, in the order of the tensors.
+    /// - `times`: The time spent in each step, to which the runs of all slices are added.
+    ///
+    /// # Returns
+    /// A `Result<ArrayD<A>, QtnError>` containing the summed result before the output
+    /// permutation.
+    fn run_slices<A: Scalar>(
+        &self,
+        tensor_list: &[CowArray<A, IxDyn>],
+        times: &mut [Duration],
+    ) -> Result<ArrayD<A>, QtnError> {
+        let slices: usize = self.slice_dims.iter().product();
+        let threads = if self.parallel_slices {
+            thread::available_parallelism().map_or(1, |t| t.get())
+        } else {
+            1
+        };
+
+        let slice = |index: usize| {
+            let views = self.slice_views(tensor_list, index);
+            let mut times = vec![Duration::ZERO; self.steps.len()];
+            self.run_steps(views, &mut times).map(|t| (t, times))
+        };
+
+        let mut result: Option<ArrayD<A>> = None;
+        for start in (0..slices).step_by(threads) {
+            let batch: Vec<SliceResult<A>> = if threads == 1 {
+                vec![slice(start)]
+            } else {
+                thread::scope(|scope| {
+                    let handles: Vec<_> = (start..slices.min(start + threads))
+                        .map(|index| scope.spawn(move || slice(index)))
+                        .collect();
+                    handles
+                        .into_iter()
+                        .map(|h| h.join().expect("Slice contraction panicked"))
+                        .collect()
+                })
+            };
+
+            for partial in batch {
+                let (partial, slice_times) = partial?;
+                for (time, slice_time) in times.iter_mut().zip(slice_times) {
+                    *time += slice_time;
+                }
+                match result.as_mut() {
+                    Some(sum) => *sum += &partial,
+                    None => result = Some(partial),
+                }
+            }
+        }
+
+        Ok(result.expect("A sliced contraction has at least one slice"))
+    }
+
+    /// Returns the views of the inputs for one slice.
+    ///
+    /// # Arguments
+    /// - `tensor_list`: The traced inputs, in the order of the tensors.
+    /// - `index`: The number of the slice, with the last sliced index running fastest.
+    ///
+    /// # Returns
+    /// A `Vec<CowArray<A, IxDyn>>` with the inputs restricted to the slice.
+    fn slice_views<'a, A: Scalar>(
+        &self,
+        tensor_list: &'a [CowArray<A, IxDyn>],
+        mut index: usize,
+    ) -> Vec<CowArray<'a, A, IxDyn>> {
+        let mut values = vec![0; self.slice_dims.len()];
+        for (value, &dim) in values.iter_mut().zip(&self.slice_dims).rev() {
+            *value = index % dim;
+            index /= dim;
+        }
+        tensor_list
+            .iter()
+            .zip(&self.slice_axes)
+            .map(|(tensor, axes)| {
+                let mut view: ArrayViewD<A> = tensor.view();
+                for &(axis, s) in axes {
+                    view = view.index_axis_move(Axis(axis), values[s]);
+                }
+                CowArray::from(view)
+            })
+            .collect()
+    }
+
+    /// Contracts a list of tensors and bounds the floating-point error of the result.
+    ///
+    /// Alongside every intermediate, a tensor of elementwise absolute error bounds is propagated
+    /// with the standard worst-case analysis of floating-point dot products: a product of
+    /// intermediates `A` and `B` with error bounds `E_A` and `E_B`, summing over `k` terms, has
+    /// the bound `γ |A| |B| + |A| E_B + E_A (|B| + E_B)` with `γ = (k + 2) u / (1 - (k + 2) u)`
+    /// and the unit roundoff `u` of the scalar type. Traces and the summation of slices are
+    /// bounded in the same way. The bounds hold for any summation order of the BLAS library and
+    /// are rigorous to first order in `u`; the rounding errors of evaluating the bounds are of
+    /// relative order `u` and not tracked.
+    ///
+    /// This costs three additional products per step. It makes it possible to certify that a
+    /// small difference between two contracted values, such as an energy gap, is above the
+    /// numerical noise. The steps are executed sequentially even if parallel execution was
+    /// requested.
+    ///
+    /// # Arguments
+    /// - `tensors`: The tensors to be contracted, which are taken to be exact, with the shapes
+    ///   the plan was compiled for.
+    ///
+    /// # Returns
+    /// A `Result<(ArrayD<A>, ArrayD<A::Real>), QtnError>` containing the result, as returned by
+    /// [`ContractionPlan::execute`], and the bound on the absolute error of every element.
+    ///
+    /// # Errors
+    /// This function may return the same errors as [`ContractionPlan::execute`].
+    pub fn execute_with_bounds<A, S>(
+        &self,
+        tensors: &[ArrayBase<S, IxDyn>],
+    ) -> Result<(ArrayD<A>, ArrayD<A::Real>), QtnError>
+    where
+        A: Scalar,
+        S: Data<Elem = A>,
+    {
+        if tensors.len() != self.shapes.len() {
+            return Err(QtnError::OrderCountMismatch {
+                tensors: tensors.len(),
+                orders: self.shapes.len(),
+            });
+        }
+
+        // Traces are bounded like the summation of `dim` terms
+        let mut inputs = Vec::with_capacity(tensors.len());
+        let mut errors = Vec::with_capacity(tensors.len());
+        for (k, (tensor, pairs)) in tensors.iter().zip(&self.traces).enumerate() {
+            if tensor.shape() != self.shapes[k].as_slice() {
+                return Err(QtnError::PlanShapeMismatch {
+                    tensor: k,
+                    expected: self.shapes[k].clone(),
+                    found: tensor.shape().to_vec(),
+                });
+            }
+            let mut value = CowArray::from(tensor.view());
+            let mut error: Option<ArrayD<A>> = None;
+            for &[i, j] in pairs {
+                let gamma = A::from_real(gamma::<A>(value.shape()[i]));
+                let rounding = trace(&absolute(&value), vec![i, j])?.mapv(|x| x * gamma);
+                error = Some(match error {
+                    Some(error) => trace(&error, vec![i, j])? + rounding,
+                    None => rounding,
+                });
+                value = CowArray::from(trace(&value, vec![i, j])?);
+            }
+            inputs.push(value);
+            errors.push(error);
+        }
+
+        let slices: usize = self.slice_dims.iter().product();
+        let mut result: Option<(ArrayD<A>, ArrayD<A>, ArrayD<A>)> = None;
+        for index in 0..slices {
+            let values = self.slice_views(&inputs, index);
+            let error_views: Vec<Option<CowArray<A, IxDyn>>> = if self.slice_dims.is_empty() {
+                errors
+                    .iter()
+                    .map(|e| e.as_ref().map(|e| CowArray::from(e.view())))
+                    .collect()
+            } else {
+                // Error tensors have the shapes of the traced inputs and are sliced alike
+                let owned: Vec<CowArray<A, IxDyn>> = errors
+                    .iter()
+                    .zip(&inputs)
+                    .map(|(e, v)| match e {
+                        Some(e) => CowArray::from(e.view()),
+                        None => CowArray::from(ArrayD::zeros(v.raw_dim())),
+                    })
+                    .collect();
+                self.slice_views(&owned, index)
+                    .into_iter()
+                    .map(|e| Some(CowArray::from(e.into_owned())))
+                    .collect()
+            };
+            let (value, error) = self.bounded_steps(values, error_views)?;
+
+            result = Some(match result {
+                Some((sum, bound, magnitude)) => {
+                    (sum + &value, bound + &error, magnitude + &absolute(&value))
+                }
+                None => {
+                    let magnitude = absolute(&value);
+                    (value, error, magnitude)
+                }
+            });
+        }
+
+        let (value, mut error, magnitude) = result.expect("A contraction has at least one slice");
+        if slices > 1 {
+            error.scaled_add(A::from_real(gamma::<A>(slices)), &magnitude);
+        }
+        let error = error.mapv(|e| e.re());
+        Ok((
+            value.permuted_axes(IxDyn(&self.output_axes)),
+            error.permuted_axes(IxDyn(&self.output_axes)),
+        ))
+    }
+
+    /// Runs the pairwise contractions of [`ContractionPlan::execute_with_bounds`] on one slice.
+    ///
+    /// # Arguments
+    /// - `values`: The (traced and sliced) inputs.
+    /// - `errors`: The error bounds of the inputs, with real entries stored in the scalar type,
+    ///   or `None` for exact inputs.
+    ///
+    /// # Returns
+    /// A `Result<(ArrayD<A>, ArrayD<A>), QtnError>` containing the result before the output
+    /// permutation and its error bound.
+    fn bounded_steps<A: Scalar>(
+        &self,
+        mut values: Vec<CowArray<A, IxDyn>>,
+        mut errors: Vec<Option<CowArray<A, IxDyn>>>,
+    ) -> Result<(ArrayD<A>, ArrayD<A>), QtnError> {
+        for step in &self.steps {
+            let [i, j] = step.pair;
+            let (abs_a, abs_b) = (absolute(&values[i]), absolute(&values[j]));
+            let (abs_a, abs_b) = (CowArray::from(abs_a), CowArray::from(abs_b));
+
+            let mut error = self.run_step(step, &abs_a, &abs_b)?;
+            error.mapv_inplace(|x| x * A::from_real(gamma::<A>(step.k + 2)));
+            if let Some(error_b) = &errors[j] {
+                error += &self.run_step(step, &abs_a, error_b)?;
+            }
+            if let Some(error_a) = &errors[i] {
+                let b = match &errors[j] {
+                    Some(error_b) => CowArray::from(&abs_b.view() + &error_b.view()),
+                    None => abs_b,
+                };
+                error += &self.run_step(step, error_a, &b)?;
+            }
+
+            values[i] = CowArray::from(self.run_step(step, &values[i], &values[j])?);
+            values.remove(j);
+            errors[i] = Some(CowArray::from(error));
+            errors.remove(j);
+        }
+
+        let value = values.remove(0).into_owned();
+        let error = match errors.remove(0) {
+            Some(error) => error.into_owned(),
+            None => ArrayD::zeros(value.raw_dim()),
+        };
+        Ok((value, error))
+    }
+}
+
+/// Returns the elementwise absolute values of a tensor, stored in its scalar type.
+fn absolute<A: Scalar, S: Data<Elem = A>>(tensor: &ArrayBase<S, IxDyn>) -> ArrayD<A> {
+    tensor.mapv(|x| A::from_real(x.abs()))
+}
+
+/// Returns the constant `γ_n = n u / (1 - n u)` bounding the relative rounding error of a sum of
+/// `n` products, where `u` is the unit roundoff of the real type of `A`.
+fn gamma<A: Scalar>(n: usize) -> A::Real {
+    let (one, half) = (A::real(1.0), A::real(0.5));
+    let nu = A::real(n) * epsilon::<A>() * half;
+    nu / (one - nu)
+}
+
+/// Computes a single element of a contraction without a plan.
+///
+/// The free legs of every tensor are fixed to the values in `fixed`, indices repeated on a
+/// tensor are traced, and the remaining slices are multiplied one by one in the order of the
+/// tensors. This only serves as a reference for [`ContractOptions::self_test`].
+///
+/// # Arguments
+/// - `tensors`: The tensors of the network.
+/// - `labels`: The contraction order of the network.
+/// - `fixed`: The value of every free leg.
+/// - `absolute`: Whether the absolute values of the tensors are contracted instead, which
+///   bounds the size of the rounding errors.
+///
+/// # Returns
+/// A `Result<A, QtnError>` containing the element of the contraction.
+fn reference_element<A, S>(
+    tensors: &[ArrayBase<S, IxDyn>],
+    labels: &[Vec<i32>],
+    fixed: &HashMap<i32, usize>,
+    absolute: bool,
+) -> Result<A, QtnError>
+where
+    A: Scalar,
+    S: Data<Elem = A>,
+{
+    let position = |legs: &[i32], leg: &i32| legs.iter().position(|l| l == leg).unwrap();
+    let mut product = ArrayD::from_elem(IxDyn(&[]), A::one());
+    let mut legs: Vec<i32> = Vec::new();
+    for (tensor, tensor_legs) in tensors.iter().zip(labels) {
+        let mut slice = tensor.view();
+        let mut remaining = Vec::new();
+        for (axis, leg) in tensor_legs.iter().enumerate().rev() {
+            match fixed.get(leg) {
+                Some(&value) => slice = slice.index_axis_move(Axis(axis), value),
+                None => remaining.insert(0, *leg),
+            }
+        }
+        let mut slice = if absolute {
+            slice.mapv(|x| A::from_real(x.abs()))
+        } else {
+            slice.to_owned()
+        };
+        while let Some(q) = (1..remaining.len()).find(|&q| remaining[..q].contains(&remaining[q])) {
+            let p = position(&remaining, &remaining[q]);
+            slice = trace(&slice, vec![p, q])?;
+            remaining.remove(q);
+            remaining.remove(p);
+        }
+
+        let shared: Vec<i32> = legs
+            .iter()
+            .filter(|l| remaining.contains(l))
+            .cloned()
+            .collect();
+        let free_a: Vec<i32> = legs
+            .iter()
+            .filter(|l| !shared.contains(l))
+            .cloned()
+            .collect();
+        let free_b: Vec<i32> = remaining
+            .iter()
+            .filter(|l| !shared.contains(l))
+            .cloned()
+            .collect();
+        let a = product.view().permuted_axes(IxDyn(
+            &free_a
+                .iter()
+                .chain(&shared)
+                .map(|l| position(&legs, l))
+                .collect::<Vec<_>>(),
+        ));
+        let b = slice.view().permuted_axes(IxDyn(
+            &shared
+                .iter()
+                .chain(&free_b)
+                .map(|l| position(&remaining, l))
+                .collect::<Vec<_>>(),
+        ));
+        let m: usize = a.shape()[..free_a.len()].iter().product();
+        let n: usize = b.shape()[shared.len()..].iter().product();
+        let k: usize = a.shape()[free_a.len()..].iter().product();
+        let shape: Vec<usize> = a.shape()[..free_a.len()]
+            .iter()
+            .chain(&b.shape()[shared.len()..])
+            .cloned()
+            .collect();
+        product = matrix(a, (m, k))
+            .dot(&matrix(b, (k, n)))
+            .into_shape_with_order(IxDyn(&shape))?;
+        legs = [free_a, free_b].concat();
+    }
+    Ok(product[IxDyn(&[])])
+}
+
+/// Number of plans a [`PlanCache`] keeps unless configured otherwise.
+const DEFAULT_PLAN_CAPACITY: usize = 64;
+
+/// Cache of compiled plans, keyed by the shapes and the contraction order of a network.
+///
+/// Sweeping algorithms such as DMRG or TEBD contract the same few network topologies over and
+/// over, so after the first call of each topology the path optimizer and the resolution of
+/// permutations and matrix sizes are skipped entirely. The cache holds at most `capacity`
+/// plans; when it is full, the plan used least recently is dropped.
+#[derive(Clone, Debug)]
+pub struct PlanCache {
+    options: ContractOptions,
+    capacity: usize,
+    plans: HashMap<PlanKey, (ContractionPlan, u64)>,
+    clock: u64,
+    hits: usize,
+    misses: usize,
+}
+
+/// Shapes and contraction order identifying a cached plan.
+type PlanKey = (Vec<Vec<usize>>, Vec<Vec<i32>>);
+
+impl Default for PlanCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_PLAN_CAPACITY)
+    }
+}
+
+impl PlanCache {
+    /// Creates an empty cache that compiles plans with the default options.
+    ///
+    /// # Arguments
+    /// - `capacity`: The largest number of plans kept. At least one plan is always kept.
+    pub fn new(capacity: usize) -> Self {
+        Self::with_options(capacity, ContractOptions::default())
+    }
+
+    /// Creates an empty cache that compiles plans with the given options.
+    ///
+    /// # Arguments
+    /// - `capacity`: The largest number of plans kept. At least one plan is always kept.
+    /// - `options`: The [`ContractOptions`] used to compile every plan of the cache.
+    pub fn with_options(capacity: usize, options: ContractOptions) -> Self {
+        PlanCache {
+            options,
+            capacity: capacity.max(1),
+            plans: HashMap::new(),
+            clock: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Returns the plan for a network, compiling it if it is not cached.
+    ///
+    /// # Arguments
+    /// - `shapes`: The shapes of the tensors to be contracted.
+    /// - `contraction_order`: A vector of vectors containing integers that specify the order of
+    ///   contraction, following the same convention as [`contract`].
+    ///
+    /// # Returns
+    /// A `Result<&ContractionPlan, QtnError>` containing the plan, or the errors of [`compile`]
+    /// if the network is not cached and cannot be compiled.
+    pub fn get(
+        &mut self,
+        shapes: &[Vec<usize>],
+        contraction_order: &[&[i32]],
+    ) -> Result<&ContractionPlan, QtnError> {
+        let key: PlanKey = (
+            shapes.to_vec(),
+            contraction_order.iter().map(|o| o.to_vec()).collect(),
+        );
+        self.clock += 1;
+
+        if let Some(entry) = self.plans.get_mut(&key) {
+            self.hits += 1;
+            entry.1 = self.clock;
+        } else {
+            let plan = compile(shapes, contraction_order, &self.options)?;
+            self.misses += 1;
+            self.evict(self.capacity - 1);
+            self.plans.insert(key.clone(), (plan, self.clock));
+        }
+        Ok(&self.plans[&key].0)
+    }
+
+    /// Contracts a list of tensors with the cached plan of their network.
+    ///
+    /// # Arguments
+    /// - `tensors`: A slice of `ArrayD<A>` or `ArrayViewD<A>` representing the tensors to be
+    ///   contracted.
+    /// - `contraction_order`: A vector of vectors containing integers that specify the order of
+    ///   contraction, following the same convention as [`contract`].
+    ///
+    /// # Returns
+    /// A `Result<ArrayD<A>, QtnError>` containing the result, or the same errors as
+    /// [`contract_with`].
+    pub fn contract<A, S>(
+        &mut self,
+        tensors: &[ArrayBase<S, IxDyn>],
+        contraction_order: &[&[i32]],
+    ) -> Result<ArrayD<A>, QtnError>
+    where
+        A: Scalar,
+        S: Data<Elem = A>,
+    {
+        let shapes: Vec<Vec<usize>> = tensors.iter().map(|t| t.shape().to_vec()).collect();
+        self.get(&shapes, contraction_order)?.execute(tensors)
+    }
+
+    /// Returns the number of cached plans.
+    pub fn len(&self) -> usize {
+        self.plans.len()
+    }
+
+    /// Returns `true` if no plan is cached.
+    pub fn is_empty(&self) -> bool {
+        self.plans.is_empty()
+    }
+
+    /// Returns the largest number of plans kept.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Changes the largest number of plans kept, dropping the least recently used plans if
+    /// more are cached.
+    ///
+    /// # Arguments
+    /// - `capacity`: The new capacity. At least one plan is always kept.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        self.evict(self.capacity);
+    }
+
+    /// Returns the number of lookups that found a cached plan.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Returns the number of lookups that had to compile a plan.
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
+
+    /// Drops all cached plans and resets the hit and miss counters.
+    pub fn clear(&mut self) {
+        self.plans.clear();
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    /// Drops the least recently used plans until at most `keep` remain.
+    fn evict(&mut self, keep: usize) {
+        while self.plans.len() > keep {
+            let oldest = self
+                .plans
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(key, _)| key.clone())
+                .expect("The cache is not empty");
+            self.plans.remove(&oldest);
+        }
+    }
+}
+
+/// Largest number of released buffers a [`ContractionContext`] keeps for reuse.
+const MAX_POOLED: usize = 8;
+
+/// Reusable state for contractions that are repeated many times.
+///
+/// A context caches the compiled [`ContractionPlan`] of every network it has contracted in a
+/// [`PlanCache`], and keeps a pool of buffers for the intermediates. The intermediate of each
+/// pairwise step is written into a recycled buffer, and the operands it consumed are returned
+/// to the pool, so a sweep contracting the same network over and over no longer allocates and
+/// frees every intermediate. Results handed back with [`ContractionContext::recycle`] are
+/// reused as well.
+///
+/// Sliced contractions and parallel branches are executed without the pool.
+#[derive(Clone, Debug)]
+pub struct ContractionContext<A: Scalar = f64> {
+    plans: PlanCache,
+    pool: Vec<Vec<A>>,
+}
+
+impl<A: Scalar> Default for ContractionContext<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Scalar> ContractionContext<A> {
+    /// Creates an empty context that plans contractions with the default options.
+    pub fn new() -> Self {
+        Self::with_options(ContractOptions::default())
+    }
+
+    /// Creates an empty context that plans contractions with the given options.
+    ///
+    /// # Arguments
+    /// - `options`: The [`ContractOptions`] used to compile every plan of the context.
+    pub fn with_options(options: ContractOptions) -> Self {
+        ContractionContext {
+            plans: PlanCache::with_options(DEFAULT_PLAN_CAPACITY, options),
+            pool: Vec::new(),
+        }
+    }
+
+    /// Contracts a list of tensors, reusing the cached plan and the buffer pool.
+    ///
+    /// # Arguments
+    /// - `tensors`: A slice of `ArrayD<A>` or `ArrayViewD<A>` representing the tensors to be
+    ///   contracted.
+    /// - `contraction_order`: A vector of vectors containing integers that specify the order of
+    ///   contraction, following the same convention as [`contract`].
+    ///
+    /// # Returns
+    /// A `Result<ArrayD<A>, QtnError>` where:
+    /// - `Ok(ArrayD<A>)` contains the resulting tensor, as returned by [`contract_with`].
+    /// - `Err(QtnError)` contains the error if the contraction fails.
+    ///
+    /// # Errors
+    /// This function may return the same errors as [`contract_with`].
+    pub fn contract<S: Data<Elem = A>>(
+        &mut self,
+        tensors: &[ArrayBase<S, IxDyn>],
+        contraction_order: &[&[i32]],
+    ) -> Result<ArrayD<A>, QtnError> {
+        let shapes: Vec<Vec<usize>> = tensors.iter().map(|t| t.shape().to_vec()).collect();
+        let plan = self.plans.get(&shapes, contraction_order)?;
+        execute_pooled(plan, tensors, &mut self.pool)
+    }
+
+    /// Contracts a list of tensors with a given plan, reusing the buffer pool.
+    ///
+    /// # Arguments
+    /// - `plan`: The compiled [`ContractionPlan`].
+    /// - `tensors`: The tensors to be contracted, with the shapes the plan was compiled for.
+    ///
+    /// # Returns
+    /// A `Result<ArrayD<A>, QtnError>` containing the result, as returned by
+    /// [`ContractionPlan::execute`], or the errors of that method.
+    pub fn execute<S: Data<Elem = A>>(
+        &mut self,
+        plan: &ContractionPlan,
+        tensors: &[ArrayBase<S, IxDyn>],
+    ) -> Result<ArrayD<A>, QtnError> {
+        execute_pooled(plan, tensors, &mut self.pool)
+    }
+
+    /// Hands a tensor that is no longer needed, e.g. the result of a previous iteration, back to
+    /// the buffer pool.
+    pub fn recycle(&mut self, tensor: ArrayD<A>) {
+        release(&mut self.pool, tensor);
+    }
+
+    /// Returns the number of buffers currently held for reuse.
+    pub fn pooled_buffers(&self) -> usize {
+        self.pool.len()
+    }
+
+    /// Returns the number of cached plans.
+    pub fn cached_plans(&self) -> usize {
+        self.plans.len()
+    }
+
+    /// Returns the plan cache of the context, e.g. to change its capacity.
+    pub fn plan_cache(&mut self) -> &mut PlanCache {
+        &mut self.plans
+    }
+
+    /// Drops all cached plans and pooled buffers.
+    pub fn clear(&mut self) {
+        self.plans.clear();
+        self.pool.clear();
+    }
+}
+
+/// Executes a plan, writing every intermediate into a buffer taken from `pool` and returning the
+/// consumed operands to it.
+fn execute_pooled<A, S>(
+    plan: &ContractionPlan,
+    tensors: &[ArrayBase<S, IxDyn>],
+    pool: &mut Vec<Vec<A>>,
+) -> Result<ArrayD<A>, QtnError>
+where
+    A: Scalar,
+    S: Data<Elem = A>,
+{
+    if !plan.slice_dims.is_empty() || plan.parallel_branches {
+        return plan.execute(tensors);
+    }
+
+    let mut tensor_list = plan.traced_inputs(tensors)?;
+    for step in &plan.steps {
+        let [i, j] = step.pair;
+        let product = if step.kernel == PairKernel::Gemm {
+            let mut buffer = acquire(pool, step.m * step.n);
+            {
+                let a = tensor_list[i].view().permuted_axes(IxDyn(&step.perm_a));
+                let b = tensor_list[j].view().permuted_axes(IxDyn(&step.perm_b));
+                let a = matrix(a, (step.m, step.k));
+                let b = matrix(b, (step.k, step.n));
+                let out = ArrayViewMut2::from_shape((step.m, step.n), &mut buffer)?;
+                plan.gemm_into(&a.view(), &b.view(), out);
+            }
+            ArrayD::from_shape_vec(IxDyn(&step.shape), buffer)?
+        } else {
+            plan.run_step(step, &tensor_list[i], &tensor_list[j])?
+        };
+
+        let a = std::mem::replace(&mut tensor_list[i], CowArray::from(product));
+        let b = tensor_list.remove(j);
+        for operand in [a, b] {
+            if operand.is_owned() {
+                release(pool, operand.into_owned());
+            }
+        }
+    }
+
+    let tensor = tensor_list.remove(0).into_owned();
+    let tensor = tensor.permuted_axes(IxDyn(&plan.output_axes));
+    plan.verify(tensors, tensor.view())?;
+    Ok(tensor)
+}
+
+/// Takes a buffer of exactly `len` elements from the pool, preferring the smallest one that is
+/// large enough, or allocates a new one.
+fn acquire<A: Scalar>(pool: &mut Vec<Vec<A>>, len: usize) -> Vec<A> {
+    let fit = (0..pool.len())
+        .filter(|&k| pool[k].capacity() >= len)
+        .min_by_key(|&k| pool[k].capacity());
+    let mut buffer = match fit {
+        Some(k) => pool.swap_remove(k),
+        None => Vec::with_capacity(len),
+    };
+    // Entries beyond the current length are initialized once; the product overwrites them all
+    if buffer.len() >= len {
+        buffer.truncate(len);
+    } else {
+        buffer.resize(len, A::zero());
+    }
+    buffer
+}
+
+/// Returns the buffer of a tensor to the pool, dropping the smallest buffer if the pool is full.
+fn release<A: Scalar>(pool: &mut Vec<Vec<A>>, tensor: ArrayD<A>) {
+    let (buffer, _) = tensor.into_raw_vec_and_offset();
+    pool.push(buffer);
+    if pool.len() > MAX_POOLED {
+        let smallest = (0..pool.len())
+            .min_by_key(|&k| pool[k].capacity())
+            .expect("The pool is not empty");
+        pool.swap_remove(smallest);
+    }
+}
+
+/// Chooses contracted indices to slice over so that no intermediate exceeds a memory limit.
+///
+/// The path is kept fixed. While the largest intermediate is above the limit, one more
+/// contracted index is sliced, chosen according to `strategy`.
+///
+/// # Arguments
+/// - `order`: The contraction indices of each tensor, after traces have been removed.
+/// - `dims`: The dimension of every index.
+/// - `path`: The contraction path.
+/// - `limit`: The largest allowed number of elements of an intermediate.
+/// - `strategy`: The [`SliceStrategy`] used to choose the next index.
+///
+/// # Returns
+/// A `Result<Vec<i32>, QtnError>` where:
+/// - `Ok(Vec<i32>)` contains the indices to slice, empty if the path already fits.
+/// - `Err(QtnError::MemoryLimitExceeded)` if the limit cannot be reached by slicing contracted
+///   indices.
+fn slice_selection(
+    order: &[Vec<i32>],
+    dims: &HashMap<i32, usize>,
+    path: &[Vec<usize>],
+    limit: usize,
+    strategy: SliceStrategy,
+) -> Result<Vec<i32>, QtnError> {
+    let excess = |tree: &[(Vec<i32>, usize)]| -> usize {
+        tree.iter()
+            .map(|&(_, size)| size.saturating_sub(limit))
+            .sum()
+    };
+    let largest = |tree: &[(Vec<i32>, usize)]| tree.iter().map(|&(_, size)| size).max();
+
+    let mut candidates: Vec<i32> = order.iter().flatten().cloned().filter(|&l| l > 0).collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+
+    let mut sliced: Vec<i32> = Vec::new();
+    loop {
+        let (tree, _) = tree_cost(order, dims, path, &sliced);
+        let current = largest(&tree).unwrap_or(0);
+        if current <= limit {
+            return Ok(sliced);
+        }
+
+        let remaining = candidates.iter().filter(|l| !sliced.contains(*l));
+        let trial = |l: i32| tree_cost(order, dims, path, &[sliced.as_slice(), &[l]].concat());
+        let best = match strategy {
+            SliceStrategy::Overhead => {
+                let before = excess(&tree);
+                remaining
+                    .filter_map(|&l| {
+                        let (tree, flops) = trial(l);
+                        let after = excess(&tree);
+                        (after < before).then_some((flops, after, l))
+                    })
+                    .min_by(|(f1, e1, l1), (f2, e2, l2)| {
+                        f1.total_cmp(f2).then(e1.cmp(e2)).then(l1.cmp(l2))
+                    })
+                    .map(|(_, _, l)| l)
+            }
+            SliceStrategy::Memory => remaining
+                .map(|&l| {
+                    let (tree, flops) = trial(l);
+                    (largest(&tree).unwrap_or(0), flops, l)
+                })
+                .min_by(|(s1, f1, l1), (s2, f2, l2)| {
+                    s1.cmp(s2).then(f1.total_cmp(f2)).then(l1.cmp(l2))
+                })
+                .map(|(_, _, l)| l),
+            SliceStrategy::Betweenness => remaining
+                .filter_map(|&l| {
+                    // How far the intermediates carrying the index exceed the limit
+                    let score: f64 = tree
+                        .iter()
+                        .filter(|(legs, size)| *size > limit && legs.contains(&l))
+                        .map(|&(_, size)| (size as f64 / limit.max(1) as f64).ln())
+                        .sum();
+                    (score > 0.0).then_some((score, l))
+                })
+                .max_by(|(s1, l1), (s2, l2)| s1.total_cmp(s2).then(l2.cmp(l1)))
+                .map(|(_, l)| l),
+        };
+
+        match best {
+            Some(leg) => sliced.push(leg),
+            None => {
+                return Err(QtnError::MemoryLimitExceeded {
+                    limit,
+                    required: current,
+                })
+            }
+        }
+    }
+}
+
+/// Intermediates of a contraction tree with some indices sliced, and the multiply-adds over all
+/// slices.
+///
+/// # Arguments
+/// - `order`: The contraction indices of each tensor, after traces have been removed.
+/// - `dims`: The dimension of every index.
+/// - `path`: The contraction path.
+/// - `sliced`: The sliced indices, which count with dimension one within a slice.
+///
+/// # Returns
+/// A tuple of the legs and the number of elements of the intermediate produced by every step,
+/// and the total number of multiply-adds of all slices.
+fn tree_cost(
+    order: &[Vec<i32>],
+    dims: &HashMap<i32, usize>,
+    path: &[Vec<usize>],
+    sliced: &[i32],
+) -> (Vec<(Vec<i32>, usize)>, f64) {
+    let dim = |l: &i32| if sliced.contains(l) { 1 } else { dims[l] };
+    let mut legs = order.to_vec();
+    let mut tree = Vec::with_capacity(path.len());
+    let mut flops = 0.0;
+    for pair in path {
+        let (a, b) = (&legs[pair[0]], &legs[pair[1]]);
+        let all: f64 = a
+            .iter()
+            .chain(b.iter().filter(|l| !a.contains(l)))
+            .map(|l| dim(l) as f64)
+            .product();
+        let result: Vec<i32> = a
+            .iter()
+            .filter(|l| !b.contains(l))
+            .chain(b.iter().filter(|l| !a.contains(l)))
+            .cloned()
+            .collect();
+        let size = result.iter().map(dim).product::<usize>();
+        flops += all;
+        legs[pair[0]] = result.clone();
+        legs.remove(pair[1]);
+        tree.push((result, size));
+    }
+    let slices: f64 = sliced.iter().map(|l| dims[l] as f64).product();
+    (tree, flops * slices)
+}
+
+/// Checks that a requested output ordering lists every free index exactly once.
+///
+/// # Arguments
+/// - `order`: A slice of vectors containing the contraction indices of each tensor.
+/// - `out_order`: The requested order of the free indices in the result.
+///
+/// # Returns
+/// A `Result<(), QtnError>` where:
+/// - `Ok(())` indicates that `out_order` is a permutation of the negative indices of `order`.
+/// - `Err(QtnError::InvalidOutputOrder)` otherwise.
+fn output_validation(order: &[Vec<i32>], out_order: &[i32]) -> Result<(), QtnError> {
+    let mut free: Vec<i32> = order.iter().flatten().cloned().filter(|&x| x < 0).collect();
+    let mut requested = out_order.to_vec();
+    free.sort_unstable();
+    requested.sort_unstable();
+
+    if free != requested {
+        return Err(QtnError::InvalidOutputOrder(out_order.to_vec()));
+    }
+    Ok(())
+}
+
+/// Checks that every tensor has one contraction index per leg.
+///
+/// # Arguments
+/// - `shapes`: A slice of the shapes of the tensors to be contracted.
+/// - `order`: A slice of vectors containing the contraction indices of each tensor.
+///
+/// # Returns
+/// A `Result<(), QtnError>` where:
+/// - `Ok(())` indicates that the number of tensors and the ranks match the contraction order.
+/// - `Err(QtnError)` is [`QtnError::EmptyNetwork`], [`QtnError::OrderCountMismatch`] or
+///   [`QtnError::RankMismatch`], depending on the first inconsistency found.
+pub(crate) fn rank_validation(shapes: &[Vec<usize>], order: &[Vec<i32>]) -> Result<(), QtnError> {
+    if shapes.is_empty() {
+        return Err(QtnError::EmptyNetwork);
+    }
+    if shapes.len() != order.len() {
+        return Err(QtnError::OrderCountMismatch {
+            tensors: shapes.len(),
+            orders: order.len(),
+        });
+    }
+    for (k, (shape, indices)) in shapes.iter().zip(order).enumerate() {
+        if shape.len() != indices.len() {
+            return Err(QtnError::RankMismatch {
+                tensor: k,
+                rank: shape.len(),
+                order_len: indices.len(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the legs joined by each positive index have the same dimension.
+///
+/// # Arguments
+/// - `shapes`: A slice of the shapes of the tensors to be contracted.
+/// - `order`: A slice of vectors containing the contraction indices of each tensor. The ranks
+///   must already have been checked with [`rank_validation`].
+///
+/// # Returns
+/// A `Result<(), QtnError>` where:
+/// - `Ok(())` indicates that all shared legs have matching dimensions.
+/// - `Err(QtnError::ShapeMismatch)` names the two legs of the first mismatching index.
+pub(crate) fn shape_validation(shapes: &[Vec<usize>], order: &[Vec<i32>]) -> Result<(), QtnError> {
+    let mut legs: HashMap<i32, (usize, usize)> = HashMap::new();
+    for (k, indices) in order.iter().enumerate() {
+        for (axis, &index) in indices.iter().enumerate() {
+            if index < 0 {
+                continue;
+            }
+            match legs.get(&index) {
+                None => {
+                    legs.insert(index, (k, axis));
+                }
+                Some(&(other, other_axis)) => {
+                    let dim = shapes[k][axis];
+                    let other_dim = shapes[other][other_axis];
+                    if dim != other_dim {
+                        return Err(QtnError::ShapeMismatch {
+                            tensor: other,
+                            axis: other_axis,
+                            dim: other_dim,
+                            other_tensor: k,
+                            other_axis: axis,
+                            other_dim: dim,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
 }
 
 /// Validates the indices in the contraction order for tensor operations.
@@ -67,15 +2110,15 @@ pub fn contract(
 ///   order for the tensors. Each inner vector represents the order of dimensions for a specific tensor.
 ///
 /// # Returns
-/// A `Result<(), String>` where:
+/// A `Result<(), QtnError>` where:
 /// - `Ok(())` indicates that the validation was successful and all indices are valid.
-/// - `Err(String)` contains an error message if any index fails the validation checks.
+/// - `Err(QtnError::InvalidContractionOrder)` names the first index that fails the validation checks.
 ///
 /// # Notes
 /// This function is crucial for ensuring that tensor contractions are performed correctly,
 /// preventing runtime errors due to invalid index configurations. It should be called before
 /// attempting to perform any tensor contractions.
-fn indices_validation(order: &[Vec<i32>]) -> Result<(), String> {
+pub(crate) fn indices_validation(order: &[Vec<i32>]) -> Result<(), QtnError> {
     let mut counts = HashMap::new();
 
     // Count how many times each index appears in the contraction order
@@ -85,65 +2128,51 @@ fn indices_validation(order: &[Vec<i32>]) -> Result<(), String> {
     for (&key, &count) in &counts {
         // Check if positive indices appear exactly twice
         if key > 0 && count != 2 {
-            return Err(format!(
-                "Index {} must appear exactly twice in contraction order list.",
-                key
-            ));
+            return Err(QtnError::InvalidContractionOrder { index: key, count });
         }
 
         // Check if negative indices appear at most once
         if key < 0 && count > 1 {
-            return Err(format!(
-                "Index {} must appear at most once in contraction order list.",
-                key
-            ));
+            return Err(QtnError::InvalidContractionOrder { index: key, count });
+        }
+
+        // Zero is neither a free nor a contracted leg
+        if key == 0 {
+            return Err(QtnError::InvalidContractionOrder { index: key, count });
         }
     }
     Ok(())
 }
 
-/// Prepares the tensors and contraction orders for tensor contraction operations.
+/// Prepares the shapes and contraction orders of a network for path planning.
 ///
-/// This function modifies the input tensors and their corresponding contraction orders to ensure
-/// that they are compatible for contraction. It performs the following tasks:
-/// - Expands the shapes of the tensors to include extra dimensions, ensuring that all tensors
-///   have compatible shapes for the contraction process.
+/// This function connects every tensor to the last one through an extra leg of dimension one, so
+/// that the planner always finds a connected network. It performs the following tasks:
+/// - Expands the shapes of the tensors to include the extra dimensions.
 /// - Updates the contraction orders to reflect the new shapes of the tensors, adding new indices
 ///   for the extra dimensions.
 ///
 /// # Arguments
-/// - `tensors`: A mutable reference to a vector of `ArrayD<f64>` representing the tensors to be
-///   contracted. This will be modified to include extra dimensions as needed.
+/// - `shapes`: A mutable reference to the shapes of the tensors to be contracted. This will be
+///   modified to include extra dimensions as needed.
 /// - `orders`: A mutable reference to a vector of vectors containing integers that specify the
 ///   order of dimensions for each tensor. This will be updated to reflect the new contraction
 ///   orders after processing.
 ///
 /// # Notes
-/// This function assumes that the contraction orders are valid and that the tensors are properly
-/// initialized. It modifies the tensors and orders in place, so the original vectors will be
-/// updated directly. This function should be called before performing any tensor contractions.
-fn prepare_contraction_data(tensors: &mut [ArrayD<f64>], orders: &mut Vec<Vec<i32>>) {
-    let max_idx = orders.iter().flatten().cloned().max().unwrap_or(0);
-    let ten_len = tensors.len();
-    let extra_dims: Vec<usize> = vec![1; ten_len - 1];
-
-    // Expand the shape of each tensor except the last one to include an extra dimension
-    for i in 0..ten_len - 1 {
-        tensors[i] = tensors[i]
-            .to_shape([tensors[i].shape(), &[1]].concat())
-            .unwrap()
-            .to_owned();
-    }
-
-    let last_shape = tensors.last().unwrap().shape().to_vec();
-
-    // Expand the last tensor's shape to include extra dimensions
-    tensors[ten_len - 1] = tensors
-        .last_mut()
-        .unwrap()
-        .to_shape([&last_shape, &extra_dims[..]].concat())
-        .unwrap()
-        .to_owned();
+/// This function assumes that the contraction orders are valid and that the network has at least
+/// one tensor. The extra legs only affect planning: they have dimension one and are never
+/// materialized on the tensor data.
+fn prepare_contraction_data(shapes: &mut [Vec<usize>], orders: &mut Vec<Vec<i32>>) {
+    let max_idx = orders.iter().flatten().cloned().max().unwrap_or(0).max(0);
+    let ten_len = shapes.len();
+
+    // Expand the shape of each tensor except the last one to include an extra dimension, and the
+    // last tensor's shape to include all of them
+    for shape in shapes.iter_mut().take(ten_len - 1) {
+        shape.push(1);
+    }
+    shapes[ten_len - 1].extend(std::iter::repeat_n(1, ten_len - 1));
 
     let mut new_orders = Vec::new();
     let mut new_dims = Vec::new();
@@ -168,7 +2197,7 @@ fn prepare_contraction_data(tensors: &mut [ArrayD<f64>], orders: &mut Vec<Vec<i3
 /// continues to generate pairs until only one tensor remains.
 ///
 /// # Arguments
-/// - `tensors`: A reference to a slice of `ArrayD<f64>` representing the tensors to be contracted.
+/// - `tensors`: A reference to a slice of `ArrayD<A>` representing the tensors to be contracted.
 /// - `orders`: A reference to a vector of vectors containing integers that specify the order of
 ///   dimensions for each tensor. This is used to guide the contraction process.
 ///
@@ -180,8 +2209,12 @@ fn prepare_contraction_data(tensors: &mut [ArrayD<f64>], orders: &mut Vec<Vec<i3
 /// This function assumes that the shapes and orders of the tensors are valid and that the
 /// tensors are properly initialized. The contraction plan generated by this function should be
 /// used to guide the actual contraction operations in a subsequent step.
-pub fn contract_map(tensors: &[ArrayD<f64>], orders: &[Vec<i32>]) -> Vec<Vec<usize>> {
-    let mut shapes = shape_vec(tensors);
+pub fn contract_map<A>(tensors: &[ArrayD<A>], orders: &[Vec<i32>]) -> Vec<Vec<usize>> {
+    greedy_map(shape_vec(tensors), orders)
+}
+
+/// Generates the greedy contraction plan of [`contract_map`] from the tensor shapes alone.
+pub(crate) fn greedy_map(mut shapes: Vec<Vec<i32>>, orders: &[Vec<i32>]) -> Vec<Vec<usize>> {
     let mut contraction_orders = orders.to_vec();
     let mut plan = Vec::new();
 
@@ -196,51 +2229,6 @@ pub fn contract_map(tensors: &[ArrayD<f64>], orders: &[Vec<i32>]) -> Vec<Vec<usi
     plan
 }
 
-/// Checks for indices that need to be traced in the given tensor and performs the trace operation.
-///
-/// The function identifies pairs of indices in the contraction order that appear exactly twice,
-/// indicating that a trace operation should be performed. It then traces the tensor along these
-/// indices and removes them from the contraction order.
-///
-/// # Arguments
-/// - `tensor`: A mutable reference to an `ArrayD<f64>` representing the tensor to be traced.
-/// - `order`: A mutable reference to a vector of integers representing the contraction order of the tensor.
-///
-/// # Returns
-/// - `Result<(), String>`: Returns `Ok(())` if the trace operation is successful. If an error occurs,
-///   it returns an `Err` containing a descriptive error message.
-///
-/// # Errors
-/// This function may return an error if the trace operation cannot be performed due to invalid
-/// indices or if the tensor does not have the required dimensions for tracing.
-///
-/// # Note
-/// The function modifies the input tensor and contraction order in place. It is important to ensure
-/// that the contraction order is correctly specified before calling this function, as incorrect
-/// orders may lead to runtime errors or unexpected behavior. Additionally, the tensor must have
-/// dimensions that correspond to the indices being traced.
-fn trace_check(tensor: &mut ArrayD<f64>, order: &mut Vec<i32>) -> Result<(), String> {
-    let mut index_map = HashMap::new();
-    for (i, &val) in order.iter().enumerate() {
-        index_map.entry(val).or_insert_with(Vec::new).push(i);
-    }
-
-    // Check for pairs of indices that need to be traced
-    for indices in index_map.values() {
-        // If an index appears exactly twice, it indicates a trace operation
-        if indices.len() == 2 {
-            let trace_axes: Vec<usize> = indices.iter().map(|&i| i).collect();
-            *tensor = trace(tensor, trace_axes)?;
-
-            // Remove the traced indices from the order
-            for &i in indices.iter().rev() {
-                order.remove(i);
-            }
-        }
-    }
-    Ok(())
-}
-
 /// Computes a matrix of ratios representing the shared dimensions between pairs of tensors,
 /// along with a vector of row sums.
 ///
@@ -347,49 +2335,6 @@ fn select_best_nodes(matrix: &Array2<f64>, scores: &[f64]) -> Vec<usize> {
     vec![i, j]
 }
 
-/// Converts contraction orders into indices for shared dimensions between two specified orders.
-///
-/// This function identifies the common indices shared between two contraction orders and returns
-/// their positions in the respective orders. It is useful for determining which dimensions are
-/// involved in a contraction operation.
-///
-/// # Arguments
-/// - `order_list`: A slice of vectors representing the contraction orders for each tensor.
-/// - `indexes`: A slice of indices indicating which two orders to compare.
-///
-/// # Returns
-/// - `Vec<usize>`: A vector containing the indices of the common dimensions in the specified
-///   contraction orders.
-///
-/// # Note
-/// The function assumes that the provided indexes correspond to valid contraction orders in the
-/// `order_list`. If the specified orders do not share any common indices, the resulting vector
-/// will be empty. Care should be taken to ensure that the orders being compared are relevant to
-/// the contraction operation being performed.
-fn order_to_index(order_list: &[Vec<i32>], indexes: &[usize]) -> Vec<usize> {
-    // Create a set of common indices shared between the two specified orders
-    let common: HashSet<i32> = order_list[indexes[0]]
-        .iter()
-        .cloned()
-        .collect::<HashSet<_>>()
-        .intersection(&order_list[indexes[1]].iter().cloned().collect())
-        .cloned()
-        .collect();
-
-    let mut idx = vec![];
-
-    // Iterate over the specified indexes to find their positions in the order list
-    for i in indexes {
-        for ce in &common {
-            if let Some(ndx) = order_list[*i].iter().position(|value| value == ce) {
-                idx.push(ndx);
-            }
-        }
-    }
-
-    idx
-}
-
 /// Merges and reformats the contraction orders after a contraction operation.
 ///
 /// This function combines the contraction orders of two tensors that have been contracted,
@@ -494,7 +2439,7 @@ fn shape_reformat(shapes: &mut Vec<Vec<i32>>, orders: &[Vec<i32>], indices: &[us
 /// in a format that is easier to work with during contraction operations.
 ///
 /// # Arguments
-/// - `tensors`: A slice of `ArrayD<f64>` representing the tensors whose shapes are to be extracted.
+/// - `tensors`: A slice of `ArrayD<A>` representing the tensors whose shapes are to be extracted.
 ///
 /// # Returns
 /// - `Vec<Vec<i32>>`: A vector of vectors, where each inner vector contains the dimensions of
@@ -505,41 +2450,37 @@ fn shape_reformat(shapes: &mut Vec<Vec<i32>>, orders: &[Vec<i32>], indices: &[us
 /// vector of shapes will have the same length as the input tensor slice, and each inner vector
 /// will correspond to the dimensions of the respective tensor. This format is particularly useful
 /// for operations that require knowledge of tensor dimensions, such as contraction and reshaping.
-fn shape_vec(tensors: &[ArrayD<f64>]) -> Vec<Vec<i32>> {
+fn shape_vec<A>(tensors: &[ArrayD<A>]) -> Vec<Vec<i32>> {
     tensors
         .iter()
         .map(|t| t.shape().iter().map(|&d| d as i32).collect())
         .collect()
 }
 
-/// Rearranges the axes of the final tensor based on the contraction order.
+/// Finds the traces within a tensor and removes the traced indices from its contraction order.
 ///
-/// This function takes a tensor and its contraction order, sorts the order, and permutes the
-/// tensor's axes accordingly. The resulting tensor will have its dimensions arranged in a
-/// specified order, which is important for ensuring that the output tensor matches the expected
-/// layout after contraction.
+/// Pairs of equal indices are resolved one at a time, so that the axis positions of each pair
+/// refer to the tensor after the previous traces have been applied.
 ///
 /// # Arguments
-/// - `tensor`: An `ArrayD<f64>` representing the final contracted tensor to be rearranged.
-/// - `order`: A vector of vectors representing the contraction order, which indicates how the
-///   dimensions should be permuted.
+/// - `order`: A mutable reference to the contraction order of the tensor.
 ///
 /// # Returns
-/// - `ArrayD<f64>`: The tensor with its axes permuted according to the specified order.
-///
-/// # Note
-/// The function assumes that the contraction order is valid and corresponds to the dimensions of
-/// the input tensor. The output tensor will have its axes rearranged based on the sorted order,
-/// which is crucial for maintaining the correct structure of the tensor after contraction. Care
-/// should be taken to ensure that the order provided accurately reflects the desired output layout.
-fn final_order(tensor: ArrayD<f64>, order: Vec<Vec<i32>>) -> ArrayD<f64> {
-    let mut sorted = order[0].clone();
-    sorted.sort_by(|a, b| b.cmp(a));
-
-    let axis_order: Vec<_> = sorted
-        .iter()
-        .map(|x| order[0].iter().position(|y| y == x).unwrap())
-        .collect();
+/// - `Vec<[usize; 2]>`: The axis pairs to trace, in the order in which they must be applied.
+fn trace_pairs(order: &mut Vec<i32>) -> Vec<[usize; 2]> {
+    let mut pairs = Vec::new();
+
+    // If an index appears twice on the same tensor, it indicates a trace operation
+    while let Some((i, j)) = (0..order.len()).find_map(|i| {
+        (i + 1..order.len())
+            .find(|&j| order[j] == order[i])
+            .map(|j| (i, j))
+    }) {
+        pairs.push([i, j]);
 
-    tensor.permuted_axes(IxDyn(&axis_order))
+        // Remove the traced indices from the order
+        order.remove(j);
+        order.remove(i);
+    }
+    pairs
 }
@@ -1,66 +1,234 @@
-use ndarray::{Array2, ArrayD, Axis, IxDyn};
+use crate::error::QtnError;
+use crate::scalar::{splitter, ExtendedSum, Scalar};
+use ndarray::linalg::general_mat_mul;
+use ndarray::{
+    Array2, ArrayD, ArrayView1, ArrayView2, ArrayViewD, ArrayViewMut2, Axis, CowArray, Ix2, IxDyn,
+};
+use std::thread;
+
+/// Largest contracted dimension handled by the unrolled kernel instead of a GEMM call.
+const SMALL_K_MAX: usize = 4;
+
+/// Number of multiply-adds below which the deterministic kernel runs on a single thread.
+const PARALLEL_MIN_WORK: usize = 1 << 16;
+
+/// Contracted axes of a tensor dot product, following the `axes` argument of
+/// `numpy.tensordot`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Axes {
+    /// Contracts the last `k` axes of the first tensor with the first `k` axes of the second
+    /// one, in order. `Last(0)` gives the outer product.
+    Last(usize),
+    /// Contracts each axis of the first list (of the first tensor) with the axis at the same
+    /// position of the second list (of the second tensor).
+    Pairs(Vec<usize>, Vec<usize>),
+    /// The axes of the first tensor followed by the paired axes of the second one, i.e. the
+    /// rows of a row-major `2 x k` matrix. This is the original form of [`tensor_dot`], which a
+    /// `Vec<usize>` converts into.
+    Interleaved(Vec<usize>),
+}
+
+impl From<Vec<usize>> for Axes {
+    fn from(axis_vec: Vec<usize>) -> Self {
+        Axes::Interleaved(axis_vec)
+    }
+}
 
 /// Computes the tensor dot product of two tensors along specified axes.
 ///
-/// This function takes two tensors and a vector of axes to contract over. The axes
-/// must be specified in pairs, where each pair consists of an axis from the first
-/// tensor and an axis from the second tensor. The function checks for shape compatibility
+/// This function takes two tensors and the axes to contract over, following the `axes`
+/// argument of `numpy.tensordot`: `Axes::Last(k)` contracts the last `k` axes of the first
+/// tensor with the first `k` axes of the second one, and `Axes::Pairs(axes_a, axes_b)` pairs
+/// the listed axes of both tensors position by position. A plain `Vec<usize>` is still accepted
+/// as the interleaved list [`Axes::Interleaved`]. The function checks for shape compatibility
 /// along the specified axes and performs the dot product accordingly.
 ///
 /// # Parameters
 ///
-/// - `a`: A reference to a tensor of type `ArrayD<f64>`. This is the first tensor
+/// - `a`: A reference to a tensor of type `ArrayD<A>`, where `A` is any `ndarray` linear algebra
+///   scalar (e.g. `f32`, `f64`, `Complex32` or `Complex64`). This is the first tensor
 ///   involved in the dot product.
-/// - `b`: A reference to a tensor of type `ArrayD<f64>`. This is the second tensor
+/// - `b`: A reference to a tensor of type `ArrayD<A>`. This is the second tensor
 ///   involved in the dot product.
-/// - `axis_vec`: A vector of `usize` representing the axes to contract over. The length
-///   of this vector must be even, as it specifies pairs of axes (one from `a` and one from `b`).
+/// - `axes`: The axes to contract over, e.g. `Axes::Last(1)` for a matrix product,
+///   `Axes::Pairs(vec![0, 2], vec![1, 0])` or the interleaved `vec![0, 2, 1, 0]`. No contracted
+///   axes, as in `Axes::Last(0)` or an empty vector, give the outer product, see [`outer`].
 ///
 /// # Returns
 ///
-/// - `Result<ArrayD<f64>, String>`: Returns a `Result` containing either:
-///   - `Ok(ArrayD<f64>)`: The resulting tensor after performing the dot product.
-///   - `Err(String)`: An error message if the input is invalid or if there is a shape mismatch
+/// - `Result<ArrayD<A>, QtnError>`: Returns a `Result` containing either:
+///   - `Ok(ArrayD<A>)`: The resulting tensor after performing the dot product, with the
+///     remaining axes of `a` followed by the remaining axes of `b`.
+///   - `Err(QtnError)`: An error if the input is invalid or if there is a shape mismatch
 ///     along the specified axes.
 ///
 /// # Errors
 ///
 /// The function may return an error in the following cases:
-/// - If the length of `axis_vec` is not an even number.
-/// - If the shapes of the specified axes in tensors `a` and `b` do not match.
-pub fn tensor_dot(
-    a: &ArrayD<f64>,
-    b: &ArrayD<f64>,
-    axis_vec: Vec<usize>,
-) -> Result<ArrayD<f64>, String> {
-    // Check if the length of axis_vec is even
-    if axis_vec.len() % 2 != 0 {
-        return Err("Axis length is not even number!".to_string());
+/// - [`QtnError::OddAxisCount`] if an interleaved list does not have an even length.
+/// - [`QtnError::InvalidArgument`] if an axis is out of range or repeated, `Last(k)` exceeds
+///   the rank of a tensor or the two lists of `Pairs` have different lengths.
+/// - [`QtnError::ShapeMismatch`] if the shapes of the specified axes in tensors `a` (tensor 0)
+///   and `b` (tensor 1) do not match.
+pub fn tensor_dot<A: Scalar>(
+    a: &ArrayD<A>,
+    b: &ArrayD<A>,
+    axes: impl Into<Axes>,
+) -> Result<ArrayD<A>, QtnError> {
+    let (a_reshape, b_reshape, shape) = dot_operands(a, b, axes.into())?;
+
+    // Compute the dot product of the reshaped matrices
+    let res = matmul(&a_reshape.view(), &b_reshape.view());
+
+    let output = res
+        .to_shape(shape)
+        .expect("Failed to reshape output")
+        .into_owned();
+
+    Ok(output)
+}
+
+/// Computes the tensor dot product of two tensors and writes it into an existing tensor.
+///
+/// This is [`tensor_dot`] without allocating the result, for loops that repeatedly compute
+/// products of the same shape. The previous entries of `out` are overwritten.
+///
+/// # Parameters
+///
+/// - `a`: A reference to the first tensor.
+/// - `b`: A reference to the second tensor.
+/// - `axes`: The axes to contract over, as in [`tensor_dot`].
+/// - `out`: The tensor receiving the result. It must have the shape of the result of
+///   [`tensor_dot`]. If it is in standard (row-major) layout, the product is written into it
+///   directly; otherwise it is computed separately and copied.
+///
+/// # Returns
+///
+/// - `Result<(), QtnError>`: `Ok(())` once `out` holds the product, or an error.
+///
+/// # Errors
+///
+/// The function may return the same errors as [`tensor_dot`], and
+/// [`QtnError::InvalidArgument`] if `out` does not have the shape of the result.
+pub fn tensor_dot_into<A: Scalar>(
+    a: &ArrayD<A>,
+    b: &ArrayD<A>,
+    axes: impl Into<Axes>,
+    out: &mut ArrayD<A>,
+) -> Result<(), QtnError> {
+    let (a_reshape, b_reshape, shape) = dot_operands(a, b, axes.into())?;
+    if out.shape() != shape.as_slice() {
+        return Err(QtnError::InvalidArgument(format!(
+            "Output has shape {:?}, but the product has shape {:?}.",
+            out.shape(),
+            shape
+        )));
     }
 
-    // Create a 2D array from axis_vec to separate axes for a and b
-    let axis = Array2::from_shape_vec((2, axis_vec.len() / 2), axis_vec).unwrap();
-    let axes_a = axis.index_axis(Axis(0), 0).to_vec();
-    let axes_b = axis.index_axis(Axis(0), 1).to_vec();
+    let (a, b) = (a_reshape.view(), b_reshape.view());
+    match out.view_mut().into_shape_with_order((a.nrows(), b.ncols())) {
+        Ok(out) => matmul_into(&a, &b, out),
+        Err(_) => out.assign(&matmul(&a, &b).into_shape_clone(IxDyn(&shape))?),
+    }
+    Ok(())
+}
+
+/// Computes the outer (tensor) product of two tensors.
+///
+/// The axes of the result are the axes of `a` followed by the axes of `b`, and every element is
+/// the product of one element of each tensor. This is the tensor dot product without contracted
+/// axes, `tensor_dot(a, b, vec![])`, as used to assemble product states, projectors and
+/// disconnected parts of a network.
+///
+/// # Parameters
+///
+/// - `a`: A reference to the first tensor, of shape `(d_1, ..., d_n)`.
+/// - `b`: A reference to the second tensor, of shape `(e_1, ..., e_m)`.
+///
+/// # Returns
+///
+/// - `ArrayD<A>`: The tensor of shape `(d_1, ..., d_n, e_1, ..., e_m)` with the elements
+///   `a[i] b[j]`.
+pub fn outer<A: Scalar>(a: &ArrayD<A>, b: &ArrayD<A>) -> ArrayD<A> {
+    let shape = [a.shape(), b.shape()].concat();
+    let values = a
+        .iter()
+        .flat_map(|&x| b.iter().map(move |&y| x * y))
+        .collect();
+    ArrayD::from_shape_vec(IxDyn(&shape), values).expect("ShapeError!")
+}
+
+/// Product matrices of the operands of a tensor dot product, and the shape of the result.
+type DotOperands<'a, A> = (CowArray<'a, A, Ix2>, CowArray<'a, A, Ix2>, Vec<usize>);
+
+/// Validates the axes of a tensor dot product and brings both operands into matrix form.
+///
+/// # Returns
+/// A `Result` containing the `m x k` and `k x n` matrices and the shape of the product, or the
+/// errors documented on [`tensor_dot`].
+fn dot_operands<'a, A: Scalar>(
+    a: &'a ArrayD<A>,
+    b: &'a ArrayD<A>,
+    axes: Axes,
+) -> Result<DotOperands<'a, A>, QtnError> {
+    let (axes_a, axes_b) = match axes {
+        Axes::Last(k) if k <= a.ndim() && k <= b.ndim() => {
+            ((a.ndim() - k..a.ndim()).collect(), (0..k).collect())
+        }
+        Axes::Pairs(axes_a, axes_b) if axes_a.len() == axes_b.len() => (axes_a, axes_b),
+        Axes::Interleaved(axis_vec) => {
+            if !axis_vec.len().is_multiple_of(2) {
+                return Err(QtnError::OddAxisCount {
+                    len: axis_vec.len(),
+                });
+            }
+            let mut axes_a = axis_vec;
+            let axes_b = axes_a.split_off(axes_a.len() / 2);
+            (axes_a, axes_b)
+        }
+        axes => {
+            return Err(QtnError::InvalidArgument(format!(
+                "{:?} cannot be contracted between tensors of rank {} and {}.",
+                axes,
+                a.ndim(),
+                b.ndim()
+            )))
+        }
+    };
+    for (tensor, axes, ndim) in [(0, &axes_a, a.ndim()), (1, &axes_b, b.ndim())] {
+        if let Some(axis) = axes.iter().find(|&&axis| axis >= ndim) {
+            return Err(QtnError::InvalidArgument(format!(
+                "Axis {} is out of range for tensor {} of rank {}.",
+                axis, tensor, ndim
+            )));
+        }
+        if let Some(k) = (1..axes.len()).find(|&k| axes[..k].contains(&axes[k])) {
+            return Err(QtnError::InvalidArgument(format!(
+                "Axis {} of tensor {} is contracted more than once.",
+                axes[k], tensor
+            )));
+        }
+    }
 
     let ash = a.shape();
     let bsh = b.shape();
 
     // Check for shape compatibility along the specified axes
     for k in 0..axes_a.len() {
-        if ash[axes_a[k] as usize] != bsh[axes_b[k] as usize] {
-            return Err(format!(
-                "Shape mismatch along specified axes: a[{}] = {}, b[{}] = {}",
-                axes_a[k], ash[axes_a[k]], axes_b[k], bsh[axes_b[k]]
-            )
-            .to_string());
+        if ash[axes_a[k]] != bsh[axes_b[k]] {
+            return Err(QtnError::ShapeMismatch {
+                tensor: 0,
+                axis: axes_a[k],
+                dim: ash[axes_a[k]],
+                other_tensor: 1,
+                other_axis: axes_b[k],
+                other_dim: bsh[axes_b[k]],
+            });
         }
     }
 
     // Identify axes in tensor A that are not involved in the contraction
-    let notin_a: Vec<usize> = (0..a.ndim())
-        .filter(|&k| !axes_a.contains(&(k as usize)))
-        .collect();
+    let notin_a: Vec<usize> = (0..a.ndim()).filter(|&k| !axes_a.contains(&k)).collect();
 
     // Calculate the product of sizes for linked and unlinked axes in tensor A
     let a_mpl_linked: usize = axes_a.iter().map(|&ndx| ash[ndx]).product();
@@ -70,9 +238,7 @@ pub fn tensor_dot(
     let newaxes_a = [notin_a.clone(), axes_a].concat();
 
     // Do same for tensor B:
-    let notin_b: Vec<usize> = (0..b.ndim())
-        .filter(|&k| !axes_b.contains(&(k as usize)))
-        .collect();
+    let notin_b: Vec<usize> = (0..b.ndim()).filter(|&k| !axes_b.contains(&k)).collect();
 
     let b_mpl_linked: usize = axes_b.iter().map(|&ndx| bsh[ndx]).product();
     let b_mpl_unlinked: usize = notin_b.iter().map(|&ndx| bsh[ndx]).product();
@@ -81,23 +247,191 @@ pub fn tensor_dot(
 
     // Permute and reshape tensor A to a 2D matrix for dot product
     let a_permute = a.view().permuted_axes(IxDyn(&newaxes_a));
-    let a_reshape = a_permute.to_shape((a_mpl_unlinked, a_mpl_linked)).unwrap();
+    let a_reshape = matrix(a_permute, (a_mpl_unlinked, a_mpl_linked));
 
     // Do same for tensor B:
     let b_permute = b.view().permuted_axes(IxDyn(&newaxes_b));
-    let b_reshape = b_permute.to_shape((b_mpl_linked, b_mpl_unlinked)).unwrap();
-
-    // Compute the dot product of the reshaped matrices
-    let res = a_reshape.dot(&b_reshape).into_owned();
+    let b_reshape = matrix(b_permute, (b_mpl_linked, b_mpl_unlinked));
 
     // Determine the output shape based on the unlinked axes
     let old_a: Vec<_> = notin_a.iter().map(|&ndx| ash[ndx]).collect();
     let old_b: Vec<_> = notin_b.iter().map(|&ndx| bsh[ndx]).collect();
 
-    let output = res
-        .to_shape([old_a, old_b].concat())
-        .expect("Failed to reshape output")
-        .into_owned();
+    Ok((a_reshape, b_reshape, [old_a, old_b].concat()))
+}
 
-    Ok(output)
+/// Reshapes a permuted tensor into a matrix, copying it only if its layout requires it.
+pub(crate) fn matrix<A: Scalar>(
+    view: ArrayViewD<'_, A>,
+    shape: (usize, usize),
+) -> CowArray<'_, A, Ix2> {
+    match view.clone().into_shape_with_order(shape) {
+        Ok(matrix) => CowArray::from(matrix),
+        Err(_) => CowArray::from(view.to_shape(shape).unwrap().into_owned()),
+    }
+}
+
+/// Multiplies two matrices, using the unrolled kernel when the contracted dimension is small
+/// (e.g. a single physical leg) and a GEMM call otherwise.
+pub(crate) fn matmul<A: Scalar>(a: &ArrayView2<A>, b: &ArrayView2<A>) -> Array2<A> {
+    match a.ncols() {
+        1 => small_k_dot::<A, 1>(a, b),
+        2 => small_k_dot::<A, 2>(a, b),
+        3 => small_k_dot::<A, 3>(a, b),
+        SMALL_K_MAX => small_k_dot::<A, SMALL_K_MAX>(a, b),
+        _ => a.dot(b),
+    }
+}
+
+/// Multiplies two matrices like [`matmul`] and writes the product into `out`, which must have
+/// the shape of the product.
+pub(crate) fn matmul_into<A: Scalar>(a: &ArrayView2<A>, b: &ArrayView2<A>, out: ArrayViewMut2<A>) {
+    match a.ncols() {
+        1 => small_k_dot_into::<A, 1>(a, b, out),
+        2 => small_k_dot_into::<A, 2>(a, b, out),
+        3 => small_k_dot_into::<A, 3>(a, b, out),
+        SMALL_K_MAX => small_k_dot_into::<A, SMALL_K_MAX>(a, b, out),
+        _ => {
+            let mut out = out;
+            general_mat_mul(A::one(), a, b, A::zero(), &mut out)
+        }
+    }
+}
+
+/// Multiplies two matrices with a fixed summation order.
+///
+/// Every element of the product is accumulated sequentially along the contracted dimension, in
+/// ascending order, by a single thread. The rows of the product are split into contiguous blocks
+/// over the available threads, but since no element is ever reduced across threads, the result
+/// is bitwise identical for any number of threads and does not depend on the BLAS library. This
+/// is slower than a GEMM call and is only used when deterministic results are requested.
+pub(crate) fn deterministic_matmul<A: Scalar>(a: &ArrayView2<A>, b: &ArrayView2<A>) -> Array2<A> {
+    let mut out = Array2::zeros((a.nrows(), b.ncols()));
+    deterministic_matmul_into(a, b, out.view_mut());
+    out
+}
+
+/// Computes the product of [`deterministic_matmul`] and writes it into `out`.
+pub(crate) fn deterministic_matmul_into<A: Scalar>(
+    a: &ArrayView2<A>,
+    b: &ArrayView2<A>,
+    out: ArrayViewMut2<A>,
+) {
+    if a.ncols() <= SMALL_K_MAX {
+        return matmul_into(a, b, out);
+    }
+
+    ordered_matmul_into(a, b, out, |a_row, b_col| {
+        let mut acc = A::zero();
+        for (&x, &y) in a_row.iter().zip(b_col.iter()) {
+            acc += x * y;
+        }
+        acc
+    });
+}
+
+/// Multiplies two matrices, accumulating every element in double-word arithmetic.
+///
+/// Each element is computed as if in twice the working precision and rounded once, see
+/// [`ExtendedSum`], so cancellations between large terms, as in energy differences, do not
+/// lose digits. The summation order is fixed as in [`deterministic_matmul`], and the kernel is
+/// several times slower than it.
+pub(crate) fn extended_matmul<A: Scalar>(a: &ArrayView2<A>, b: &ArrayView2<A>) -> Array2<A> {
+    let mut out = Array2::zeros((a.nrows(), b.ncols()));
+    extended_matmul_into(a, b, out.view_mut());
+    out
+}
+
+/// Computes the product of [`extended_matmul`] and writes it into `out`.
+pub(crate) fn extended_matmul_into<A: Scalar>(
+    a: &ArrayView2<A>,
+    b: &ArrayView2<A>,
+    out: ArrayViewMut2<A>,
+) {
+    // Kept as a scalar, which unlike its real type is known to be `Sync`
+    let splitter = A::from_real(splitter::<A>());
+    ordered_matmul_into(a, b, out, |a_row, b_col| {
+        let mut acc = ExtendedSum::new(splitter.re());
+        for (&x, &y) in a_row.iter().zip(b_col.iter()) {
+            acc.add_product(x, y);
+        }
+        acc.value()
+    });
+}
+
+/// Multiplies two matrices by computing every element with `dot` on a single thread.
+///
+/// The rows of the product are split into contiguous blocks over the available threads, and
+/// `dot` receives a row of `a` and a column of `b` as contiguous views.
+fn ordered_matmul_into<A, F>(
+    a: &ArrayView2<A>,
+    b: &ArrayView2<A>,
+    mut out: ArrayViewMut2<A>,
+    dot: F,
+) where
+    A: Scalar,
+    F: Fn(ArrayView1<A>, ArrayView1<A>) -> A + Sync,
+{
+    let (m, k, n) = (a.nrows(), a.ncols(), b.ncols());
+    let b_t = b.t().as_standard_layout().into_owned();
+
+    let threads = if m * k * n < PARALLEL_MIN_WORK {
+        1
+    } else {
+        thread::available_parallelism().map_or(1, |t| t.get())
+    };
+    let block = m.div_ceil(threads).max(1);
+
+    thread::scope(|scope| {
+        for (a_rows, mut out_rows) in a
+            .axis_chunks_iter(Axis(0), block)
+            .zip(out.axis_chunks_iter_mut(Axis(0), block))
+        {
+            let (b_t, dot) = (&b_t, &dot);
+            scope.spawn(move || {
+                for (a_row, mut out_row) in a_rows.rows().into_iter().zip(out_rows.rows_mut()) {
+                    for (b_col, o) in b_t.rows().into_iter().zip(out_row.iter_mut()) {
+                        *o = dot(a_row, b_col);
+                    }
+                }
+            });
+        }
+    });
+}
+
+/// Multiplies an `m x K` matrix with a `K x n` matrix for a small, compile-time inner dimension.
+///
+/// For contractions over physical legs of dimension 2 to 4 the setup cost of a GEMM call
+/// dominates the few multiply-adds per output element. Here the `K` coefficients of each row of
+/// `a` are loaded once into a fixed-size array and the inner product has a constant trip count,
+/// so the compiler fully unrolls it and the loop body contains no data-dependent branches.
+///
+/// # Arguments
+/// - `a`: A view of the left matrix with exactly `K` columns.
+/// - `b`: A view of the right matrix with exactly `K` rows. Any strides are accepted.
+///
+/// # Returns
+/// An `Array2<A>` holding the `m x n` product.
+fn small_k_dot<A: Scalar, const K: usize>(a: &ArrayView2<A>, b: &ArrayView2<A>) -> Array2<A> {
+    let mut out = Array2::zeros((a.nrows(), b.ncols()));
+    small_k_dot_into::<A, K>(a, b, out.view_mut());
+    out
+}
+
+/// Computes the product of [`small_k_dot`] and writes it into `out`.
+fn small_k_dot_into<A: Scalar, const K: usize>(
+    a: &ArrayView2<A>,
+    b: &ArrayView2<A>,
+    mut out: ArrayViewMut2<A>,
+) {
+    for (a_row, mut out_row) in a.rows().into_iter().zip(out.rows_mut()) {
+        let coeff: [A; K] = std::array::from_fn(|k| a_row[k]);
+        for (b_col, o) in b.columns().into_iter().zip(out_row.iter_mut()) {
+            let mut acc = A::zero();
+            for k in 0..K {
+                acc += coeff[k] * b_col[k];
+            }
+            *o = acc;
+        }
+    }
 }
@@ -1,12 +1,28 @@
-use ndarray::{arr1, Array1, Array2, ArrayD, IxDyn};
-use ndarray_linalg::SVD;
+use crate::diag::DiagTensor;
+use crate::error::QtnError;
+use crate::scalar::Scalar;
+use ndarray::{arr1, s, Array1, Array2, ArrayD, ArrayView1, Axis, AxisDescription, IxDyn, Slice};
+use ndarray_linalg::{
+    Cholesky, Eig, Eigh, Factorize, FactorizeC, JobSvd, Solve, SolveC, QR, SVD, SVDDC, UPLO,
+};
 use rand::Rng;
+use std::thread;
 
-pub struct SVDResult {
-    pub u: Array2<f64>,
-    pub sigma_f64: Box<[f64]>,
-    pub sigma: Array1<f64>,
-    pub vt: Array2<f64>,
+pub struct SVDResult<A: Scalar = f64> {
+    pub u: Array2<A>,
+    pub sigma_f64: Box<[A::Real]>,
+    pub sigma: Array1<A::Real>,
+    pub vt: Array2<A>,
+}
+
+impl<A: Scalar> SVDResult<A> {
+    /// Returns the singular values as a [`DiagTensor`] of the scalar type of the decomposition.
+    ///
+    /// The bond weights can then be applied to the legs of `u` or `vt` with
+    /// [`DiagTensor::apply`], without building the dense diagonal matrix.
+    pub fn sigma_diag(&self) -> DiagTensor<A> {
+        DiagTensor::from_real(&self.sigma)
+    }
 }
 
 /// Creates a tensor of the specified shape, filled with the given value.
@@ -63,6 +79,31 @@ pub fn identity(size: usize) -> Array2<f64> {
     Array2::eye(size)
 }
 
+/// Creates the generalized Kronecker delta (COPY) tensor of the given rank.
+///
+/// The element is 1 where all indices are equal and 0 elsewhere. Contracted with other tensors
+/// it splits a hyperedge into ordinary legs, e.g. to share one index among several tensors or
+/// to wire a diagonal operator into a network. For rank 2 it is the identity matrix.
+///
+/// # Arguments
+///
+/// * `rank` - The number of legs.
+/// * `dim` - The dimension of every leg.
+///
+/// # Returns
+///
+/// An `ArrayD<f64>` of shape `(dim, ..., dim)` representing the delta tensor.
+pub fn delta(rank: usize, dim: usize) -> ArrayD<f64> {
+    let mut tensor = ArrayD::zeros(IxDyn(&vec![dim; rank]));
+    for i in 0..dim {
+        tensor[IxDyn(&vec![i; rank])] = 1.0;
+    }
+    if rank == 0 {
+        tensor[IxDyn(&[])] = 1.0;
+    }
+    tensor
+}
+
 /// Creates a diagonal matrix from the given diagonal elements.
 ///
 /// # Arguments
@@ -87,32 +128,409 @@ pub fn diagonal(diag: &[f64]) -> Array2<f64> {
 /// An `ArrayD<f64>` representing the tensor filled with random values.
 pub fn random(shape: &[usize]) -> ArrayD<f64> {
     let size = shape.iter().product();
-    let mut rng = rand::thread_rng(); // Use thread_rng for random number generation
+    let mut rng = rand::rng(); // Use the thread-local generator for random number generation
     let rnd_values: Vec<f64> = (0..size).map(|_| rng.random_range(0.0..=1.0)).collect();
     ArrayD::from_shape_vec(IxDyn(shape), rnd_values).expect("ShapeError!")
 }
 
+/// Applies a function to every element of a tensor, e.g. `exp`, a threshold or added noise.
+///
+/// # Arguments
+///
+/// * `tensor` - The tensor.
+/// * `f` - The function applied to each element.
+///
+/// # Returns
+///
+/// An `ArrayD<A>` of the same shape holding `f(x)` for every element `x`.
+pub fn map_elements<A: Scalar, F: Fn(A) -> A>(tensor: &ArrayD<A>, f: F) -> ArrayD<A> {
+    tensor.mapv(f)
+}
+
+/// Applies a function to every element of a tensor in place, see [`map_elements`].
+///
+/// # Arguments
+///
+/// * `tensor` - The tensor, whose elements are replaced by `f(x)`.
+/// * `f` - The function applied to each element.
+pub fn map_elements_inplace<A: Scalar, F: Fn(A) -> A>(tensor: &mut ArrayD<A>, f: F) {
+    tensor.mapv_inplace(f);
+}
+
+/// Adds a scalar to every element of a tensor.
+///
+/// # Arguments
+///
+/// * `tensor` - The tensor.
+/// * `value` - The scalar added to each element.
+///
+/// # Returns
+///
+/// An `ArrayD<A>` holding `x + value` for every element `x`.
+pub fn add_scalar<A: Scalar>(tensor: &ArrayD<A>, value: A) -> ArrayD<A> {
+    tensor.mapv(|x| x + value)
+}
+
+/// Adds a scalar to every element of a tensor in place, see [`add_scalar`].
+///
+/// # Arguments
+///
+/// * `tensor` - The tensor.
+/// * `value` - The scalar added to each element.
+pub fn add_scalar_inplace<A: Scalar>(tensor: &mut ArrayD<A>, value: A) {
+    tensor.mapv_inplace(|x| x + value);
+}
+
+/// Multiplies every element of a tensor by a scalar.
+///
+/// # Arguments
+///
+/// * `tensor` - The tensor.
+/// * `factor` - The scalar each element is multiplied by.
+///
+/// # Returns
+///
+/// An `ArrayD<A>` holding `factor x` for every element `x`.
+pub fn scale<A: Scalar>(tensor: &ArrayD<A>, factor: A) -> ArrayD<A> {
+    tensor.mapv(|x| x * factor)
+}
+
+/// Multiplies every element of a tensor by a scalar in place, see [`scale`].
+///
+/// # Arguments
+///
+/// * `tensor` - The tensor.
+/// * `factor` - The scalar each element is multiplied by.
+pub fn scale_inplace<A: Scalar>(tensor: &mut ArrayD<A>, factor: A) {
+    tensor.mapv_inplace(|x| x * factor);
+}
+
+/// Computes the Frobenius norm of a tensor, the square root of the sum of the squared moduli
+/// of its elements.
+///
+/// # Arguments
+///
+/// * `tensor` - The tensor.
+///
+/// # Returns
+///
+/// The norm as `A::Real`.
+pub fn frobenius_norm<A: Scalar>(tensor: &ArrayD<A>) -> A::Real {
+    let sum = tensor.iter().fold(A::real(0.0), |acc, &x| acc + x.square());
+    ndarray_linalg::Scalar::sqrt(sum)
+}
+
+/// Returns the largest modulus of the elements of a tensor, or zero for an empty tensor.
+///
+/// # Arguments
+///
+/// * `tensor` - The tensor.
+///
+/// # Returns
+///
+/// The largest modulus as `A::Real`.
+pub fn max_abs<A: Scalar>(tensor: &ArrayD<A>) -> A::Real {
+    tensor.iter().fold(A::real(0.0), |m, &x| {
+        let abs = x.abs();
+        if abs > m {
+            abs
+        } else {
+            m
+        }
+    })
+}
+
+/// Rescales a tensor to unit Frobenius norm.
+///
+/// # Arguments
+///
+/// * `tensor` - The tensor.
+///
+/// # Returns
+///
+/// A `Result<ArrayD<A>, QtnError>` where:
+/// - `Ok(ArrayD<A>)` contains the normalized tensor.
+/// - `Err(QtnError::InvalidArgument)` if the tensor has norm zero.
+pub fn normalize<A: Scalar>(tensor: &ArrayD<A>) -> Result<ArrayD<A>, QtnError> {
+    let norm = frobenius_norm(tensor);
+    if norm == A::real(0.0) {
+        return Err(QtnError::InvalidArgument(
+            "A tensor of norm zero cannot be normalized.".to_string(),
+        ));
+    }
+    let scale = A::from_real(A::real(1.0) / norm);
+    Ok(tensor.mapv(|x| x * scale))
+}
+
+/// Computes the Frobenius distance `||a - b||` of two tensors, e.g. to check the convergence of
+/// an iterative algorithm.
+///
+/// # Arguments
+///
+/// * `a` - The first tensor.
+/// * `b` - The second tensor, of the same shape as `a`.
+///
+/// # Returns
+///
+/// A `Result<A::Real, QtnError>` where:
+/// - `Ok(A::Real)` contains the distance.
+/// - `Err(QtnError::InvalidArgument)` if the shapes differ.
+pub fn distance<A: Scalar>(a: &ArrayD<A>, b: &ArrayD<A>) -> Result<A::Real, QtnError> {
+    if a.shape() != b.shape() {
+        return Err(QtnError::InvalidArgument(format!(
+            "Tensors of shapes {:?} and {:?} have no distance.",
+            a.shape(),
+            b.shape()
+        )));
+    }
+    let sum = a
+        .iter()
+        .zip(b)
+        .fold(A::real(0.0), |acc, (&x, &y)| acc + (x - y).square());
+    Ok(ndarray_linalg::Scalar::sqrt(sum))
+}
+
+/// Returns the complex conjugate of a tensor. Real tensors are returned unchanged.
+///
+/// # Arguments
+///
+/// * `tensor` - The tensor.
+///
+/// # Returns
+///
+/// An `ArrayD<A>` holding the conjugate of every element.
+pub fn conj<A: Scalar>(tensor: &ArrayD<A>) -> ArrayD<A> {
+    tensor.mapv(|x| x.conj())
+}
+
+/// Returns the conjugate transpose (Hermitian adjoint) of a matrix, the transpose for real
+/// matrices.
+///
+/// # Arguments
+///
+/// * `matrix` - The matrix, of shape `(m, n)`.
+///
+/// # Returns
+///
+/// An `Array2<A>` of shape `(n, m)`.
+pub fn dagger<A: Scalar>(matrix: &Array2<A>) -> Array2<A> {
+    matrix.t().mapv(|x| x.conj())
+}
+
+/// Returns the adjoint of an operator stored as a tensor with its output (ket) legs first and
+/// its input (bra) legs last.
+///
+/// The two groups of legs are exchanged and the elements conjugated, so an operator with legs
+/// `(o_1, ..., o_k, i_1, ..., i_m)` becomes one with legs `(i_1, ..., i_m, o_1, ..., o_k)`.
+/// For a matrix and `ket_axes = 1` this is [`dagger`].
+///
+/// # Arguments
+///
+/// * `tensor` - The operator.
+/// * `ket_axes` - The number of leading output legs.
+///
+/// # Returns
+///
+/// A `Result<ArrayD<A>, QtnError>` where:
+/// - `Ok(ArrayD<A>)` contains the adjoint operator.
+/// - `Err(QtnError::InvalidArgument)` if `ket_axes` exceeds the rank of `tensor`.
+pub fn adjoint<A: Scalar>(tensor: &ArrayD<A>, ket_axes: usize) -> Result<ArrayD<A>, QtnError> {
+    let ndim = tensor.ndim();
+    if ket_axes > ndim {
+        return Err(QtnError::InvalidArgument(format!(
+            "Operator of rank {} has no {} output legs.",
+            ndim, ket_axes
+        )));
+    }
+    let perm: Vec<usize> = (ket_axes..ndim).chain(0..ket_axes).collect();
+    Ok(tensor.view().permuted_axes(IxDyn(&perm)).mapv(|x| x.conj()))
+}
+
+/// Computes the Kronecker product of two matrices.
+///
+/// The result acts as `a` on the first and `b` on the second factor of a product space, e.g.
+/// `kron(&sz, &sz)` is the two-site operator `Sz ⊗ Sz` in the basis `|s1 s2>`.
+///
+/// # Arguments
+///
+/// * `a` - The left factor, of shape `(m, n)`.
+/// * `b` - The right factor, of shape `(p, q)`.
+///
+/// # Returns
+///
+/// An `Array2<A>` of shape `(m p, n q)` with the entries `a[i, j] b[k, l]` at
+/// `(i p + k, j q + l)`.
+pub fn kron<A: Scalar>(a: &Array2<A>, b: &Array2<A>) -> Array2<A> {
+    ndarray::linalg::kron(a, b)
+}
+
+/// Computes the Kronecker product of a sequence of matrices, from left to right.
+///
+/// This builds multi-site operators such as `Sz ⊗ 1 ⊗ Sz` for exact-diagonalization checks.
+///
+/// # Arguments
+///
+/// * `factors` - The matrices, with the first one acting on the leftmost site.
+///
+/// # Returns
+///
+/// An `Array2<A>` holding the product, or the 1x1 identity if `factors` is empty.
+pub fn kron_all<A: Scalar>(factors: &[Array2<A>]) -> Array2<A> {
+    factors
+        .iter()
+        .fold(Array2::eye(1), |product, factor| kron(&product, factor))
+}
+
+/// Computes the direct sum of two matrices.
+///
+/// The result is the block-diagonal matrix with `a` in the upper left and `b` in the lower right
+/// block, e.g. for the MPO of a sum of Hamiltonians.
+///
+/// # Arguments
+///
+/// * `a` - The upper left block, of shape `(m, n)`.
+/// * `b` - The lower right block, of shape `(p, q)`.
+///
+/// # Returns
+///
+/// An `Array2<A>` of shape `(m + p, n + q)`.
+pub fn direct_sum<A: Scalar>(a: &Array2<A>, b: &Array2<A>) -> Array2<A> {
+    let (m, n) = a.dim();
+    let (p, q) = b.dim();
+    let mut sum = Array2::zeros((m + p, n + q));
+    sum.slice_mut(s![..m, ..n]).assign(a);
+    sum.slice_mut(s![m.., n..]).assign(b);
+    sum
+}
+
+/// Computes the direct sum of two tensors along selected axes.
+///
+/// Along the axes in `axes` the tensors are placed in separate blocks, with `a` first, while all
+/// other axes are shared. Summing two MPS tensors over their bond axes, with the physical axis
+/// shared, gives the tensor of the sum of the two states.
+///
+/// # Arguments
+///
+/// * `a` - The first tensor.
+/// * `b` - The second tensor, of the same rank as `a`.
+/// * `axes` - The axes along which the tensors are block-embedded.
+///
+/// # Returns
+///
+/// A `Result<ArrayD<A>, QtnError>` where:
+/// - `Ok(ArrayD<A>)` contains the direct sum, whose dimension along the axes in `axes` is the
+///   sum of those of `a` and `b`, and is shared along all other axes.
+/// - `Err(QtnError::InvalidArgument)` if the ranks differ or an axis is out of range.
+/// - `Err(QtnError::ShapeMismatch)` if a shared axis has different dimensions.
+pub fn direct_sum_axes<A: Scalar>(
+    a: &ArrayD<A>,
+    b: &ArrayD<A>,
+    axes: &[usize],
+) -> Result<ArrayD<A>, QtnError> {
+    if a.ndim() != b.ndim() || axes.iter().any(|&axis| axis >= a.ndim()) {
+        return Err(QtnError::InvalidArgument(format!(
+            "Direct sum over axes {:?} needs tensors of equal rank with these axes, but they have \
+             ranks {} and {}.",
+            axes,
+            a.ndim(),
+            b.ndim()
+        )));
+    }
+
+    let mut shape = a.shape().to_vec();
+    for (axis, (dim, &other_dim)) in shape.iter_mut().zip(b.shape()).enumerate() {
+        if axes.contains(&axis) {
+            *dim += other_dim;
+        } else if *dim != other_dim {
+            return Err(QtnError::ShapeMismatch {
+                tensor: 0,
+                axis,
+                dim: *dim,
+                other_tensor: 1,
+                other_axis: axis,
+                other_dim,
+            });
+        }
+    }
+
+    let mut sum = ArrayD::zeros(IxDyn(&shape));
+    let split = |desc: AxisDescription, first: bool| {
+        let axis = desc.axis.index();
+        match (axes.contains(&axis), first) {
+            (false, _) => Slice::from(..),
+            (true, true) => Slice::from(..a.shape()[axis]),
+            (true, false) => Slice::from(a.shape()[axis]..),
+        }
+    };
+    sum.slice_each_axis_mut(|desc| split(desc, true)).assign(a);
+    sum.slice_each_axis_mut(|desc| split(desc, false)).assign(b);
+    Ok(sum)
+}
+
 /// Performs Singular Value Decomposition (SVD) on the given 2D array.
 ///
+/// Both real (`f64`) and complex (`Complex64`) matrices are supported. The singular values are
+/// always real, so for a complex input `sigma` holds `f64` values while `u` and `vt` are complex.
+///
 /// # Arguments
 ///
-/// * `arr` - A 2D array of type `Array2<f64>` to perform SVD on.
+/// * `arr` - A 2D array of type `Array2<A>` to perform SVD on.
 ///
 /// # Returns
 ///
-/// A `Result<SVDResult, String>` where:
-/// - `Ok(SVDResult)` contains the SVD results (U, sigma, VT).
-/// - `Err(String)` contains an error message if the SVD operation fails.
-pub fn svd(arr: Array2<f64>) -> Result<SVDResult, String> {
-    let (u, sigma, vt) = arr
-        .svd(true, true)
-        .map_err(|err| format!("SVD error: {:?}", err))?;
+/// A `Result<SVDResult<A>, QtnError>` where:
+/// - `Ok(SVDResult<A>)` contains the SVD results (U, sigma, VT).
+/// - `Err(QtnError::LinalgFailure)` describes the failure if the SVD operation fails.
+pub fn svd<A: Scalar>(arr: Array2<A>) -> Result<SVDResult<A>, QtnError> {
+    svd_with(arr, SvdDriver::Gesvd)
+}
+
+/// LAPACK driver computing a singular value decomposition, see [`svd_with`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SvdDriver {
+    /// The standard driver `gesvd` (QR iteration), as used by [`svd`]. It is slower, but also
+    /// converges for ill-conditioned matrices on which divide-and-conquer can fail.
+    #[default]
+    Gesvd,
+    /// The divide-and-conquer driver `gesdd`, considerably faster for large bond matrices. If it
+    /// fails or returns non-finite singular values, the decomposition is retried with `Gesvd`.
+    Gesdd,
+}
 
-    let u = u.ok_or_else(|| "U matrix is None".to_string())?;
-    let vt = vt.ok_or_else(|| "VT matrix is None".to_string())?;
-    let sigma_f64: &[f64] = sigma
+/// Performs Singular Value Decomposition (SVD) on the given 2D array with a chosen LAPACK
+/// driver.
+///
+/// # Arguments
+///
+/// * `arr` - A 2D array of type `Array2<A>` to perform SVD on.
+/// * `driver` - The [`SvdDriver`] to use.
+///
+/// # Returns
+///
+/// A `Result<SVDResult<A>, QtnError>` where:
+/// - `Ok(SVDResult<A>)` contains the SVD results (U, sigma, VT), as returned by [`svd`].
+/// - `Err(QtnError::LinalgFailure)` describes the failure if the robust driver fails as well.
+pub fn svd_with<A: Scalar>(arr: Array2<A>, driver: SvdDriver) -> Result<SVDResult<A>, QtnError> {
+    let decomposition = match driver {
+        SvdDriver::Gesvd => None,
+        SvdDriver::Gesdd => match arr.svddc(JobSvd::All) {
+            // Comparisons with NaN are false, so this also rejects NaN
+            Ok(result) if result.1.iter().all(|&s| s < A::real(f64::INFINITY)) => Some(result),
+            _ => None,
+        },
+    };
+    let (u, sigma, vt) = match decomposition {
+        Some(result) => result,
+        None => arr.svd(true, true).map_err(|err| {
+            QtnError::LinalgFailure(format!("SVD error with the gesvd driver: {:?}", err))
+        })?,
+    };
+
+    let u = u.ok_or_else(|| QtnError::LinalgFailure("U matrix is None".to_string()))?;
+    let vt = vt.ok_or_else(|| QtnError::LinalgFailure("VT matrix is None".to_string()))?;
+    let sigma_f64: &[A::Real] = sigma
         .as_slice()
-        .ok_or_else(|| "Sigma is empty".to_string())?;
+        .ok_or_else(|| QtnError::LinalgFailure("Sigma is empty".to_string()))?;
 
     Ok(SVDResult {
         u,
@@ -121,3 +539,594 @@ pub fn svd(arr: Array2<f64>) -> Result<SVDResult, String> {
         vt,
     })
 }
+
+/// Performs Singular Value Decomposition (SVD) on every matrix of a stack.
+///
+/// The last two axes of `arr` are the rows and columns of the matrices, and all leading axes
+/// form the batch, as in the bond matrices of a TEBD step or a PEPS simple update. The matrices
+/// can be decomposed on all available threads.
+///
+/// # Arguments
+///
+/// * `arr` - A tensor of type `ArrayD<A>` of rank 2 or more.
+/// * `parallel` - Whether the matrices are distributed over all available threads.
+///
+/// # Returns
+///
+/// A `Result<Vec<SVDResult<A>>, QtnError>` where:
+/// - `Ok(Vec<SVDResult<A>>)` contains the decomposition of every matrix, with the batch axes
+///   flattened in row-major order.
+/// - `Err(QtnError::InvalidArgument)` if `arr` has rank below 2.
+/// - `Err(QtnError::LinalgFailure)` describes the failure if an SVD operation fails.
+pub fn svd_batched<A>(arr: ArrayD<A>, parallel: bool) -> Result<Vec<SVDResult<A>>, QtnError>
+where
+    A: Scalar,
+    A::Real: Send,
+{
+    let ndim = arr.ndim();
+    if ndim < 2 {
+        return Err(QtnError::InvalidArgument(format!(
+            "Batched SVD needs a tensor of rank 2 or more, but it has rank {}.",
+            ndim
+        )));
+    }
+    let (m, n) = (arr.shape()[ndim - 2], arr.shape()[ndim - 1]);
+    let batch: usize = arr.shape()[..ndim - 2].iter().product();
+    let matrices = arr.to_shape((batch, m, n))?;
+    let decompose = |k: usize| svd(matrices.index_axis(Axis(0), k).to_owned());
+
+    let threads = if parallel {
+        thread::available_parallelism().map_or(1, |t| t.get())
+    } else {
+        1
+    };
+    if threads == 1 || batch < 2 {
+        return (0..batch).map(decompose).collect();
+    }
+
+    // Every thread decomposes a contiguous chunk, so the results stay in batch order
+    let chunk = batch.div_ceil(threads);
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..batch)
+            .step_by(chunk)
+            .map(|start| {
+                let decompose = &decompose;
+                scope.spawn(move || {
+                    (start..batch.min(start + chunk))
+                        .map(decompose)
+                        .collect::<Result<Vec<_>, _>>()
+                })
+            })
+            .collect();
+        let mut results = Vec::with_capacity(batch);
+        for handle in handles {
+            results.extend(handle.join().expect("Batched SVD panicked")?);
+        }
+        Ok(results)
+    })
+}
+
+/// Performs a truncated singular value decomposition of the given 2D array.
+///
+/// Singular values smaller than `cutoff` times the largest one are discarded, and at most
+/// `max_rank` of the remaining ones are kept, together with the matching columns of `U` and rows
+/// of `VT`. At least one singular value is always kept. This is the truncation step of every
+/// MPS and PEPS update.
+///
+/// # Arguments
+///
+/// * `arr` - A 2D array of type `Array2<A>` to perform SVD on.
+/// * `max_rank` - The largest number of singular values to keep, or `None` for no limit.
+/// * `cutoff` - The relative cutoff for small singular values.
+/// * `normalize` - Whether to rescale the kept singular values so that their squares sum to the
+///   squared norm of `arr`, as needed to keep a truncated state normalized.
+///
+/// # Returns
+///
+/// A `Result<(SVDResult<A>, A::Real), QtnError>` where:
+/// - `Ok((SVDResult<A>, A::Real))` contains the truncated SVD and the discarded weight, the sum
+///   of the squares of the discarded singular values relative to the sum of all squares.
+/// - `Err(QtnError::LinalgFailure)` describes the failure if the SVD operation fails.
+pub fn svd_truncated<A: Scalar>(
+    arr: Array2<A>,
+    max_rank: Option<usize>,
+    cutoff: f64,
+    normalize: bool,
+) -> Result<(SVDResult<A>, A::Real), QtnError> {
+    let result = svd(arr)?;
+
+    let largest = result
+        .sigma
+        .iter()
+        .fold(A::real(0.0), |m, &s| if s > m { s } else { m });
+    let threshold = largest * A::real(cutoff);
+    let rank = result
+        .sigma
+        .iter()
+        .filter(|&&s| s >= threshold)
+        .count()
+        .min(max_rank.unwrap_or(usize::MAX))
+        .max(1);
+
+    Ok(truncate(result, rank, normalize))
+}
+
+/// Performs a singular value decomposition truncated to a target error.
+///
+/// Instead of a fixed bond dimension, the smallest number of singular values is kept whose
+/// discarded weight does not exceed `target_error`, so the bond grows where the state is
+/// entangled and stays small elsewhere. `max_rank` caps the growth; if the cap is reached the
+/// discarded weight returned may exceed the target. At least one singular value is always kept.
+///
+/// # Arguments
+///
+/// * `arr` - A 2D array of type `Array2<A>` to perform SVD on.
+/// * `target_error` - The largest acceptable discarded weight, relative to the squared norm of
+///   `arr`.
+/// * `max_rank` - The largest number of singular values to keep, or `None` for no limit.
+/// * `normalize` - Whether to rescale the kept singular values so that their squares sum to the
+///   squared norm of `arr`.
+///
+/// # Returns
+///
+/// A `Result<(SVDResult<A>, A::Real), QtnError>` where:
+/// - `Ok((SVDResult<A>, A::Real))` contains the truncated SVD and the discarded weight, as
+///   returned by [`svd_truncated`].
+/// - `Err(QtnError::LinalgFailure)` describes the failure if the SVD operation fails.
+pub fn svd_to_error<A: Scalar>(
+    arr: Array2<A>,
+    target_error: f64,
+    max_rank: Option<usize>,
+    normalize: bool,
+) -> Result<(SVDResult<A>, A::Real), QtnError> {
+    let result = svd(arr)?;
+
+    // The weights of the tails are summed from the smallest singular value up, so that tiny
+    // tails are not lost to cancellation
+    let mut tails = vec![A::real(0.0); result.sigma.len() + 1];
+    for (k, &s) in result.sigma.iter().enumerate().rev() {
+        tails[k] = tails[k + 1] + s * s;
+    }
+    let target = tails[0] * A::real(target_error);
+    let rank = tails
+        .iter()
+        .position(|&tail| tail <= target)
+        .unwrap_or(result.sigma.len())
+        .min(max_rank.unwrap_or(usize::MAX))
+        .max(1);
+
+    Ok(truncate(result, rank, normalize))
+}
+
+/// Keeps the `rank` largest singular values of a decomposition and computes the discarded
+/// weight, see [`svd_truncated`].
+fn truncate<A: Scalar>(
+    result: SVDResult<A>,
+    rank: usize,
+    normalize: bool,
+) -> (SVDResult<A>, A::Real) {
+    let rank = rank.min(result.sigma.len());
+    let weight = |values: &[A::Real]| values.iter().fold(A::real(0.0), |acc, &s| acc + s * s);
+    let total = weight(&result.sigma_f64);
+    let kept = weight(&result.sigma_f64[..rank]);
+    let discarded = if total > A::real(0.0) {
+        (total - kept) / total
+    } else {
+        A::real(0.0)
+    };
+
+    let mut sigma = result.sigma.slice_move(s![..rank]);
+    if normalize && kept > A::real(0.0) {
+        let scale = ndarray_linalg::Scalar::sqrt(total / kept);
+        sigma.mapv_inplace(|s| s * scale);
+    }
+
+    (
+        SVDResult {
+            u: result.u.slice_move(s![.., ..rank]),
+            sigma_f64: sigma.to_vec().into(),
+            sigma,
+            vt: result.vt.slice_move(s![..rank, ..]),
+        },
+        discarded,
+    )
+}
+
+/// Computes an orthonormal basis of the kernel (null space) of the given 2D array.
+///
+/// The basis is read off the right singular vectors whose singular values are at most `tol`
+/// times the largest one. It spans the directions `x` with `arr x = 0`, as used for tangent-space
+/// projectors and for removing gauge and constraint directions in variational optimization.
+///
+/// # Arguments
+///
+/// * `arr` - A 2D array of type `Array2<A>` of any shape.
+/// * `tol` - The relative tolerance below which a singular value counts as zero.
+///
+/// # Returns
+///
+/// A `Result<Array2<A>, QtnError>` where:
+/// - `Ok(Array2<A>)` contains the basis vectors as the orthonormal columns of an `n x d` matrix,
+///   where `n` is the number of columns of `arr` and `d` the dimension of the kernel.
+/// - `Err(QtnError::LinalgFailure)` describes the failure if the SVD operation fails.
+pub fn nullspace<A: Scalar>(arr: Array2<A>, tol: f64) -> Result<Array2<A>, QtnError> {
+    let n = arr.ncols();
+    let result = svd(arr)?;
+
+    let largest = result
+        .sigma
+        .iter()
+        .fold(A::real(0.0), |m, &s| if s > m { s } else { m });
+    let cutoff = largest * A::real(tol);
+    let rank = result.sigma.iter().filter(|&&s| s > cutoff).count();
+
+    // The full VT holds the complement of the row space in its last rows
+    if result.vt.nrows() < n {
+        return Err(QtnError::LinalgFailure(
+            "VT matrix does not span the full row space".to_string(),
+        ));
+    }
+    Ok(dagger(&result.vt.slice(s![rank.., ..]).to_owned()))
+}
+
+/// Performs a thin QR decomposition `arr = Q R` of the given 2D array.
+///
+/// For an `m x n` input and `k = min(m, n)`, `Q` is `m x k` with orthonormal columns and `R` is
+/// `k x n` upper triangular.
+///
+/// # Arguments
+///
+/// * `arr` - A 2D array of type `Array2<A>` to decompose.
+///
+/// # Returns
+///
+/// A `Result<(Array2<A>, Array2<A>), QtnError>` where:
+/// - `Ok((Q, R))` contains the two factors.
+/// - `Err(QtnError::LinalgFailure)` describes the failure if the decomposition fails.
+pub fn qr<A: Scalar>(arr: Array2<A>) -> Result<(Array2<A>, Array2<A>), QtnError> {
+    let (q, r) = arr
+        .qr()
+        .map_err(|err| QtnError::LinalgFailure(format!("QR error: {:?}", err)))?;
+
+    // Keep the thin factors, whatever the backend returns
+    let k = arr.nrows().min(arr.ncols());
+    Ok((q.slice_move(s![.., ..k]), r.slice_move(s![..k, ..])))
+}
+
+/// Performs a thin LQ decomposition `arr = L Q` of the given 2D array.
+///
+/// For an `m x n` input and `k = min(m, n)`, `L` is `m x k` lower triangular and `Q` is `k x n`
+/// with orthonormal rows. This is the factorization used when a tensor is brought into
+/// right-canonical form, so sweeps from right to left do not have to transpose around [`qr`].
+///
+/// # Arguments
+///
+/// * `arr` - A 2D array of type `Array2<A>` to decompose.
+///
+/// # Returns
+///
+/// A `Result<(Array2<A>, Array2<A>), QtnError>` where:
+/// - `Ok((L, Q))` contains the two factors.
+/// - `Err(QtnError::LinalgFailure)` describes the failure if the decomposition fails.
+pub fn lq<A: Scalar>(arr: Array2<A>) -> Result<(Array2<A>, Array2<A>), QtnError> {
+    // arr^H = Q' R' gives arr = R'^H Q'^H
+    let (q, r) = qr(dagger(&arr))?;
+    Ok((dagger(&r), dagger(&q)))
+}
+
+/// Performs a thin RQ decomposition `arr = R Q` of the given 2D array.
+///
+/// For an `m x n` input and `k = min(m, n)`, `R` is `m x k` upper triangular (upper
+/// trapezoidal, aligned to the bottom-right corner, if `m > n`) and `Q` is `k x n` with
+/// orthonormal rows.
+///
+/// # Arguments
+///
+/// * `arr` - A 2D array of type `Array2<A>` to decompose.
+///
+/// # Returns
+///
+/// A `Result<(Array2<A>, Array2<A>), QtnError>` where:
+/// - `Ok((R, Q))` contains the two factors.
+/// - `Err(QtnError::LinalgFailure)` describes the failure if the decomposition fails.
+pub fn rq<A: Scalar>(arr: Array2<A>) -> Result<(Array2<A>, Array2<A>), QtnError> {
+    // With the rows reversed, (J arr)^H = Q' R' gives arr = (J R'^H J) (J Q'^H), and reversing
+    // both axes turns the lower triangular R'^H into an upper triangular factor
+    let mut flipped = arr;
+    flipped.invert_axis(Axis(0));
+    let (q, r) = qr(dagger(&flipped))?;
+
+    let mut r = dagger(&r);
+    r.invert_axis(Axis(0));
+    r.invert_axis(Axis(1));
+    let mut q = dagger(&q);
+    q.invert_axis(Axis(0));
+    Ok((
+        r.as_standard_layout().into_owned(),
+        q.as_standard_layout().into_owned(),
+    ))
+}
+
+/// Computes the eigendecomposition `a = V diag(w) V^H` of a real symmetric or complex Hermitian
+/// matrix.
+///
+/// This is the decomposition used for density matrices, effective Hamiltonians and thermal
+/// states. The eigenvalues are real and returned in ascending order, with the matching
+/// orthonormal eigenvectors as the columns of `V`.
+///
+/// # Arguments
+///
+/// * `a` - A square 2D array of type `Array2<A>`. Only its lower triangle is read.
+///
+/// # Returns
+///
+/// A `Result<(Array1<A::Real>, Array2<A>), QtnError>` where:
+/// - `Ok((w, V))` contains the sorted eigenvalues and the eigenvectors.
+/// - `Err(QtnError::InvalidArgument)` is returned if `a` is not square.
+/// - `Err(QtnError::LinalgFailure)` describes the failure if the decomposition fails.
+pub fn eigh<A: Scalar>(a: &Array2<A>) -> Result<(Array1<A::Real>, Array2<A>), QtnError> {
+    system_validation(a, a, true)?;
+    let (w, v) = a
+        .eigh(UPLO::Lower)
+        .map_err(|err| QtnError::LinalgFailure(format!("Eigenvalue error: {:?}", err)))?;
+
+    // The order is not guaranteed by every backend
+    let mut order: Vec<usize> = (0..w.len()).collect();
+    order.sort_by(|&i, &j| A::total_cmp_real(&w[i], &w[j]));
+    Ok((w.select(Axis(0), &order), v.select(Axis(1), &order)))
+}
+
+/// Computes the eigendecomposition `a = V diag(w) V^-1` of a general square matrix.
+///
+/// Eigenvalues of non-symmetric matrices, such as MPS transfer matrices, are complex in general,
+/// so both the eigenvalues and the eigenvectors are returned as complex numbers, also for a real
+/// input. They are sorted by decreasing modulus, so the dominant eigenpair, which determines the
+/// fixed point and the correlation length of a transfer matrix, comes first.
+///
+/// # Arguments
+///
+/// * `a` - A square 2D array of type `Array2<A>`.
+///
+/// # Returns
+///
+/// A `Result<(Array1<A::Complex>, Array2<A::Complex>), QtnError>` where:
+/// - `Ok((w, V))` contains the sorted eigenvalues and the right eigenvectors as the columns of
+///   `V`.
+/// - `Err(QtnError::InvalidArgument)` is returned if `a` is not square.
+/// - `Err(QtnError::LinalgFailure)` describes the failure if the decomposition fails.
+pub fn eig<A: Scalar>(a: &Array2<A>) -> Result<Eigenpairs<A>, QtnError> {
+    system_validation(a, a, true)?;
+    let (w, v) = a
+        .eig()
+        .map_err(|err| QtnError::LinalgFailure(format!("Eigenvalue error: {:?}", err)))?;
+
+    let modulus: Vec<A::Real> = w.iter().map(|&x| ndarray_linalg::Scalar::abs(x)).collect();
+    let mut order: Vec<usize> = (0..w.len()).collect();
+    order.sort_by(|&i, &j| A::total_cmp_real(&modulus[j], &modulus[i]));
+    Ok((w.select(Axis(0), &order), v.select(Axis(1), &order)))
+}
+
+/// Solves the generalized eigenvalue problem `a v = lambda b v` for general square matrices.
+///
+/// The problem is reduced to the standard one for `b^-1 a`, so `b` must be invertible. The
+/// eigenpairs are complex in general and sorted by decreasing modulus, as in [`eig`]. For a
+/// Hermitian `a` and a positive definite `b`, as in variational problems with a norm matrix,
+/// [`eigh_generalized`] is more accurate and returns real eigenvalues.
+///
+/// # Arguments
+///
+/// * `a` - A square 2D array of type `Array2<A>`.
+/// * `b` - An invertible 2D array of type `Array2<A>` of the same shape.
+///
+/// # Returns
+///
+/// A `Result<(Array1<A::Complex>, Array2<A::Complex>), QtnError>` where:
+/// - `Ok((w, V))` contains the sorted eigenvalues and the eigenvectors as the columns of `V`.
+/// - `Err(QtnError::InvalidArgument)` is returned if `a` is not square or `b` has a different
+///   shape.
+/// - `Err(QtnError::LinalgFailure)` describes the failure if `b` is singular or the
+///   decomposition fails.
+pub fn eig_generalized<A: Scalar>(a: &Array2<A>, b: &Array2<A>) -> Result<Eigenpairs<A>, QtnError> {
+    pencil_validation(a, b)?;
+    eig(&solve(b, a)?)
+}
+
+/// Solves the generalized eigenvalue problem `a v = lambda b v` for a Hermitian `a` and a
+/// Hermitian positive definite `b`.
+///
+/// With the Cholesky factor `b = L L^H`, the problem is reduced to the Hermitian eigenproblem
+/// of `L^-1 a L^-H`. The eigenvalues are real and returned in ascending order, and the
+/// eigenvectors are orthonormal with respect to `b`, i.e. `V^H b V = 1`.
+///
+/// # Arguments
+///
+/// * `a` - A square, Hermitian 2D array of type `Array2<A>`.
+/// * `b` - A Hermitian positive definite 2D array of type `Array2<A>` of the same shape.
+///
+/// # Returns
+///
+/// A `Result<(Array1<A::Real>, Array2<A>), QtnError>` where:
+/// - `Ok((w, V))` contains the sorted eigenvalues and the eigenvectors as the columns of `V`.
+/// - `Err(QtnError::InvalidArgument)` is returned if `a` is not square or `b` has a different
+///   shape.
+/// - `Err(QtnError::LinalgFailure)` describes the failure if `b` is not positive definite or
+///   the decomposition fails.
+pub fn eigh_generalized<A: Scalar>(
+    a: &Array2<A>,
+    b: &Array2<A>,
+) -> Result<(Array1<A::Real>, Array2<A>), QtnError> {
+    pencil_validation(a, b)?;
+    let l = cholesky(b)?;
+
+    // L^-1 a L^-H = L^-1 (L^-1 a)^H for a Hermitian a
+    let half = solve(&l, a)?;
+    let reduced = solve(&l, &dagger(&half))?;
+    let (w, z) = eigh(&reduced)?;
+    Ok((w, solve(&dagger(&l), &z)?))
+}
+
+/// Solves the linear system `a x = b` by an LU decomposition with partial pivoting.
+///
+/// The matrix is factorized once and every column of `b` is solved with the same factors.
+///
+/// # Arguments
+///
+/// * `a` - A square 2D array of type `Array2<A>`, the matrix of the system.
+/// * `b` - A 2D array of type `Array2<A>` whose columns are the right-hand sides.
+///
+/// # Returns
+///
+/// A `Result<Array2<A>, QtnError>` where:
+/// - `Ok(Array2<A>)` contains the solutions as the columns of `x`.
+/// - `Err(QtnError::InvalidArgument)` is returned if `a` is not square or `b` has a different
+///   number of rows.
+/// - `Err(QtnError::LinalgFailure)` describes the failure if `a` is singular.
+pub fn solve<A: Scalar>(a: &Array2<A>, b: &Array2<A>) -> Result<Array2<A>, QtnError> {
+    system_validation(a, b, true)?;
+    let lu = a
+        .factorize()
+        .map_err(|err| QtnError::LinalgFailure(format!("LU error: {:?}", err)))?;
+    solve_columns(b, |col| lu.solve(&col), "LU")
+}
+
+/// Computes the Cholesky factor of a Hermitian positive definite matrix.
+///
+/// # Arguments
+///
+/// * `a` - A square 2D array of type `Array2<A>`. Only its lower triangle is read.
+///
+/// # Returns
+///
+/// A `Result<Array2<A>, QtnError>` where:
+/// - `Ok(Array2<A>)` contains the lower triangular `L` with `a = L L^H`.
+/// - `Err(QtnError::InvalidArgument)` is returned if `a` is not square.
+/// - `Err(QtnError::LinalgFailure)` describes the failure if `a` is not positive definite.
+pub fn cholesky<A: Scalar>(a: &Array2<A>) -> Result<Array2<A>, QtnError> {
+    system_validation(a, a, true)?;
+    a.cholesky(UPLO::Lower)
+        .map_err(|err| QtnError::LinalgFailure(format!("Cholesky error: {:?}", err)))
+}
+
+/// Solves the linear system `a x = b` for a Hermitian positive definite matrix by a Cholesky
+/// decomposition.
+///
+/// This is about twice as fast as [`solve`] and is the natural choice for the normal equations
+/// of variational fits and for the positive norm matrices of full-update schemes.
+///
+/// # Arguments
+///
+/// * `a` - A square, Hermitian positive definite 2D array of type `Array2<A>`.
+/// * `b` - A 2D array of type `Array2<A>` whose columns are the right-hand sides.
+///
+/// # Returns
+///
+/// A `Result<Array2<A>, QtnError>` where:
+/// - `Ok(Array2<A>)` contains the solutions as the columns of `x`.
+/// - `Err(QtnError::InvalidArgument)` is returned if `a` is not square or `b` has a different
+///   number of rows.
+/// - `Err(QtnError::LinalgFailure)` describes the failure if `a` is not positive definite.
+pub fn solve_cholesky<A: Scalar>(a: &Array2<A>, b: &Array2<A>) -> Result<Array2<A>, QtnError> {
+    system_validation(a, b, true)?;
+    let factor = a
+        .factorizec(UPLO::Lower)
+        .map_err(|err| QtnError::LinalgFailure(format!("Cholesky error: {:?}", err)))?;
+    solve_columns(b, |col| factor.solvec(&col), "Cholesky")
+}
+
+/// Computes the minimum-norm least-squares solution of `a x = b` through an SVD.
+///
+/// Singular values up to `rcond` times the largest one are treated as zero, so rank-deficient
+/// and ill-conditioned systems, as met in variational fitting, give a regularized solution
+/// instead of blowing up.
+///
+/// # Arguments
+///
+/// * `a` - A 2D array of type `Array2<A>` of any shape, the matrix of the system.
+/// * `b` - A 2D array of type `Array2<A>` whose columns are the right-hand sides.
+/// * `rcond` - The relative cutoff for small singular values.
+///
+/// # Returns
+///
+/// A `Result<Array2<A>, QtnError>` where:
+/// - `Ok(Array2<A>)` contains the solutions as the columns of `x`.
+/// - `Err(QtnError::InvalidArgument)` is returned if `b` has a different number of rows than
+///   `a`.
+/// - `Err(QtnError::LinalgFailure)` describes the failure if the SVD fails.
+pub fn least_squares<A: Scalar>(
+    a: &Array2<A>,
+    b: &Array2<A>,
+    rcond: f64,
+) -> Result<Array2<A>, QtnError> {
+    system_validation(a, b, false)?;
+    let result = svd(a.clone())?;
+
+    let largest = result
+        .sigma
+        .iter()
+        .fold(A::real(0.0), |m, &s| if s > m { s } else { m });
+    let cutoff = largest * A::real(rcond);
+    let rank = result.sigma.iter().filter(|&&s| s > cutoff).count();
+
+    // x = V S^-1 U^H b over the retained singular values
+    let mut projected = dagger(&result.u.slice(s![.., ..rank]).to_owned()).dot(b);
+    for (mut row, &s) in projected.axis_iter_mut(Axis(0)).zip(&result.sigma) {
+        row.mapv_inplace(|x| x / A::from_real(s));
+    }
+    Ok(dagger(&result.vt.slice(s![..rank, ..]).to_owned()).dot(&projected))
+}
+
+/// Checks that `b` has as many rows as `a`, and that `a` is square if required.
+fn system_validation<A: Scalar>(
+    a: &Array2<A>,
+    b: &Array2<A>,
+    square: bool,
+) -> Result<(), QtnError> {
+    if square && a.nrows() != a.ncols() {
+        return Err(QtnError::InvalidArgument(format!(
+            "Matrix of shape {:?} is not square.",
+            a.dim()
+        )));
+    }
+    if b.nrows() != a.nrows() {
+        return Err(QtnError::InvalidArgument(format!(
+            "Right-hand side has {} rows, but the matrix has {}.",
+            b.nrows(),
+            a.nrows()
+        )));
+    }
+    Ok(())
+}
+
+/// Solves every column of `b` with a factorized matrix.
+fn solve_columns<A, F>(b: &Array2<A>, solve: F, name: &str) -> Result<Array2<A>, QtnError>
+where
+    A: Scalar,
+    F: Fn(ArrayView1<A>) -> ndarray_linalg::error::Result<Array1<A>>,
+{
+    let mut x = Array2::zeros(b.dim());
+    for (col, mut out) in b.columns().into_iter().zip(x.columns_mut()) {
+        let solution = solve(col)
+            .map_err(|err| QtnError::LinalgFailure(format!("{} solve error: {:?}", name, err)))?;
+        out.assign(&solution);
+    }
+    Ok(x)
+}
+
+/// Complex eigenvalues and eigenvectors of a general matrix.
+type Eigenpairs<A> = (
+    Array1<<A as ndarray_linalg::Scalar>::Complex>,
+    Array2<<A as ndarray_linalg::Scalar>::Complex>,
+);
+
+/// Checks that `a` is square and `b` has the same shape.
+fn pencil_validation<A: Scalar>(a: &Array2<A>, b: &Array2<A>) -> Result<(), QtnError> {
+    system_validation(a, a, true)?;
+    if b.dim() != a.dim() {
+        return Err(QtnError::InvalidArgument(format!(
+            "Matrices of shapes {:?} and {:?} do not form a pencil.",
+            a.dim(),
+            b.dim()
+        )));
+    }
+    Ok(())
+}
@@ -1,4 +1,6 @@
-use ndarray::{s, Array1, ArrayD, IxDyn};
+use crate::error::QtnError;
+use crate::scalar::{splitter, ExtendedSum, Scalar};
+use ndarray::{s, Array1, ArrayBase, ArrayD, Data, IxDyn};
 
 /// Computes the trace of a tensor along specified axes.
 ///
@@ -8,47 +10,63 @@ use ndarray::{s, Array1, ArrayD, IxDyn};
 ///
 /// # Parameters
 ///
-/// - `tensor`: A reference to a tensor of type `ArrayD<f64>`. This is the tensor
-///   for which the trace will be calculated.
+/// - `tensor`: A reference to a tensor of type `ArrayD<A>`, or any other array with `IxDyn`
+///   dimension such as an `ArrayViewD<A>`, where `A` is any `ndarray` linear algebra scalar
+///   (real or complex). This is the tensor for which the trace will be calculated.
 /// - `axes`: A vector of `usize` containing exactly two axes indices along which
 ///   the trace will be computed.
 ///
 /// # Returns
 ///
-/// - `Result<ArrayD<f64>, String>`: Returns a `Result` containing either:
-///   - `Ok(ArrayD<f64>)`: The resulting tensor after computing the trace.
-///   - `Err(String)`: An error message if the input is invalid or if there is a shape mismatch.
+/// - `Result<ArrayD<A>, QtnError>`: Returns a `Result` containing either:
+///   - `Ok(ArrayD<A>)`: The resulting tensor after computing the trace.
+///   - `Err(QtnError)`: An error if the input is invalid or if there is a shape mismatch.
 ///
 /// # Errors
 ///
 /// The function may return an error in the following cases:
-/// - If the length of `axes` is not exactly 2.
-/// - If the sizes of the specified axes in the tensor do not match.
-pub fn trace(tensor: &ArrayD<f64>, axes: Vec<usize>) -> Result<ArrayD<f64>, String> {
+/// - [`QtnError::TraceAxisCount`] if the length of `axes` is not exactly 2.
+/// - [`QtnError::ShapeMismatch`] if the sizes of the specified axes in the tensor do not match.
+pub fn trace<A, S>(tensor: &ArrayBase<S, IxDyn>, axes: Vec<usize>) -> Result<ArrayD<A>, QtnError>
+where
+    A: Scalar,
+    S: Data<Elem = A>,
+{
+    trace_with(tensor, axes, false)
+}
+
+/// Computes the trace of [`trace`], optionally summing the diagonal elements in double-word
+/// arithmetic, see [`ExtendedSum`].
+pub(crate) fn trace_with<A, S>(
+    tensor: &ArrayBase<S, IxDyn>,
+    axes: Vec<usize>,
+    extended: bool,
+) -> Result<ArrayD<A>, QtnError>
+where
+    A: Scalar,
+    S: Data<Elem = A>,
+{
     // Check if exactly two axes are provided
     if axes.len() != 2 {
-        return Err(format!(
-            "Trace calculation need two axes index. (Axes length is {}!)",
-            axes.len()
-        )
-        .to_string());
+        return Err(QtnError::TraceAxisCount { len: axes.len() });
     }
 
     let t_shape = tensor.shape().to_vec();
 
     // Check if the sizes of the specified axes are the same
     if t_shape[axes[0]] != t_shape[axes[1]] {
-        return Err(format!(
-            "Shape mismatch along specified axes: tenosr[{}] = {}, tensor[{}] = {}",
-            axes[0], t_shape[axes[0]], axes[1], t_shape[axes[1]]
-        )
-        .to_string());
+        return Err(QtnError::ShapeMismatch {
+            tensor: 0,
+            axis: axes[0],
+            dim: t_shape[axes[0]],
+            other_tensor: 0,
+            other_axis: axes[1],
+            other_dim: t_shape[axes[1]],
+        });
     }
 
     // Identify axes in the tensor that are not involved in the trace calculation
-    let notin: Vec<usize> = (0..tensor.ndim())
-        .filter(|&k| !axes.contains(&(k as usize)))
-        .collect();
+    let notin: Vec<usize> = (0..tensor.ndim()).filter(|&k| !axes.contains(&k)).collect();
 
     // Get the shapes of the axes that are not involved in the trace
     let notin_shape: Vec<_> = notin.iter().map(|&ndx| t_shape[ndx]).collect();
@@ -70,11 +88,21 @@ pub fn trace(tensor: &ArrayD<f64>, axes: Vec<usize>) -> Result<ArrayD<f64>, Stri
         .expect("Failed to reshape permuted tensor")
         .into_owned();
 
-    let mut result = Array1::<f64>::zeros(r_shape_dim[0]);
+    let mut result = Array1::<A>::zeros(r_shape_dim[0]);
 
-    for i in 0..t_shape[axes[0]] {
-        let slice = t_permuted.slice(s![i, i, ..]);
-        result = &result + &slice;
+    if extended {
+        let mut sums = vec![ExtendedSum::<A>::new(splitter::<A>()); r_shape_dim[0]];
+        for i in 0..t_shape[axes[0]] {
+            for (sum, &x) in sums.iter_mut().zip(t_permuted.slice(s![i, i, ..])) {
+                sum.add(x);
+            }
+        }
+        result = sums.iter().map(|sum| sum.value()).collect();
+    } else {
+        for i in 0..t_shape[axes[0]] {
+            let slice = t_permuted.slice(s![i, i, ..]);
+            result = &result + &slice;
+        }
     }
 
     Ok(result
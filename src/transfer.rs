@@ -0,0 +1,331 @@
+use crate::error::QtnError;
+use crate::tencon::contract;
+use crate::trace::trace;
+use ndarray::{Array1, Array2, ArrayD, Axis};
+use ndarray_linalg::{c64, Eig};
+
+/// Builds the column transfer matrix of a finite strip from a column of local tensors.
+///
+/// Each local tensor must have four legs ordered as `(left, up, right, down)`. The tensors are
+/// stacked from top to bottom, the `down` leg of each tensor is contracted with the `up` leg of
+/// the next one, and the remaining horizontal legs are grouped into the row (left) and column
+/// (right) indices of the transfer matrix. A column with a single tensor describes a plain 1D
+/// chain, in which case the vertical legs usually have dimension one.
+///
+/// # Arguments
+/// - `column`: A slice of rank-4 `ArrayD<f64>` tensors, ordered from top to bottom.
+/// - `periodic`: If `true`, the `up` leg of the first tensor is contracted with the `down` leg of
+///   the last tensor (cylinder geometry). Otherwise both boundary legs are summed over (free
+///   boundary conditions).
+///
+/// # Returns
+/// A `Result<Array2<f64>, QtnError>` where:
+/// - `Ok(Array2<f64>)` contains the transfer matrix, whose rows are the left legs and whose
+///   columns are the right legs, both in row-major order from top to bottom.
+/// - `Err(QtnError)` contains the error if the column is empty or a tensor has the wrong rank.
+///
+/// # Errors
+/// This function may return an error if:
+/// - `column` is empty.
+/// - Any tensor does not have exactly four legs.
+/// - The vertical legs of neighbouring tensors have different dimensions.
+pub fn column_transfer_matrix(
+    column: &[ArrayD<f64>],
+    periodic: bool,
+) -> Result<Array2<f64>, QtnError> {
+    if column.is_empty() {
+        return Err(QtnError::InvalidArgument(
+            "Transfer matrix needs at least one local tensor.".to_string(),
+        ));
+    }
+    if let Some(k) = column.iter().position(|t| t.ndim() != 4) {
+        return Err(QtnError::InvalidArgument(format!(
+            "Local tensor {} must have four legs (left, up, right, down), found {}.",
+            k,
+            column[k].ndim()
+        )));
+    }
+
+    let h = column.len();
+    let rows: usize = column.iter().map(|t| t.shape()[0]).product();
+    let cols: usize = column.iter().map(|t| t.shape()[2]).product();
+
+    let strip = if h == 1 {
+        if periodic {
+            trace(&column[0], vec![1, 3])?
+        } else {
+            column[0].sum_axis(Axis(3)).sum_axis(Axis(1))
+        }
+    } else {
+        let h = h as i32;
+        let mut tensors = column.to_vec();
+        let mut orders: Vec<Vec<i32>> = (0..h)
+            .map(|k| vec![-(k + 1), k, -(h + k + 1), k + 1])
+            .collect();
+
+        if periodic {
+            // Close the column by linking the first `up` leg to the last `down` leg
+            orders[0][1] = h;
+        } else {
+            // Free boundaries: sum over the outer vertical legs and drop them from the orders
+            tensors[0] = tensors[0].sum_axis(Axis(1));
+            orders[0].remove(1);
+            let last = tensors.len() - 1;
+            tensors[last] = tensors[last].sum_axis(Axis(3));
+            orders[last].remove(3);
+        }
+
+        let order_refs: Vec<&[i32]> = orders.iter().map(|o| o.as_slice()).collect();
+        contract(&tensors, &order_refs)?
+    };
+
+    Ok(strip.to_shape((rows, cols))?.into_owned())
+}
+
+/// Raises a square transfer matrix to an integer power by repeated squaring.
+///
+/// # Arguments
+/// - `t`: A square `Array2<f64>` transfer matrix.
+/// - `n`: The exponent, i.e. the number of columns of the finite chain.
+///
+/// # Returns
+/// A `Result<Array2<f64>, QtnError>` containing `t^n`, or an error if `t` is not square.
+/// The zeroth power is the identity matrix.
+pub fn transfer_power(t: &Array2<f64>, n: usize) -> Result<Array2<f64>, QtnError> {
+    check_square(t)?;
+
+    let mut result = Array2::eye(t.nrows());
+    let mut base = t.clone();
+    let mut n = n;
+
+    while n > 0 {
+        if n & 1 == 1 {
+            result = result.dot(&base);
+        }
+        base = base.dot(&base);
+        n >>= 1;
+    }
+
+    Ok(result)
+}
+
+/// Computes the logarithm of the partition function of a finite chain of `length` columns.
+///
+/// The matrix power is accumulated with intermediate rescaling, so long chains do not overflow.
+/// For periodic chains the partition function is `Tr(T^length)`, for open chains it is the sum of
+/// all entries of `T^length` (free boundary conditions on both ends).
+///
+/// # Arguments
+/// - `t`: A square `Array2<f64>` transfer matrix with non-negative Boltzmann weights.
+/// - `length`: The number of columns in the chain. Must be at least one.
+/// - `periodic`: Selects periodic (`true`) or open (`false`) boundary conditions along the chain.
+///
+/// # Returns
+/// A `Result<f64, QtnError>` containing `ln Z`, or an error if `t` is not square, `length`
+/// is zero, or the partition function is not positive.
+pub fn log_partition_function(
+    t: &Array2<f64>,
+    length: usize,
+    periodic: bool,
+) -> Result<f64, QtnError> {
+    check_square(t)?;
+    if length == 0 {
+        return Err(QtnError::InvalidArgument(
+            "Chain length must be at least one.".to_string(),
+        ));
+    }
+
+    let mut result = Array2::<f64>::eye(t.nrows());
+    let mut log_scale = 0.0;
+    let mut base = t.clone();
+    let mut base_log_scale = 0.0;
+    let mut n = length;
+
+    while n > 0 {
+        if n & 1 == 1 {
+            result = result.dot(&base);
+            log_scale += base_log_scale;
+            log_scale += rescale(&mut result);
+        }
+        n >>= 1;
+        if n > 0 {
+            base = base.dot(&base);
+            base_log_scale *= 2.0;
+            base_log_scale += rescale(&mut base);
+        }
+    }
+
+    let z = if periodic {
+        result.diag().sum()
+    } else {
+        result.sum()
+    };
+    if z.is_nan() || z <= 0.0 {
+        return Err(QtnError::InvalidArgument(format!(
+            "Partition function is not positive (Z = {}).",
+            z
+        )));
+    }
+
+    Ok(z.ln() + log_scale)
+}
+
+/// Computes the leading eigenvalue and eigenvector of a transfer matrix by power iteration.
+///
+/// The iteration starts from the uniform vector, which has non-zero overlap with the Perron
+/// vector of any matrix with positive Boltzmann weights.
+///
+/// # Arguments
+/// - `t`: A square `Array2<f64>` transfer matrix.
+/// - `tol`: Convergence tolerance on the change of the normalized eigenvector between iterations.
+/// - `max_iter`: The maximum number of matrix-vector products.
+///
+/// # Returns
+/// A `Result<(f64, Array1<f64>), QtnError>` where:
+/// - `Ok((lambda, v))` contains the dominant eigenvalue and its normalized eigenvector.
+/// - `Err(QtnError)` contains the error if `t` is not square or the iteration does not
+///   converge within `max_iter` steps.
+pub fn leading_eigenvalue(
+    t: &Array2<f64>,
+    tol: f64,
+    max_iter: usize,
+) -> Result<(f64, Array1<f64>), QtnError> {
+    check_square(t)?;
+
+    let n = t.nrows();
+    let mut v = Array1::from_elem(n, 1.0 / (n as f64).sqrt());
+
+    for _ in 0..max_iter {
+        let w = t.dot(&v);
+        let lambda = v.dot(&w);
+        let norm = w.dot(&w).sqrt();
+        if norm == 0.0 {
+            return Ok((0.0, v));
+        }
+
+        let w = w / (norm * lambda.signum());
+        let change = (&w - &v).mapv(f64::abs).fold(0.0_f64, |a, &b| a.max(b));
+        v = w;
+
+        if change < tol {
+            return Ok((lambda, v));
+        }
+    }
+
+    Err(QtnError::NotConverged(format!(
+        "Power iteration did not converge within {} iterations.",
+        max_iter
+    )))
+}
+
+/// Computes the full eigenvalue spectrum of a transfer matrix.
+///
+/// # Arguments
+/// - `t`: A square `Array2<f64>` transfer matrix.
+///
+/// # Returns
+/// A `Result<Array1<c64>, QtnError>` containing the (generally complex) eigenvalues sorted by
+/// decreasing modulus, or an error if the eigenvalue decomposition fails.
+pub fn spectrum(t: &Array2<f64>) -> Result<Array1<c64>, QtnError> {
+    check_square(t)?;
+
+    let (eigvals, _) = t
+        .eig()
+        .map_err(|err| QtnError::LinalgFailure(format!("Eigenvalue error: {:?}", err)))?;
+
+    let mut eigvals = eigvals.to_vec();
+    eigvals.sort_by(|a, b| b.norm().total_cmp(&a.norm()));
+
+    Ok(Array1::from(eigvals))
+}
+
+/// Computes the free-energy density in the thermodynamic limit from the leading eigenvalue.
+///
+/// The free energy per site is `f = -ln(lambda_max) / (beta * width)`, where `width` is the number
+/// of sites per column of the transfer matrix.
+///
+/// # Arguments
+/// - `t`: A square `Array2<f64>` transfer matrix.
+/// - `beta`: The inverse temperature used to build the Boltzmann weights.
+/// - `width`: The number of sites in one column.
+///
+/// # Returns
+/// A `Result<f64, QtnError>` containing the free-energy density, or an error if the
+/// spectrum cannot be computed, the matrix is empty or the leading eigenvalue is not positive.
+pub fn free_energy_density(t: &Array2<f64>, beta: f64, width: usize) -> Result<f64, QtnError> {
+    let lambda = *spectrum(t)?.first().ok_or_else(|| {
+        QtnError::InvalidArgument("Free energy needs a non-empty transfer matrix.".to_string())
+    })?;
+    if lambda.re <= 0.0 {
+        return Err(QtnError::InvalidArgument(format!(
+            "Leading eigenvalue is not positive ({}).",
+            lambda
+        )));
+    }
+
+    Ok(-lambda.norm().ln() / (beta * width as f64))
+}
+
+/// Computes the free-energy density of a finite chain of `length` columns.
+///
+/// # Arguments
+/// - `t`: A square `Array2<f64>` transfer matrix.
+/// - `length`: The number of columns in the chain.
+/// - `beta`: The inverse temperature used to build the Boltzmann weights.
+/// - `width`: The number of sites in one column.
+/// - `periodic`: Selects periodic (`true`) or open (`false`) boundary conditions along the chain.
+///
+/// # Returns
+/// A `Result<f64, QtnError>` containing `-ln(Z) / (beta * length * width)`, or an error
+/// under the same conditions as [`log_partition_function`].
+pub fn finite_free_energy_density(
+    t: &Array2<f64>,
+    length: usize,
+    beta: f64,
+    width: usize,
+    periodic: bool,
+) -> Result<f64, QtnError> {
+    let log_z = log_partition_function(t, length, periodic)?;
+    Ok(-log_z / (beta * (length * width) as f64))
+}
+
+/// Computes the correlation length from the two largest eigenvalues of a transfer matrix.
+///
+/// # Arguments
+/// - `t`: A square `Array2<f64>` transfer matrix of dimension at least two.
+///
+/// # Returns
+/// A `Result<f64, QtnError>` containing `1 / ln(|lambda_0| / |lambda_1|)`, which is infinite for a
+/// degenerate leading eigenvalue, or an error if the spectrum cannot be computed.
+pub fn correlation_length(t: &Array2<f64>) -> Result<f64, QtnError> {
+    let eigvals = spectrum(t)?;
+    if eigvals.len() < 2 {
+        return Err(QtnError::InvalidArgument(
+            "Correlation length needs at least two eigenvalues.".to_string(),
+        ));
+    }
+
+    Ok(1.0 / (eigvals[0].norm() / eigvals[1].norm()).ln())
+}
+
+/// Checks that the given transfer matrix is square.
+fn check_square(t: &Array2<f64>) -> Result<(), QtnError> {
+    if t.nrows() != t.ncols() {
+        return Err(QtnError::InvalidArgument(format!(
+            "Transfer matrix must be square, found shape ({}, {}).",
+            t.nrows(),
+            t.ncols()
+        )));
+    }
+    Ok(())
+}
+
+/// Divides the matrix by its largest absolute entry and returns the logarithm of that factor.
+fn rescale(m: &mut Array2<f64>) -> f64 {
+    let scale = m.iter().fold(0.0_f64, |a, &b| a.max(b.abs()));
+    if scale == 0.0 {
+        return 0.0;
+    }
+    m.mapv_inplace(|x| x / scale);
+    scale.ln()
+}
@@ -0,0 +1,164 @@
+use ndarray::{Array, Array2};
+use qua_ten_net::channel::*;
+use qua_ten_net::superop::kraus_to_superoperator;
+
+fn pauli_x() -> Array2<f64> {
+    Array::from_shape_vec((2, 2), vec![0.0, 1.0, 1.0, 0.0]).expect("ShapeError!")
+}
+
+fn amplitude_damping(gamma: f64) -> Vec<Array2<f64>> {
+    vec![
+        Array::from_shape_vec((2, 2), vec![1.0, 0.0, 0.0, (1.0 - gamma).sqrt()])
+            .expect("ShapeError!"),
+        Array::from_shape_vec((2, 2), vec![0.0, gamma.sqrt(), 0.0, 0.0]).expect("ShapeError!"),
+    ]
+}
+
+#[test]
+fn test_choi_from_superoperator() {
+    let kraus = amplitude_damping(0.3);
+    let superop = kraus_to_superoperator(&kraus).unwrap();
+    let choi = choi_from_superoperator(&superop, 2, 2).unwrap();
+    assert_eq!(choi, choi_from_kraus(&kraus).unwrap());
+    assert!((choi.diag().sum() - 2.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_choi_rank() {
+    let damping = choi_from_kraus(&amplitude_damping(0.3)).unwrap();
+    assert_eq!(choi_rank(&damping, 1e-10).unwrap(), 2);
+
+    let identity = choi_from_kraus(&[Array2::<f64>::eye(2)]).unwrap();
+    assert_eq!(choi_rank(&identity, 1e-10).unwrap(), 1);
+}
+
+#[test]
+fn test_process_fidelity() {
+    let identity = choi_from_kraus(&[Array2::<f64>::eye(2)]).unwrap();
+    let flip = choi_from_kraus(&[pauli_x()]).unwrap();
+    let damping = choi_from_kraus(&amplitude_damping(0.36)).unwrap();
+
+    assert!((process_fidelity(&identity, &identity, 2).unwrap() - 1.0).abs() < 1e-10);
+    assert!(process_fidelity(&identity, &flip, 2).unwrap().abs() < 1e-10);
+
+    // |Tr K_0|^2 / 4 = (1 + 0.8)^2 / 4
+    let f_pro = process_fidelity(&damping, &identity, 2).unwrap();
+    assert!((f_pro - 0.81).abs() < 1e-10);
+
+    let f_avg = average_gate_fidelity(&damping, &identity, 2).unwrap();
+    assert!((f_avg - (2.0 * 0.81 + 1.0) / 3.0).abs() < 1e-10);
+}
+
+#[test]
+fn test_diamond_norm_bounds() {
+    let identity = choi_from_kraus(&[Array2::<f64>::eye(2)]).unwrap();
+    let flip = choi_from_kraus(&[pauli_x()]).unwrap();
+
+    let (lower, upper) = diamond_norm_bounds(&identity, &flip, 2).unwrap();
+    assert!((lower - 2.0).abs() < 1e-10);
+    assert!((upper - 2.0).abs() < 1e-10);
+
+    let (lower, upper) = diamond_norm_bounds(&identity, &identity, 2).unwrap();
+    assert!(lower.abs() < 1e-10 && upper.abs() < 1e-10);
+}
+
+#[test]
+fn test_diamond_norm_bounds_fail_shape() {
+    let identity = choi_from_kraus(&[Array2::<f64>::eye(2)]).unwrap();
+    let err = diamond_norm_bounds(&identity, &Array2::eye(2), 2).unwrap_err();
+    assert_eq!(
+        "Choi matrices have different shapes: (4, 4) and (2, 2).",
+        err.to_string()
+    );
+}
+
+#[test]
+fn test_apply_instrument() {
+    let s = 1.0 / 2.0_f64.sqrt();
+    let psi = Array::from_vec(vec![s, 0.0, s, 0.0]);
+    let rho = Array2::from_shape_fn((4, 4), |(i, j)| psi[i] * psi[j]);
+    let projectors = vec![
+        Array::from_shape_vec((2, 2), vec![1.0, 0.0, 0.0, 0.0]).expect("ShapeError!"),
+        Array::from_shape_vec((2, 2), vec![0.0, 0.0, 0.0, 1.0]).expect("ShapeError!"),
+    ];
+
+    let outcomes = apply_instrument_to_state(&psi, &[2, 2], &[0], &projectors).unwrap();
+    assert!((outcomes[0].probability - 0.5).abs() < 1e-12);
+    assert!((outcomes[1].probability - 0.5).abs() < 1e-12);
+    assert_eq!(
+        outcomes[1].state.as_ref().unwrap().to_vec(),
+        vec![0.0, 0.0, 1.0, 0.0]
+    );
+
+    let outcomes = apply_instrument_to_state(&psi, &[2, 2], &[1], &projectors).unwrap();
+    assert!((outcomes[0].probability - 1.0).abs() < 1e-12);
+    assert!(outcomes[1].state.is_none());
+
+    let outcomes = apply_instrument(&rho, &[2, 2], &[0], &projectors).unwrap();
+    let post = outcomes[0].state.as_ref().unwrap();
+    assert!((outcomes[0].probability - 0.5).abs() < 1e-12);
+    assert!((post[[0, 0]] - 1.0).abs() < 1e-12);
+    assert!((post.sum() - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_povm_kraus() {
+    let e0 = Array::from_shape_vec((2, 2), vec![0.9, 0.0, 0.0, 0.2]).expect("ShapeError!");
+    let e1 = Array2::eye(2) - &e0;
+    let kraus = povm_kraus(&[e0.clone(), e1]).unwrap();
+
+    let s = 1.0 / 2.0_f64.sqrt();
+    let psi = Array::from_vec(vec![s, s]);
+    let outcomes = apply_instrument_to_state(&psi, &[2], &[0], &kraus).unwrap();
+    assert!((outcomes[0].probability - 0.55).abs() < 1e-12);
+    assert!((outcomes[1].probability - 0.45).abs() < 1e-12);
+
+    let err = povm_kraus(&[e0]).unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("Kraus operators do not satisfy the completeness relation"));
+}
+
+#[test]
+fn test_partial_trace() {
+    // Bell state (|00> + |11>) / sqrt(2): each qubit is maximally mixed
+    let psi = Array::from_vec(vec![1.0, 0.0, 0.0, 1.0]) / 2.0_f64.sqrt();
+    let bell = Array2::from_shape_fn((4, 4), |(i, j)| psi[i] * psi[j]);
+    for keep in [[0], [1]] {
+        let reduced = partial_trace(&bell, &[2, 2], &keep).unwrap();
+        assert!((&reduced - &(Array2::<f64>::eye(2) * 0.5))
+            .iter()
+            .all(|x| x.abs() < 1e-12));
+    }
+
+    // Product state rho_a (x) rho_b (x) rho_c of a qubit, a qutrit and a qubit
+    let rho_a = Array::from_shape_vec((2, 2), vec![0.7, 0.1, 0.1, 0.3]).expect("ShapeError!");
+    let rho_b = Array2::from_diag(&Array::from_vec(vec![0.5, 0.3, 0.2]));
+    let rho_c = Array::from_shape_vec((2, 2), vec![0.4, -0.2, -0.2, 0.6]).expect("ShapeError!");
+    let kron = |x: &Array2<f64>, y: &Array2<f64>| ndarray::linalg::kron(x, y);
+    let rho = kron(&kron(&rho_a, &rho_b), &rho_c);
+    let dims = [2, 3, 2];
+
+    let close = |x: &Array2<f64>, y: &Array2<f64>| (x - y).iter().all(|d| d.abs() < 1e-12);
+    assert!(close(&partial_trace(&rho, &dims, &[1]).unwrap(), &rho_b));
+    assert!(close(
+        &partial_trace(&rho, &dims, &[0, 2]).unwrap(),
+        &kron(&rho_a, &rho_c)
+    ));
+    // The kept subsystems appear in the requested order
+    assert!(close(
+        &partial_trace(&rho, &dims, &[2, 0]).unwrap(),
+        &kron(&rho_c, &rho_a)
+    ));
+    assert!(close(
+        &partial_trace(&rho, &dims, &[0, 1, 2]).unwrap(),
+        &rho
+    ));
+    let trace = partial_trace(&rho, &dims, &[]).unwrap();
+    assert_eq!(trace.dim(), (1, 1));
+    assert!((trace[[0, 0]] - 1.0).abs() < 1e-12);
+
+    assert!(partial_trace(&rho, &[2, 2], &[0]).is_err());
+    assert!(partial_trace(&rho, &dims, &[0, 0]).is_err());
+    assert!(partial_trace(&rho, &dims, &[3]).is_err());
+}
@@ -0,0 +1,84 @@
+use ndarray::{arr1, Array2, ArrayD, IxDyn};
+use qua_ten_net::diag::DiagTensor;
+use qua_ten_net::error::QtnError;
+use qua_ten_net::tencon::contract;
+use qua_ten_net::tensor::{random, svd};
+
+#[test]
+fn test_diag_dense() {
+    let diag = DiagTensor::new(arr1(&[1.0, 2.0, 3.0]));
+    assert_eq!(diag.dim(), 3);
+    assert_eq!(diag.to_dense(), Array2::from_diag(&arr1(&[1.0, 2.0, 3.0])));
+    assert_eq!(
+        diag.sqrt().weights(),
+        &arr1(&[1.0, 2.0_f64.sqrt(), 3.0_f64.sqrt()])
+    );
+    assert_eq!(diag.inverse(1e-12).weights(), &arr1(&[1.0, 0.5, 1.0 / 3.0]));
+
+    let cut = DiagTensor::new(arr1(&[2.0, 1e-15, 0.0]));
+    assert_eq!(cut.inverse(1e-12).weights(), &arr1(&[0.5, 0.0, 0.0]));
+
+    let product = diag
+        .contract(&DiagTensor::new(arr1(&[2.0, 2.0, 0.5])))
+        .unwrap();
+    assert_eq!(product.weights(), &arr1(&[2.0, 4.0, 1.5]));
+}
+
+#[test]
+fn test_diag_apply() {
+    let tnsr = random(&[2, 3, 4]);
+    let diag = DiagTensor::new(arr1(&[0.5, -1.0, 2.0]));
+
+    let tst = diag.apply(&tnsr, 1).unwrap();
+    let dense = diag.to_dense().into_dyn();
+    let rslt = contract(&[tnsr, dense], &[&[-1, 1, -3], &[1, -2]]).unwrap();
+
+    assert_eq!(tst.shape(), &[2, 3, 4]);
+    assert!(tst
+        .iter()
+        .zip(rslt.iter())
+        .all(|(a, b)| (a - b).abs() < 1e-12));
+}
+
+#[test]
+fn test_diag_svd() {
+    let tnsr = random(&[4, 3]).into_dimensionality().unwrap();
+    let result = svd(tnsr.clone()).unwrap();
+
+    let us = result
+        .sigma_diag()
+        .apply(
+            &result.u.slice(ndarray::s![.., ..3]).to_owned().into_dyn(),
+            1,
+        )
+        .unwrap();
+    let rebuilt = contract(&[us, result.vt.clone().into_dyn()], &[&[-1, 1], &[1, -2]]).unwrap();
+
+    assert!(rebuilt
+        .iter()
+        .zip(tnsr.iter())
+        .all(|(a, b)| (a - b).abs() < 1e-10));
+}
+
+#[test]
+fn test_diag_fail() {
+    let tnsr = ArrayD::<f64>::zeros(IxDyn(&[2, 3]));
+    let diag = DiagTensor::new(arr1(&[1.0, 2.0]));
+
+    assert!(matches!(
+        diag.apply(&tnsr, 2),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert_eq!(
+        diag.apply(&tnsr, 1),
+        Err(QtnError::ShapeMismatch {
+            tensor: 0,
+            axis: 1,
+            dim: 3,
+            other_tensor: 1,
+            other_axis: 0,
+            other_dim: 2,
+        })
+    );
+    assert!(diag.contract(&DiagTensor::new(arr1(&[1.0]))).is_err());
+}
@@ -0,0 +1,124 @@
+use ndarray::{Array, Array2, ArrayD};
+use qua_ten_net::einsum::{contract_labeled, einsum};
+use qua_ten_net::tencon::contract;
+use qua_ten_net::tensor;
+
+fn range(shape: &[usize]) -> ArrayD<f64> {
+    let size = shape.iter().product();
+    Array::from_shape_vec(shape.to_vec(), (0..size).map(|x| x as f64).collect())
+        .expect("ShapeError!")
+}
+
+#[test]
+fn test_einsum_matches_contract() {
+    let a = tensor::random(&[2, 3, 4]);
+    let b = tensor::random(&[4, 5]);
+    let c = tensor::random(&[5, 3]);
+
+    let result = einsum("abc,cd,db->a", &[a.clone(), b.clone(), c.clone()]).unwrap();
+    let correct = contract(&[a, b, c], &[&[-1, 1, 2], &[2, 3], &[3, 1]]).unwrap();
+
+    assert_eq!(result.shape(), &[2]);
+    for (x, y) in result.iter().zip(correct.iter()) {
+        assert!((x - y).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_einsum_output_order() {
+    let a = range(&[2, 3]);
+    let b = range(&[3, 4]);
+
+    let ab = einsum("ij,jk->ik", &[a.clone(), b.clone()]).unwrap();
+    let ba = einsum("ij,jk->ki", &[a.clone(), b.clone()]).unwrap();
+    let implicit = einsum("ij,jk", &[a.clone(), b.clone()]).unwrap();
+
+    let a2 = a.into_dimensionality::<ndarray::Ix2>().unwrap();
+    let b2 = b.into_dimensionality::<ndarray::Ix2>().unwrap();
+    let product: Array2<f64> = a2.dot(&b2);
+
+    assert_eq!(ab, product.clone().into_dyn());
+    assert_eq!(ba, product.t().to_owned().into_dyn());
+    assert_eq!(implicit, product.into_dyn());
+}
+
+#[test]
+fn test_einsum_single_tensor() {
+    let a = range(&[3, 3]);
+
+    assert_eq!(
+        einsum("ii->", std::slice::from_ref(&a)).unwrap().sum(),
+        12.0
+    );
+    assert_eq!(
+        einsum("ij->ji", std::slice::from_ref(&a)).unwrap(),
+        a.t().to_owned()
+    );
+    assert_eq!(
+        einsum("ij->i", std::slice::from_ref(&a)).unwrap(),
+        Array::from_shape_vec(vec![3], vec![3.0, 12.0, 21.0]).expect("ShapeError!")
+    );
+}
+
+#[test]
+fn test_einsum_outer_product() {
+    let a = range(&[2]);
+    let b = range(&[3]);
+
+    let result = einsum("i,j->ij", &[a, b]).unwrap();
+    let correct =
+        Array::from_shape_vec(vec![2, 3], vec![0.0, 0.0, 0.0, 0.0, 1.0, 2.0]).expect("ShapeError!");
+    assert_eq!(result, correct);
+}
+
+#[test]
+fn test_einsum_fail() {
+    let a = range(&[2, 3]);
+    let b = range(&[3, 4]);
+
+    assert!(einsum("ij,jk->ik", std::slice::from_ref(&a)).is_err());
+    assert!(einsum("ijk,jk->i", &[a.clone(), b.clone()]).is_err());
+    assert!(einsum("ij,ik->jk", &[a.clone(), b.clone()]).is_err());
+    assert!(einsum("ij,jk->ix", &[a.clone(), b.clone()]).is_err());
+    assert!(einsum("ij,jk->ij", &[a.clone(), b.clone()]).is_err());
+    assert!(einsum("i1,jk->ik", &[a, b]).is_err());
+}
+
+#[test]
+fn test_contract_labeled() {
+    let a = tensor::random(&[2, 3, 4]);
+    let b = tensor::random(&[4, 5]);
+    let c = tensor::random(&[5, 3]);
+
+    let result = contract_labeled(
+        &[a.clone(), b.clone(), c.clone()],
+        &[
+            &["left", "mid", "bond"],
+            &["bond", "right"],
+            &["right", "mid"],
+        ],
+        &["left"],
+    )
+    .unwrap();
+    let correct = einsum("abc,cd,db->a", &[a, b, c]).unwrap();
+
+    assert_eq!(result.shape(), &[2]);
+    for (x, y) in result.iter().zip(correct.iter()) {
+        assert!((x - y).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_contract_labeled_fail() {
+    let a = range(&[2, 3]);
+    let b = range(&[3, 4]);
+
+    assert!(contract_labeled(&[a.clone(), b.clone()], &[&["i", "j"], &["j"]], &["i"]).is_err());
+    assert!(contract_labeled(
+        &[a.clone(), b.clone()],
+        &[&["i", "j"], &["i", "k"]],
+        &["j", "k"]
+    )
+    .is_err());
+    assert!(contract_labeled(&[a, b], &[&["i", "j"], &["j", "k"]], &["i", "i"]).is_err());
+}
@@ -0,0 +1,96 @@
+use ndarray::{Array, ArrayD};
+use qua_ten_net::index::{contract_indexed, Index, Tensor};
+use qua_ten_net::tencon::contract;
+
+fn range(shape: &[usize]) -> ArrayD<f64> {
+    let size = shape.iter().product();
+    Array::from_shape_vec(shape.to_vec(), (0..size).map(|x| x as f64).collect())
+        .expect("ShapeError!")
+}
+
+#[test]
+fn test_index_identity() {
+    let i = Index::with_tags(2, &["Site", "n=1"]);
+    let j = Index::new(2);
+
+    assert_ne!(i, j);
+    assert_eq!(i, i.clone());
+    assert_ne!(i, i.prime());
+    assert_eq!(i.prime().id(), i.id());
+    assert_eq!(i.prime().prime_level(), 1);
+    assert_eq!(i.prime().prime().noprime(), i);
+    assert!(i.has_tag("Site"));
+    assert!(!j.has_tag("Site"));
+}
+
+#[test]
+fn test_contract_indexed() {
+    let (i, j, k, l) = (Index::new(2), Index::new(3), Index::new(4), Index::new(5));
+
+    let a = Tensor::new(range(&[2, 3, 4]), &[i.clone(), j.clone(), k.clone()]).unwrap();
+    let b = Tensor::new(range(&[5, 4]), &[l.clone(), k.clone()]).unwrap();
+    let c = Tensor::new(range(&[3]), std::slice::from_ref(&j)).unwrap();
+
+    let result = contract_indexed(&[a.clone(), b.clone(), c.clone()]).unwrap();
+    let correct = contract(
+        &[a.into_data(), b.into_data(), c.into_data()],
+        &[&[-1, 1, 2], &[-2, 2], &[1]],
+    )
+    .unwrap();
+
+    assert_eq!(result.indices(), &[i, l]);
+    assert_eq!(result.data(), &correct);
+}
+
+#[test]
+fn test_tensor_permute_and_prime() {
+    let (i, j) = (Index::new(2), Index::new(3));
+    let a = Tensor::new(range(&[2, 3]), &[i.clone(), j.clone()]).unwrap();
+
+    let at = a.permute(&[j.clone(), i.clone()]).unwrap();
+    assert_eq!(at.data(), &a.data().t().to_owned());
+
+    // <a|a> through matching indices, and an outer product once the copy is primed
+    let norm = a.contract(&a).unwrap();
+    assert_eq!(
+        norm.data().sum(),
+        (0..6).map(|x| (x * x) as f64).sum::<f64>()
+    );
+    let outer = a.contract(&a.prime()).unwrap();
+    assert_eq!(outer.data().shape(), &[2, 3, 2, 3]);
+}
+
+#[test]
+fn test_tensor_fail() {
+    let (i, j) = (Index::new(2), Index::new(3));
+
+    assert!(Tensor::new(range(&[2, 3]), std::slice::from_ref(&i)).is_err());
+    assert!(Tensor::new(range(&[2, 3]), &[j.clone(), i.clone()]).is_err());
+    assert!(Tensor::new(range(&[2, 2]), &[i.clone(), i.clone()]).is_err());
+
+    let a = Tensor::new(range(&[2]), std::slice::from_ref(&i)).unwrap();
+    assert!(contract_indexed(&[a.clone(), a.clone(), a.clone()]).is_err());
+    assert!(a.permute(&[j]).is_err());
+}
+
+#[test]
+fn test_tensor_shared_data() {
+    let (i, j) = (Index::new(2), Index::new(3));
+    let a = Tensor::new(range(&[2, 3]), &[i.clone(), j.clone()]).unwrap();
+
+    // Copies, primed copies and permutations share the buffer
+    let mut b = a.clone();
+    assert!(b.shares_data(&a));
+    assert!(a.prime().shares_data(&a));
+    assert!(a.permute(&[j, i]).unwrap().shares_data(&a));
+
+    // Writing to a shared tensor copies the buffer first
+    b.data_mut()[[0, 0]] = 100.0;
+    assert!(!b.shares_data(&a));
+    assert_eq!(a.data()[[0, 0]], 0.0);
+    assert_eq!(b.data()[[0, 0]], 100.0);
+
+    // Handles can be moved to other threads
+    let handle = std::thread::spawn(move || b.data().sum());
+    assert_eq!(handle.join().unwrap(), 115.0);
+}
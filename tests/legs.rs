@@ -0,0 +1,123 @@
+use ndarray::Array2;
+use qua_ten_net::error::QtnError;
+use qua_ten_net::legs::{fuse_legs, moveaxis, permute, split_leg, swapaxes, transpose_to};
+use qua_ten_net::tensor::{random, svd};
+
+#[test]
+fn test_fuse_legs() {
+    let tensor = random(&[2, 3, 4, 5]);
+    let (matrix, fused) = fuse_legs(&tensor, &[vec![2, 0], vec![3, 1]]).unwrap();
+    assert_eq!(matrix.shape(), &[8, 15]);
+    assert_eq!(fused.leg_dims(0).unwrap(), vec![4, 2]);
+    assert_eq!(fused.leg_dims(1).unwrap(), vec![5, 3]);
+    assert!(matches!(
+        fused.leg_dims(2),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert_eq!(fused.fused_shape(), vec![8, 15]);
+    assert_eq!(fused.groups(), &[vec![2, 0], vec![3, 1]]);
+    assert_eq!(fused.shape(), &[2, 3, 4, 5]);
+
+    // Element (a, b, c, d) sits at row c * 2 + a and column d * 3 + b
+    assert_eq!(matrix[[3 * 2 + 1, 4 * 3 + 2]], tensor[[1, 2, 3, 4]]);
+    assert_eq!(fused.unfuse(&matrix).unwrap(), tensor);
+
+    // Splitting the legs of the factors of an SVD of the fused matrix
+    let matrix: Array2<f64> = matrix.into_dimensionality().unwrap();
+    let result = svd(matrix).unwrap();
+    let u = split_leg(&result.u.into_dyn(), 0, &fused.leg_dims(0).unwrap()).unwrap();
+    assert_eq!(u.shape(), &[4, 2, 8]);
+    let vt = split_leg(&result.vt.into_dyn(), 1, &fused.leg_dims(1).unwrap()).unwrap();
+    assert_eq!(vt.shape(), &[15, 5, 3]);
+
+    // A single group gives a vector
+    let (vector, fused) = fuse_legs(&tensor, &[vec![0, 1, 2, 3]]).unwrap();
+    assert_eq!(vector.shape(), &[120]);
+    assert_eq!(fused.unfuse(&vector).unwrap(), tensor);
+}
+
+#[test]
+fn test_fuse_legs_fail() {
+    let tensor = random(&[2, 3, 4]);
+    for groups in [
+        vec![vec![0, 1]],
+        vec![vec![0, 1], vec![1, 2]],
+        vec![vec![0, 1, 2, 3]],
+    ] {
+        assert!(matches!(
+            fuse_legs(&tensor, &groups),
+            Err(QtnError::InvalidArgument(_))
+        ));
+    }
+
+    let (_, fused) = fuse_legs(&tensor, &[vec![0], vec![1, 2]]).unwrap();
+    assert!(matches!(
+        fused.unfuse(&tensor),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        split_leg(&tensor, 2, &[3, 2]),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        split_leg(&tensor, 3, &[1]),
+        Err(QtnError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn test_axis_helpers() {
+    let tensor = random(&[2, 3, 4, 5]);
+
+    let moved = moveaxis(tensor.view(), 3, 1).unwrap();
+    assert_eq!(moved.shape(), &[2, 5, 3, 4]);
+    assert_eq!(moved[[1, 4, 2, 3]], tensor[[1, 2, 3, 4]]);
+    let back = moveaxis(moved, 1, 3).unwrap();
+    assert_eq!(back, tensor);
+
+    let swapped = swapaxes(tensor.clone(), 0, 2).unwrap();
+    assert_eq!(swapped.shape(), &[4, 3, 2, 5]);
+    assert_eq!(swapped[[3, 2, 1, 4]], tensor[[1, 2, 3, 4]]);
+
+    let permuted = permute(tensor.view(), &[3, 0, 2, 1]).unwrap();
+    assert_eq!(permuted.shape(), &[5, 2, 4, 3]);
+
+    // Legs addressed by name
+    let legs = ["left", "phys", "right", "aux"];
+    let target = ["aux", "left", "right", "phys"];
+    let labeled = transpose_to(tensor.view(), &legs, &target).unwrap();
+    assert_eq!(labeled, permuted);
+    let labeled = transpose_to(tensor.view(), &[-1, 1, 2, -2], &[-2, -1, 2, 1]).unwrap();
+    assert_eq!(labeled, permuted);
+}
+
+#[test]
+fn test_axis_helpers_fail() {
+    let tensor = random(&[2, 3, 4]);
+    assert!(matches!(
+        moveaxis(tensor.view(), 3, 0),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        moveaxis(tensor.view(), 0, 3),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        swapaxes(tensor.view(), 1, 5),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    for perm in [&[0, 1][..], &[0, 1, 1], &[0, 1, 3], &[0, 1, 2, 3]] {
+        assert!(matches!(
+            permute(tensor.view(), perm),
+            Err(QtnError::InvalidArgument(_))
+        ));
+    }
+    assert!(matches!(
+        transpose_to(tensor.view(), &["a", "b", "c"], &["a", "c", "d"]),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        transpose_to(tensor.view(), &["a", "b"], &["b", "a"]),
+        Err(QtnError::InvalidArgument(_))
+    ));
+}
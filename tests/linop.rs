@@ -0,0 +1,331 @@
+use ndarray::{Array1, Array2, ArrayD};
+use ndarray_linalg::c64;
+use qua_ten_net::error::QtnError;
+use qua_ten_net::linop::*;
+
+// Nonsymmetric, diagonally dominant convection-diffusion stencil
+fn stencil(n: usize) -> Array2<f64> {
+    Array2::from_shape_fn((n, n), |(i, j)| match i as i64 - j as i64 {
+        0 => 4.0,
+        1 => -1.5,
+        -1 => -0.5,
+        _ => 0.0,
+    })
+}
+
+#[test]
+fn test_gmres() {
+    let a = stencil(30);
+    let expected = Array1::from_shape_fn(30, |i| (i as f64 * 0.3).sin());
+    let b = a.dot(&expected);
+
+    let x = gmres(&a, &b, None, 1e-12, 200, 30).unwrap();
+    assert!((&x - &expected).iter().all(|e| e.abs() < 1e-9));
+
+    // A short restart cycle needs more iterations but reaches the same solution
+    let x = gmres(&a, &b, None, 1e-12, 500, 5).unwrap();
+    assert!((&x - &expected).iter().all(|e| e.abs() < 1e-9));
+
+    // Starting from the solution needs a single operator application
+    let count = std::cell::Cell::new(0);
+    let op = FnOperator::new(30, |v: &Array1<f64>| {
+        count.set(count.get() + 1);
+        a.dot(v)
+    });
+    assert_eq!(gmres(&op, &b, Some(&x), 1e-6, 10, 5).unwrap(), x);
+    assert_eq!(count.get(), 1);
+}
+
+#[test]
+fn test_bicgstab() {
+    let a = stencil(30);
+    let expected = Array1::from_shape_fn(30, |i| 1.0 / (i as f64 + 1.0));
+    let b = a.dot(&expected);
+
+    let op = FnOperator::new(30, |v: &Array1<f64>| a.dot(v));
+    let x = bicgstab(&op, &b, None, 1e-12, 200).unwrap();
+    assert!((&x - &expected).iter().all(|e| e.abs() < 1e-9));
+}
+
+#[test]
+fn test_complex_solvers() {
+    let n = 12;
+    let a = Array2::from_shape_fn((n, n), |(i, j)| {
+        if i == j {
+            c64::new(3.0, 1.0)
+        } else if i + 1 == j {
+            c64::new(0.0, -1.0)
+        } else if j + 1 == i {
+            c64::new(0.5, 0.5)
+        } else {
+            c64::new(0.0, 0.0)
+        }
+    });
+    let expected = Array1::from_shape_fn(n, |i| c64::new(i as f64, 1.0 - i as f64));
+    let b = a.dot(&expected);
+
+    for x in [
+        gmres(&a, &b, None, 1e-12, 100, 12).unwrap(),
+        bicgstab(&a, &b, None, 1e-12, 100).unwrap(),
+    ] {
+        assert!((&x - &expected).iter().all(|e| e.norm() < 1e-9));
+    }
+}
+
+#[test]
+fn test_solver_errors() {
+    let a = stencil(4);
+    let b = Array1::ones(3);
+    assert!(matches!(
+        gmres(&a, &b, None, 1e-12, 10, 4),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        bicgstab(&a, &Array1::ones(4), Some(&b), 1e-12, 10),
+        Err(QtnError::InvalidArgument(_))
+    ));
+
+    let a = stencil(50);
+    let b = Array1::ones(50);
+    assert!(matches!(
+        gmres(&a, &b, None, 1e-14, 3, 3),
+        Err(QtnError::NotConverged(_))
+    ));
+    assert!(matches!(
+        bicgstab(&a, &b, None, 1e-14, 2),
+        Err(QtnError::NotConverged(_))
+    ));
+}
+
+#[test]
+fn test_lanczos() {
+    // Open tight-binding chain, eigenvalues 2 cos(pi j / (n + 1))
+    let n = 40;
+    let hopping = |v: &ArrayD<f64>| {
+        let v = v.view().into_shape_with_order(n).unwrap();
+        Array1::from_shape_fn(n, |i| {
+            let left = if i > 0 { v[i - 1] } else { 0.0 };
+            let right = if i + 1 < n { v[i + 1] } else { 0.0 };
+            -left - right
+        })
+        .into_dyn()
+    };
+    let guess = Array1::from_shape_fn(n, |i| 1.0 + 0.1 * i as f64).into_dyn();
+    let (values, vectors) = lanczos(hopping, &guess, 3, 1e-10, n).unwrap();
+
+    for (j, (&value, vector)) in values.iter().zip(&vectors).enumerate() {
+        let angle = std::f64::consts::PI * (j + 1) as f64 / (n + 1) as f64;
+        assert!((value + 2.0 * angle.cos()).abs() < 1e-9);
+        let residual = hopping(vector) - vector.mapv(|x| x * value);
+        assert!(residual.iter().all(|x| x.abs() < 1e-8));
+        assert!((vector.iter().map(|x| x * x).sum::<f64>() - 1.0).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_lanczos_tensor_and_degenerate() {
+    // Two decoupled spins with H = Z (x) 1 + 1 (x) Z on a 2x2 tensor: eigenvalues -2, 0, 0, 2
+    let zz = |v: &ArrayD<c64>| {
+        ArrayD::from_shape_fn(v.raw_dim(), |i| {
+            let sign = |s: usize| if s == 0 { 1.0 } else { -1.0 };
+            v[&i] * (sign(i[0]) + sign(i[1]))
+        })
+    };
+    let guess = ArrayD::from_elem(vec![2, 2], c64::new(0.5, 0.5));
+    let (values, vectors) = lanczos(zz, &guess, 3, 1e-12, 4).unwrap();
+
+    assert_eq!(vectors[0].shape(), &[2, 2]);
+    let expected = [-2.0, 0.0, 0.0];
+    assert!(values
+        .iter()
+        .zip(&expected)
+        .all(|(x, y): (&f64, &f64)| (x - y).abs() < 1e-10));
+    assert!((vectors[0][[1, 1]].norm() - 1.0).abs() < 1e-10);
+
+    assert!(matches!(
+        lanczos(zz, &guess, 5, 1e-12, 4),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        lanczos(zz, &ArrayD::zeros(vec![2, 2]), 1, 1e-12, 4),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        lanczos(
+            |v: &ArrayD<c64>| v.sum_axis(ndarray::Axis(0)),
+            &guess,
+            1,
+            1e-12,
+            4
+        ),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        lanczos(zz, &guess, 3, 1e-12, 2),
+        Err(QtnError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn test_lanczos_not_converged() {
+    // A diagonal operator with distinct eigenvalues needs the full basis to resolve its spectrum
+    let n = 30;
+    let diagonal =
+        |v: &ArrayD<f64>| ArrayD::from_shape_fn(v.raw_dim(), |i| v[&i] * (i[0] as f64).powi(2));
+    let guess = ArrayD::from_elem(vec![n], 1.0);
+
+    assert!(matches!(
+        lanczos(diagonal, &guess, 1, 1e-12, 5),
+        Err(QtnError::NotConverged(_))
+    ));
+    let (values, _) = lanczos(diagonal, &guess, 1, 1e-10, n).unwrap();
+    assert!(values[0].abs() < 1e-8);
+}
+
+// Block upper triangular matrix with eigenvalues 3 +- i, 0.5 +- 2i, 2.5, -4, 1 and 0.2
+fn nonnormal() -> Array2<f64> {
+    let blocks: [(usize, usize); 6] = [(0, 2), (2, 4), (4, 5), (5, 6), (6, 7), (7, 8)];
+    let mut m = Array2::from_shape_fn((8, 8), |(i, j)| {
+        if blocks.iter().position(|b| i < b.1).unwrap()
+            < blocks.iter().position(|b| j < b.1).unwrap()
+        {
+            0.3 * ((i + 2 * j) % 5) as f64 - 0.5
+        } else {
+            0.0
+        }
+    });
+    for (i, j, v) in [
+        (0, 0, 3.0),
+        (0, 1, -1.0),
+        (1, 0, 1.0),
+        (1, 1, 3.0),
+        (2, 2, 0.5),
+        (2, 3, -2.0),
+        (3, 2, 2.0),
+        (3, 3, 0.5),
+        (4, 4, 2.5),
+        (5, 5, -4.0),
+        (6, 6, 1.0),
+        (7, 7, 0.2),
+    ] {
+        m[[i, j]] = v;
+    }
+    m
+}
+
+#[test]
+fn test_arnoldi() {
+    let m = nonnormal();
+    let matvec = |v: &ArrayD<f64>| {
+        m.dot(&v.view().into_shape_with_order(8).unwrap())
+            .into_shape_with_order(v.raw_dim())
+            .unwrap()
+    };
+    let guess = ArrayD::from_elem(vec![2, 4], 1.0);
+    let close = |x: c64, y: c64| (x - y).norm() < 1e-8;
+
+    let (values, vectors) = arnoldi(matvec, &guess, 3, Which::LargestMagnitude, 1e-10, 8).unwrap();
+    assert!(close(values[0], c64::new(-4.0, 0.0)));
+    assert!(close(values[1], c64::new(3.0, 1.0)) || close(values[1], c64::new(3.0, -1.0)));
+    assert!(close(values[1].conj(), values[2]));
+
+    let complex = m.mapv(|x| c64::new(x, 0.0));
+    for (&value, vector) in values.iter().zip(&vectors) {
+        assert_eq!(vector.shape(), &[2, 4]);
+        let x = vector.view().into_shape_with_order(8).unwrap();
+        let residual = complex.dot(&x) - x.mapv(|e| e * value);
+        assert!(residual.iter().all(|e| e.norm() < 1e-8));
+        assert!((x.iter().map(|e| e.norm_sqr()).sum::<f64>() - 1.0).abs() < 1e-10);
+    }
+
+    let (values, _) = arnoldi(matvec, &guess, 3, Which::LargestReal, 1e-10, 8).unwrap();
+    assert!((values[0].re - 3.0).abs() < 1e-8 && (values[1].re - 3.0).abs() < 1e-8);
+    assert!(close(values[2], c64::new(2.5, 0.0)));
+
+    assert!(matches!(
+        arnoldi(matvec, &guess, 0, Which::LargestReal, 1e-10, 8),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        arnoldi(matvec, &guess, 3, Which::LargestReal, 1e-10, 2),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        arnoldi(matvec, &guess, 3, Which::LargestMagnitude, 1e-10, 4),
+        Err(QtnError::NotConverged(_))
+    ));
+}
+
+#[test]
+fn test_lobpcg() {
+    // Periodic ring of 12 sites, eigenvalues -2 cos(2 pi j / 12) with doubly degenerate levels
+    let n = 12;
+    let ring = |v: &ArrayD<f64>| {
+        ArrayD::from_shape_fn(v.raw_dim(), |i| {
+            -v[[(i[0] + 1) % n]] - v[[(i[0] + n - 1) % n]]
+        })
+    };
+    let guess: Vec<ArrayD<f64>> = (0..3)
+        .map(|j| ArrayD::from_shape_fn(vec![n], |i| ((i[0] * (j + 2) + j) % 7) as f64 - 3.0))
+        .collect();
+    let (values, vectors) = lobpcg(ring, &guess, None, 1e-9, 300).unwrap();
+
+    let root = 3f64.sqrt();
+    let expected = [-2.0, -root, -root];
+    assert!(values
+        .iter()
+        .zip(&expected)
+        .all(|(x, y): (&f64, &f64)| (x - y).abs() < 1e-9));
+    for (&value, vector) in values.iter().zip(&vectors) {
+        let residual = ring(vector) - vector.mapv(|x| x * value);
+        assert!(residual.iter().all(|x| x.abs() < 1e-8));
+    }
+    let overlap: f64 = vectors[1]
+        .iter()
+        .zip(vectors[2].iter())
+        .map(|(a, b)| a * b)
+        .sum();
+    assert!(overlap.abs() < 1e-10);
+
+    // Diagonally dominant operator with a diagonal preconditioner
+    let diag = Array1::from_shape_fn(30, |i| 1.0 + i as f64);
+    let h = Array2::from_shape_fn((30, 30), |(i, j)| {
+        if i == j {
+            diag[i]
+        } else if i.abs_diff(j) == 1 {
+            0.3
+        } else {
+            0.0
+        }
+    });
+    let matvec = |v: &ArrayD<f64>| {
+        h.dot(&v.view().into_shape_with_order(30).unwrap())
+            .into_dyn()
+    };
+    let precondition = |r: &ArrayD<f64>| ArrayD::from_shape_fn(r.raw_dim(), |i| r[&i] / diag[i[0]]);
+    let guess: Vec<ArrayD<f64>> = (0..2)
+        .map(|j| ArrayD::from_shape_fn(vec![30], |i| if i[0] == j { 1.0 } else { 0.01 }))
+        .collect();
+    let (values, _) = lobpcg(matvec, &guess, Some(&precondition), 1e-10, 100).unwrap();
+    let exact = qua_ten_net::tensor::eigh(&h).unwrap().0;
+    assert!((values[0] - exact[0]).abs() < 1e-9 && (values[1] - exact[1]).abs() < 1e-9);
+
+    assert!(matches!(
+        lobpcg(ring, &[], None, 1e-9, 10),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        lobpcg(
+            ring,
+            &[guess[0].clone(), guess[0].mapv(|x| 2.0 * x)],
+            None,
+            1e-9,
+            10
+        ),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        lobpcg(matvec, &guess, None, 1e-14, 1),
+        Err(QtnError::NotConverged(_))
+    ));
+}
@@ -0,0 +1,208 @@
+use ndarray::Ix2;
+use qua_ten_net::error::QtnError;
+use qua_ten_net::network::{canonicalize, find_isomorphism, signature, Layer, TensorNetwork};
+use qua_ten_net::tencon::contract;
+use qua_ten_net::tensor;
+use qua_ten_net::trace::trace;
+
+#[test]
+fn test_signature_relabeled() {
+    // Open ring of four tensors, and the same ring with shuffled tensors and renamed bonds
+    let shapes = vec![vec![2, 3, 4], vec![4, 3, 5], vec![5, 3, 6], vec![6, 3, 2]];
+    let order: [&[i32]; 4] = [&[1, -1, 2], &[2, -2, 3], &[3, -3, 4], &[4, -4, 1]];
+    let other_shapes = vec![vec![5, 3, 6], vec![2, 3, 4], vec![6, 3, 2], vec![4, 3, 5]];
+    let other_order: [&[i32]; 4] = [&[7, -3, 9], &[8, -1, 10], &[9, -4, 8], &[10, -2, 7]];
+
+    let sig = signature(&shapes, &order).unwrap();
+    assert_eq!(sig, signature(&other_shapes, &other_order).unwrap());
+
+    let map = find_isomorphism(&shapes, &order, &other_shapes, &other_order)
+        .unwrap()
+        .unwrap();
+    assert_eq!(map, vec![1, 3, 0, 2]);
+
+    // Corresponding tensors contract to the same result
+    let tensors: Vec<_> = shapes.iter().map(|s| tensor::random(s)).collect();
+    let mut other = tensors.clone();
+    for (k, &m) in map.iter().enumerate() {
+        other[m] = tensors[k].clone();
+    }
+    let con = contract(&tensors, &order).unwrap();
+    let other_con = contract(&other, &other_order).unwrap();
+    assert!((&con - &other_con).iter().all(|x| x.abs() < 1e-12));
+}
+
+#[test]
+fn test_signature_distinguishes() {
+    let shapes = vec![vec![2, 2]; 4];
+
+    // One closed ring of four matrices and two rings of two look alike to every tensor
+    let ring: [&[i32]; 4] = [&[1, 2], &[2, 3], &[3, 4], &[4, 1]];
+    let pairs: [&[i32]; 4] = [&[1, 2], &[2, 1], &[3, 4], &[4, 3]];
+    assert_ne!(
+        signature(&shapes, &ring).unwrap(),
+        signature(&shapes, &pairs).unwrap()
+    );
+    assert_eq!(find_isomorphism(&shapes, &ring, &shapes, &pairs), Ok(None));
+
+    // The order of the free legs and the axes the bonds attach to are part of the signature
+    let open: [&[i32]; 4] = [&[-1, 2], &[2, 3], &[3, 4], &[4, -2]];
+    let swapped: [&[i32]; 4] = [&[-2, 2], &[2, 3], &[3, 4], &[4, -1]];
+    let transposed: [&[i32]; 4] = [&[-1, 2], &[3, 2], &[3, 4], &[4, -2]];
+    assert_ne!(
+        signature(&shapes, &open).unwrap(),
+        signature(&shapes, &swapped).unwrap()
+    );
+    assert_ne!(
+        signature(&shapes, &open).unwrap(),
+        signature(&shapes, &transposed).unwrap()
+    );
+
+    // A ring is mapped onto itself by any rotation; the canonical ordering is one of them
+    let (_, perm) = canonicalize(&shapes, &ring).unwrap();
+    let mut sorted = perm.clone();
+    sorted.sort();
+    assert_eq!(sorted, vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn test_signature_fail() {
+    let shapes = vec![vec![2, 3], vec![3, 2]];
+    assert_eq!(
+        signature(&shapes, &[&[-1, 2], &[2, 3]]),
+        Err(QtnError::InvalidContractionOrder { index: 3, count: 1 })
+    );
+    assert!(matches!(
+        signature(&shapes, &[&[1, 2], &[2]]),
+        Err(QtnError::RankMismatch { .. })
+    ));
+    assert!(matches!(
+        signature(&shapes, &[&[1, 2], &[1, 2]]),
+        Err(QtnError::ShapeMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_layer_repeat() {
+    // Matrix powers: a single matrix per layer, row leg in, column leg out
+    let a = tensor::random(&[3, 3]);
+    let layer = Layer::new(&[vec![3, 3]], &[&[-1, -2]], &[-1], &[-2]).unwrap();
+
+    let chain = layer.repeat(3).unwrap();
+    assert_eq!(chain.order, vec![vec![-1, 1], vec![1, 2], vec![2, -2]]);
+    let m = a.clone().into_dimensionality::<Ix2>().unwrap();
+    let cube = m.dot(&m).dot(&m);
+    let con = contract(&vec![a.clone(); 3], &chain.order()).unwrap();
+    assert!((&con - &cube.clone().into_dyn())
+        .iter()
+        .all(|x| x.abs() < 1e-12));
+
+    let ring = layer.periodic(3).unwrap();
+    assert_eq!(ring.order, vec![vec![3, 1], vec![1, 2], vec![2, 3]]);
+    let con = contract(&vec![a.clone(); 3], &ring.order()).unwrap();
+    let tr = trace(&cube.into_dyn(), vec![0, 1]).unwrap();
+    assert!((&con - &tr).iter().all(|x| x.abs() < 1e-12));
+
+    // Open legs of every layer are numbered between the inputs and the outputs
+    let column = Layer::new(
+        &[vec![2, 3, 4], vec![4, 2]],
+        &[&[-1, -2, 1], &[1, -3]],
+        &[-1],
+        &[-3],
+    )
+    .unwrap();
+    assert_eq!(column.len(), 2);
+    assert_eq!(
+        column.repeat(2).unwrap().order,
+        vec![vec![-1, -2, 1], vec![1, 3], vec![3, -3, 2], vec![2, -4]]
+    );
+}
+
+#[test]
+fn test_layer_fail() {
+    let shapes = [vec![2, 3]];
+    let invalid = |inputs: &[i32], outputs: &[i32]| {
+        matches!(
+            Layer::new(&shapes, &[&[-1, -2]], inputs, outputs),
+            Err(QtnError::InvalidArgument(_))
+        )
+    };
+    assert!(invalid(&[-1], &[-2]));
+    assert!(invalid(&[-1], &[]));
+    assert!(invalid(&[-1], &[-1]));
+    assert!(invalid(&[-1], &[-3]));
+
+    let layer = Layer::new(&[vec![2, 2]], &[&[-1, -2]], &[-1], &[-2]).unwrap();
+    assert!(matches!(layer.repeat(0), Err(QtnError::InvalidArgument(_))));
+}
+
+#[test]
+fn test_tensor_network_surgery() {
+    let mut net = TensorNetwork::new();
+    net.add(tensor::random(&[2, 3, 4]), &[-1, 1, 2]).unwrap();
+    net.add(tensor::random(&[4, 5]), &[2, 3]).unwrap();
+    net.add(tensor::random(&[5, 3, 2]), &[3, 1, -2]).unwrap();
+    let correct = net.contract().unwrap();
+    let close = |x: &ndarray::ArrayD<f64>| (x - &correct).iter().all(|d| d.abs() < 1e-10);
+
+    // Splitting a node exactly leaves the network unchanged
+    let (new, bond) = net.split(2, &[2, 1], None).unwrap();
+    assert_eq!((new, bond), (3, 4));
+    assert_eq!(net.order()[2], &[-2, 1, 4]);
+    assert_eq!(net.order()[3], &[4, 3]);
+    assert_eq!(net.tensors()[3].shape(), &[5, 5]);
+    assert!(close(&net.contract().unwrap()));
+
+    // The region {1, 3} becomes one node with the legs leaving it
+    assert_eq!(net.contract_region(&[3, 1]).unwrap(), 1);
+    assert_eq!(net.len(), 3);
+    assert_eq!(net.order()[1], &[4, 2]);
+    assert!(close(&net.contract().unwrap()));
+
+    // An adaptive split keeps the full bond of a generic tensor for a tight target
+    let mut copy = net.clone();
+    let (_, _, weight) = copy.split_to_error(1, &[0], 1e-12, None).unwrap();
+    assert!(weight < 1e-12);
+    assert!(close(&copy.contract().unwrap()));
+    let (_, _, weight) = copy.split_to_error(0, &[0], 1e-12, Some(1)).unwrap();
+    assert!(weight > 0.0);
+
+    // Rewiring the bond between nodes 1 and 2 into two free legs
+    net.rewire(1, 0, -3).unwrap();
+    net.rewire(2, 2, -4).unwrap();
+    let open = net.contract().unwrap();
+    assert_eq!(open.shape(), &[2, 2, 5, 5]);
+}
+
+#[test]
+fn test_tensor_network_fail() {
+    let mut net = TensorNetwork::new();
+    net.add(tensor::random(&[2, 3]), &[-1, 1]).unwrap();
+
+    assert!(matches!(
+        net.add(tensor::random(&[2, 3]), &[1]),
+        Err(QtnError::RankMismatch { .. })
+    ));
+    assert!(matches!(
+        net.add(tensor::random(&[4, 2]), &[1, -2]),
+        Err(QtnError::ShapeMismatch { .. })
+    ));
+    assert!(matches!(
+        net.add(tensor::random(&[2]), &[-1]),
+        Err(QtnError::InvalidContractionOrder { index: -1, .. })
+    ));
+    assert_eq!(net.len(), 1);
+
+    assert!(matches!(
+        net.rewire(0, 2, 5),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        net.contract_region(&[0, 0]),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        net.split(0, &[2], None),
+        Err(QtnError::InvalidArgument(_))
+    ));
+}
@@ -0,0 +1,72 @@
+use ndarray::{arr2, ArrayD};
+use qua_ten_net::error::QtnError;
+use qua_ten_net::param::{contract_param, ParamTensor};
+use qua_ten_net::tencon::contract;
+use qua_ten_net::tensor;
+
+#[test]
+fn test_param_tensor() {
+    let x = arr2(&[[0.0, 1.0], [1.0, 0.0]]).into_dyn();
+    let z = arr2(&[[1.0, 0.0], [0.0, -1.0]]).into_dyn();
+    let h = ParamTensor::new(ArrayD::zeros(vec![2, 2]))
+        .with_term("h", x.clone())
+        .unwrap()
+        .with_term("J", z.clone())
+        .unwrap()
+        .with_term("h", x.clone())
+        .unwrap();
+
+    assert_eq!(h.parameters(), vec!["h", "J"]);
+    assert_eq!(h.coefficient("h"), Some(&(&x * 2.0)));
+    assert_eq!(h.coefficient("g"), None);
+    assert!(!h.is_constant());
+
+    let tst = h.evaluate(&[("J", 0.5), ("h", 1.0), ("g", 3.0)]).unwrap();
+    assert_eq!(tst, &x * 2.0 + &z * 0.5);
+}
+
+#[test]
+fn test_contract_param() {
+    let a = tensor::random(&[3, 2]);
+    let b = tensor::random(&[3, 2]);
+    let h0 = tensor::random(&[2, 2]);
+    let h1 = tensor::random(&[2, 2]);
+    let order: [&[i32]; 3] = [&[-1, 1], &[1, 2], &[-2, 2]];
+
+    let h = ParamTensor::new(h0.clone())
+        .with_term("J", h1.clone())
+        .unwrap();
+    let network = [ParamTensor::new(a.clone()), h, ParamTensor::new(b.clone())];
+    let result = contract_param(&network, &order).unwrap();
+    assert_eq!(result.parameters(), vec!["J"]);
+
+    for j in [0.0, 0.3, -1.7] {
+        let tst = result.evaluate(&[("J", j)]).unwrap();
+        let rslt = contract(&[a.clone(), &h0 + &h1 * j, b.clone()], &order).unwrap();
+        assert!(tst
+            .iter()
+            .zip(rslt.iter())
+            .all(|(x, y)| (x - y).abs() < 1e-12));
+    }
+}
+
+#[test]
+fn test_param_fail() {
+    let a = tensor::random(&[2, 2]);
+    assert!(matches!(
+        ParamTensor::new(a.clone()).with_term("J", tensor::random(&[2, 3])),
+        Err(QtnError::InvalidArgument(_))
+    ));
+
+    let p = ParamTensor::new(a.clone())
+        .with_term("J", a.clone())
+        .unwrap();
+    assert!(p.evaluate(&[("h", 1.0)]).is_err());
+
+    // Two parametric tensors give a quadratic dependence
+    let order: [&[i32]; 2] = [&[-1, 1], &[1, -2]];
+    assert!(matches!(
+        contract_param(&[p.clone(), p], &order),
+        Err(QtnError::InvalidArgument(_))
+    ));
+}
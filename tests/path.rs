@@ -0,0 +1,325 @@
+use ndarray::ArrayD;
+use qua_ten_net::error::QtnError;
+use qua_ten_net::path::{
+    calibrate, from_opt_einsum, optimize_path, optimize_structured_path, path_cost,
+    reconfigure_path, to_opt_einsum, CostModel, Optimizer, TensorStructure,
+};
+use qua_ten_net::tencon::contract_map;
+
+#[test]
+fn test_path_cost_flops() {
+    // Matrix chain (2x100)(100x3)(3x50): left-to-right is far cheaper than right-to-left
+    let shapes = vec![vec![2, 100], vec![100, 3], vec![3, 50]];
+    let order: [&[i32]; 3] = [&[-1, 1], &[1, 2], &[2, -2]];
+    let model = CostModel::default();
+
+    let left = path_cost(&shapes, &order, &[vec![0, 1], vec![0, 1]], &model).unwrap();
+    let right = path_cost(&shapes, &order, &[vec![1, 2], vec![0, 1]], &model).unwrap();
+
+    assert_eq!(left, (2 * 100 * 3 + 2 * 3 * 50) as f64);
+    assert_eq!(right, (100 * 3 * 50 + 2 * 100 * 50) as f64);
+
+    // The same path priced with a fixed cost per step and memory traffic
+    let model = CostModel {
+        flop: 1.0,
+        overhead: 10.0,
+        element: 0.5,
+    };
+    let cost = path_cost(&shapes, &order, &[vec![0, 1], vec![0, 1]], &model).unwrap();
+    let traffic = (200 + 300 + 6) + (6 + 150 + 100);
+    assert_eq!(cost, left + 20.0 + 0.5 * traffic as f64);
+}
+
+#[test]
+fn test_path_cost_matches_contract_map() {
+    let shapes = vec![vec![2, 3, 4], vec![4, 5], vec![5, 3]];
+    let order: [&[i32]; 3] = [&[-1, 1, 2], &[2, 3], &[3, 1]];
+    let tensors: Vec<ArrayD<f64>> = shapes.iter().map(|s| ArrayD::zeros(s.clone())).collect();
+    let orders: Vec<Vec<i32>> = order.iter().map(|o| o.to_vec()).collect();
+
+    let path = contract_map(&tensors, &orders);
+    assert!(path_cost(&shapes, &order, &path, &CostModel::default()).unwrap() > 0.0);
+}
+
+#[test]
+fn test_path_cost_fail() {
+    let shapes = vec![vec![2, 3], vec![3, 2]];
+    let order: [&[i32]; 2] = [&[-1, 1], &[1, -2]];
+    let model = CostModel::default();
+
+    assert_eq!(
+        QtnError::InvalidPath { step: 0 },
+        path_cost(&shapes, &order, &[vec![0, 2]], &model).unwrap_err()
+    );
+    assert_eq!(
+        QtnError::InvalidPath { step: 0 },
+        path_cost(&shapes, &order, &[], &model).unwrap_err()
+    );
+}
+
+#[test]
+fn test_calibrate() {
+    let model = calibrate::<f64>(1);
+    assert_eq!(model.flop, 1.0);
+    assert!(model.overhead >= 0.0 && model.overhead.is_finite());
+    assert!(model.element >= 0.0 && model.element.is_finite());
+}
+
+#[test]
+fn test_random_greedy() {
+    // Matrix chain (2x100)(100x3)(3x50)(50x100): the cheapest path keeps the 2-dimensional leg
+    let shapes = vec![vec![2, 100], vec![100, 3], vec![3, 50], vec![50, 100]];
+    let order: [&[i32]; 4] = [&[-1, 1], &[1, 2], &[2, 3], &[3, -2]];
+    let model = CostModel::default();
+    let optimizer = Optimizer::RandomGreedy {
+        trials: 16,
+        temperature: 1.0,
+        seed: 7,
+    };
+
+    let path = optimize_path(&shapes, &order, &optimizer, &model).unwrap();
+    let cost = path_cost(&shapes, &order, &path, &model).unwrap();
+    assert_eq!(cost, (2 * 100 * 3 + 2 * 3 * 50 + 2 * 50 * 100) as f64);
+
+    // The same seed gives the same path
+    assert_eq!(
+        path,
+        optimize_path(&shapes, &order, &optimizer, &model).unwrap()
+    );
+}
+
+#[test]
+fn test_random_greedy_not_worse_than_greedy() {
+    // A ring of six tensors with mixed bond dimensions
+    let shapes = vec![
+        vec![8, 2, 3],
+        vec![3, 2, 5],
+        vec![5, 2, 4],
+        vec![4, 2, 7],
+        vec![7, 2, 6],
+        vec![6, 2, 8],
+    ];
+    let order: [&[i32]; 6] = [
+        &[1, -1, 2],
+        &[2, -2, 3],
+        &[3, -3, 4],
+        &[4, -4, 5],
+        &[5, -5, 6],
+        &[6, -6, 1],
+    ];
+    let model = CostModel::default();
+
+    let greedy = Optimizer::RandomGreedy {
+        trials: 1,
+        temperature: 0.0,
+        seed: 0,
+    };
+    let random = Optimizer::RandomGreedy {
+        trials: 32,
+        temperature: 0.5,
+        seed: 0,
+    };
+
+    let cost = |optimizer| {
+        let path = optimize_path(&shapes, &order, optimizer, &model).unwrap();
+        path_cost(&shapes, &order, &path, &model).unwrap()
+    };
+    assert!(cost(&random) <= cost(&greedy));
+}
+
+#[test]
+fn test_branch_and_bound() {
+    let shapes = vec![vec![2, 100], vec![100, 3], vec![3, 50], vec![50, 100]];
+    let order: [&[i32]; 4] = [&[-1, 1], &[1, 2], &[2, 3], &[3, -2]];
+    let model = CostModel::default();
+
+    let path = optimize_path(
+        &shapes,
+        &order,
+        &Optimizer::BranchAndBound { max_nodes: 1000 },
+        &model,
+    )
+    .unwrap();
+    let cost = path_cost(&shapes, &order, &path, &model).unwrap();
+    assert_eq!(cost, (2 * 100 * 3 + 2 * 3 * 50 + 2 * 50 * 100) as f64);
+
+    // Without any expanded node the greedy bound is returned, which is still a valid path
+    let path = optimize_path(
+        &shapes,
+        &order,
+        &Optimizer::BranchAndBound { max_nodes: 0 },
+        &model,
+    )
+    .unwrap();
+    assert!(path_cost(&shapes, &order, &path, &model).is_ok());
+}
+
+#[test]
+fn test_branch_and_bound_not_worse_than_random_greedy() {
+    let shapes = vec![
+        vec![8, 2, 3],
+        vec![3, 2, 5],
+        vec![5, 2, 4],
+        vec![4, 2, 7],
+        vec![7, 2, 6],
+        vec![6, 2, 8],
+    ];
+    let order: [&[i32]; 6] = [
+        &[1, -1, 2],
+        &[2, -2, 3],
+        &[3, -3, 4],
+        &[4, -4, 5],
+        &[5, -5, 6],
+        &[6, -6, 1],
+    ];
+    let model = CostModel {
+        flop: 1.0,
+        overhead: 100.0,
+        element: 1.0,
+    };
+
+    let cost = |optimizer| {
+        let path = optimize_path(&shapes, &order, optimizer, &model).unwrap();
+        path_cost(&shapes, &order, &path, &model).unwrap()
+    };
+    let random = Optimizer::RandomGreedy {
+        trials: 32,
+        temperature: 1.0,
+        seed: 1,
+    };
+    let exact = Optimizer::BranchAndBound { max_nodes: 100_000 };
+    assert!(cost(&exact) <= cost(&random));
+}
+
+#[test]
+fn test_reconfigure_path() {
+    // Matrix chain (2x100)(100x3)(3x50)(50x100), planned right-to-left
+    let shapes = vec![vec![2, 100], vec![100, 3], vec![3, 50], vec![50, 100]];
+    let order: [&[i32]; 4] = [&[-1, 1], &[1, 2], &[2, 3], &[3, -2]];
+    let model = CostModel::default();
+    let right = [vec![2, 3], vec![1, 2], vec![0, 1]];
+
+    let path = reconfigure_path(&shapes, &order, &right, 4, 10, &model).unwrap();
+    let cost = path_cost(&shapes, &order, &path, &model).unwrap();
+    assert_eq!(cost, (2 * 100 * 3 + 2 * 3 * 50 + 2 * 50 * 100) as f64);
+
+    // Subtrees smaller than the network still never make the path worse
+    let path = reconfigure_path(&shapes, &order, &right, 3, 10, &model).unwrap();
+    let cost = path_cost(&shapes, &order, &path, &model).unwrap();
+    assert!(cost <= path_cost(&shapes, &order, &right, &model).unwrap());
+
+    assert!(matches!(
+        reconfigure_path(&shapes, &order, &right[..2], 4, 10, &model),
+        Err(QtnError::InvalidPath { .. })
+    ));
+}
+
+#[test]
+fn test_reconfigure_not_worse_than_greedy() {
+    // Periodic chain with an open leg on every tensor
+    let shapes: Vec<Vec<usize>> = (0..10)
+        .map(|k| vec![2 + k % 3, 2, 2 + (k + 1) % 3])
+        .collect();
+    let orders: Vec<Vec<i32>> = (0..10)
+        .map(|k| vec![k + 1, -(k + 1), (k + 1) % 10 + 1])
+        .collect();
+    let order: Vec<&[i32]> = orders.iter().map(|o| o.as_slice()).collect();
+    let model = CostModel::default();
+
+    let cost = |optimizer| {
+        let path = optimize_path(&shapes, &order, optimizer, &model).unwrap();
+        path_cost(&shapes, &order, &path, &model).unwrap()
+    };
+    let greedy = Optimizer::RandomGreedy {
+        trials: 1,
+        temperature: 0.0,
+        seed: 0,
+    };
+    let reconfigure = Optimizer::Reconfigure {
+        subtree_size: 6,
+        rounds: 4,
+    };
+    assert!(cost(&reconfigure) <= cost(&greedy));
+}
+
+#[test]
+fn test_opt_einsum_format() {
+    let path = vec![vec![0, 3], vec![0, 1], vec![0, 1]];
+    let exported = to_opt_einsum(&path, 4).unwrap();
+    assert_eq!(exported, "[(0, 3), (0, 2), (0, 1)]");
+    assert_eq!(from_opt_einsum(&exported, 4).unwrap(), path);
+    assert_eq!(from_opt_einsum("[[0,3],[0,2],[0,1]]", 4).unwrap(), path);
+
+    assert_eq!(to_opt_einsum(&[], 1).unwrap(), "[]");
+    assert_eq!(from_opt_einsum("[]", 1).unwrap(), Vec::<Vec<usize>>::new());
+}
+
+#[test]
+fn test_opt_einsum_format_fail() {
+    assert_eq!(
+        QtnError::InvalidPath { step: 1 },
+        from_opt_einsum("[(0, 1), (0, 2)]", 3).unwrap_err()
+    );
+    assert_eq!(
+        QtnError::InvalidPath { step: 1 },
+        to_opt_einsum(&[vec![0, 1]], 3).unwrap_err()
+    );
+    assert!(matches!(
+        from_opt_einsum("[(0, 1), (0, x)]", 3),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        from_opt_einsum("(0, 1)", 2),
+        Err(QtnError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn test_structured_path() {
+    // (100x100) X, a diagonal 100x100 bond D and a thin (100x2) Y. Densely, D is best
+    // multiplied into Y; a diagonal D should be absorbed first as well, but costs far less.
+    let shapes = vec![vec![100, 100], vec![100, 100], vec![100, 2]];
+    let order: [&[i32]; 3] = [&[-1, 1], &[1, 2], &[2, -2]];
+    let structure = [
+        TensorStructure::Dense,
+        TensorStructure::Diagonal,
+        TensorStructure::Sparse { density: 0.5 },
+    ];
+    let optimizer = Optimizer::BranchAndBound { max_nodes: 100 };
+    let model = CostModel::default();
+
+    let path = optimize_structured_path(&shapes, &order, &structure, &optimizer, &model).unwrap();
+    assert_eq!(path, vec![vec![1, 2], vec![0, 1]]);
+
+    // Structure without the matching tensors is rejected
+    assert!(matches!(
+        optimize_structured_path(&shapes, &order, &structure[..2], &optimizer, &model),
+        Err(QtnError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn test_optimize_path_huge_intermediates() {
+    // Every intermediate of this triangle has more elements than `usize` can count
+    let big = 1 << 25;
+    let shapes = vec![
+        vec![big, big, 2, 2],
+        vec![big, big, 2, 2],
+        vec![big, big, 2, 2],
+    ];
+    let order: [&[i32]; 3] = [&[-1, -2, 1, 3], &[-3, -4, 1, 2], &[-5, -6, 2, 3]];
+    let model = CostModel::default();
+
+    for optimizer in [
+        Optimizer::RandomGreedy {
+            trials: 4,
+            temperature: 1.0,
+            seed: 3,
+        },
+        Optimizer::BranchAndBound { max_nodes: 100 },
+    ] {
+        let path = optimize_path(&shapes, &order, &optimizer, &model).unwrap();
+        let cost = path_cost(&shapes, &order, &path, &model).unwrap();
+        assert!(cost.is_finite() && cost > (big as f64).powi(4));
+    }
+}
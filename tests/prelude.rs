@@ -0,0 +1,32 @@
+use qua_ten_net::prelude::*;
+
+#[test]
+fn test_prelude() {
+    let tensors = [random(&[2, 3]), random(&[3, 4])];
+    let correct = contract(&tensors, &[&[-1, 1], &[1, -2]]).unwrap();
+    let rslt = einsum("ij,jk->ik", &tensors).unwrap();
+    assert!((&rslt - &correct).iter().all(|x| x.abs() < 1e-12));
+
+    let (result, weight): (SVDResult, f64) =
+        svd_truncated(correct.into_dimensionality().unwrap(), Some(1), 0.0, false).unwrap();
+    assert_eq!(result.sigma.len(), 1);
+    assert!((0.0..1.0).contains(&weight));
+
+    // Ground state of a two-site Heisenberg bond S.S, with energy -3/4
+    let mut bond = zeros(&[2, 2, 2, 2]);
+    for (s, t) in [(0, 0), (0, 1), (1, 0), (1, 1)] {
+        bond[[s, t, s, t]] = if s == t { 0.25 } else { -0.25 };
+    }
+    bond[[0, 1, 1, 0]] = 0.5;
+    bond[[1, 0, 0, 1]] = 0.5;
+    let matvec = |psi: &ndarray::ArrayD<f64>| {
+        contract(&[bond.view(), psi.view()], &[&[-1, -2, 1, 2], &[1, 2]]).unwrap()
+    };
+    let (energies, _) = lanczos(matvec, &random(&[2, 2]), 1, 1e-12, 4).unwrap();
+    assert!((energies[0] + 0.75).abs() < 1e-10);
+
+    assert!(matches!(
+        contract(&[ones(&[2])], &[&[1]]),
+        Err(QtnError::InvalidContractionOrder { .. })
+    ));
+}
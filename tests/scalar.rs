@@ -0,0 +1,51 @@
+use ndarray::{Array, ArrayD};
+use ndarray_linalg::{c32, c64};
+use qua_ten_net::scalar::Scalar;
+use qua_ten_net::tencon::contract;
+use qua_ten_net::trace::trace;
+
+fn norm_squared<A: Scalar>(psi: &ArrayD<A>) -> A {
+    let bra = psi.mapv(|x| x.conj());
+    contract(&[bra, psi.clone()], &[&[1, 2], &[1, 2]])
+        .unwrap()
+        .sum()
+}
+
+#[test]
+fn test_scalar_real_precisions() {
+    let psi_f32 =
+        Array::from_shape_vec(vec![2, 2], vec![1.0_f32, 2.0, 3.0, 4.0]).expect("ShapeError!");
+    let psi_f64 = psi_f32.mapv(f64::from);
+
+    assert_eq!(norm_squared(&psi_f32), 30.0_f32);
+    assert_eq!(norm_squared(&psi_f64), 30.0_f64);
+}
+
+#[test]
+fn test_scalar_complex_precisions() {
+    let psi_c64 = Array::from_shape_vec(
+        vec![2, 2],
+        vec![
+            c64::new(1.0, 1.0),
+            c64::new(0.0, 2.0),
+            c64::new(-1.0, 0.0),
+            c64::new(0.5, -0.5),
+        ],
+    )
+    .expect("ShapeError!");
+    let psi_c32 = psi_c64.mapv(|x| c32::new(x.re as f32, x.im as f32));
+
+    assert_eq!(norm_squared(&psi_c64), c64::new(7.5, 0.0));
+    assert_eq!(norm_squared(&psi_c32), c32::new(7.5, 0.0));
+
+    let tr = trace(&psi_c32, vec![0, 1]).unwrap();
+    assert_eq!(tr.sum(), c32::new(1.5, 0.5));
+}
+
+#[test]
+fn test_scalar_from_parts() {
+    assert_eq!(<f32 as Scalar>::from_parts(1.5, 2.0), 1.5);
+    assert_eq!(<f64 as Scalar>::from_parts(-0.5, 2.0), -0.5);
+    assert_eq!(<c32 as Scalar>::from_parts(1.5, 2.0), c32::new(1.5, 2.0));
+    assert_eq!(<c64 as Scalar>::from_parts(-0.5, 1.0), c64::new(-0.5, 1.0));
+}
@@ -0,0 +1,123 @@
+use ndarray::{Array, Array2};
+use ndarray_linalg::c64;
+use qua_ten_net::error::QtnError;
+use qua_ten_net::superop::*;
+
+fn sample(rows: usize, cols: usize, offset: usize) -> Array2<f64> {
+    Array::from_shape_vec(
+        (rows, cols),
+        (0..rows * cols).map(|x| (x + offset) as f64).collect(),
+    )
+    .expect("ShapeError!")
+}
+
+#[test]
+fn test_vectorize() {
+    let op = sample(2, 3, 0);
+    let vec = vectorize(&op);
+    assert_eq!(vec.to_vec(), vec![0.0, 3.0, 1.0, 4.0, 2.0, 5.0]);
+    assert_eq!(unvectorize(&vec, 2, 3).unwrap(), op);
+}
+
+#[test]
+fn test_unvectorize_fail_length() {
+    let vec = vectorize(&sample(2, 2, 0));
+    assert_eq!(
+        "Vector of length 4 cannot be reshaped into a 3x3 operator.",
+        unvectorize(&vec, 3, 3).unwrap_err().to_string()
+    );
+}
+
+#[test]
+fn test_sandwich() {
+    let a = sample(2, 2, 1);
+    let b = sample(2, 2, 5);
+    let rho = sample(2, 2, 2);
+
+    let superop = sandwich(&a, &b);
+    let direct = a.dot(&rho).dot(&b);
+    assert_eq!(apply_superoperator(&superop, &rho).unwrap(), direct);
+
+    let composed = compose(&[left_multiplication(&a), right_multiplication(&b)]).unwrap();
+    assert_eq!(composed, superop);
+}
+
+#[test]
+fn test_kraus_to_superoperator() {
+    let k0 = sample(2, 2, 0);
+    let k1 = sample(2, 2, 3);
+    let rho = sample(2, 2, 1);
+
+    let superop = kraus_to_superoperator(&[k0.clone(), k1.clone()]).unwrap();
+    let direct = k0.dot(&rho).dot(&k0.t()) + k1.dot(&rho).dot(&k1.t());
+    assert_eq!(apply_superoperator(&superop, &rho).unwrap(), direct);
+}
+
+#[test]
+fn test_liouvillian_spectrum() {
+    let gamma: f64 = 0.4;
+    let zero = c64::new(0.0, 0.0);
+    let lowering =
+        Array::from_shape_vec((2, 2), vec![zero, c64::new(gamma.sqrt(), 0.0), zero, zero])
+            .expect("ShapeError!");
+    let hamiltonian = Array2::<c64>::zeros((2, 2));
+
+    let l = liouvillian(&hamiltonian, &[lowering]).unwrap();
+
+    // Trace preservation: vec(I)^† L = 0
+    let identity = vectorize(&Array2::<c64>::eye(2));
+    assert!(identity.dot(&l).iter().all(|x| x.norm() < 1e-12));
+
+    let spectrum = liouvillian_spectrum(&l).unwrap();
+    let expected = [0.0, -gamma / 2.0, -gamma / 2.0, -gamma];
+    for (value, expected) in spectrum.iter().zip(expected) {
+        assert!((value - c64::new(expected, 0.0)).norm() < 1e-10);
+    }
+}
+
+#[test]
+fn test_steady_state() {
+    // Driven, damped qubit: H = omega * sigma_x / 2, L = sqrt(gamma) * sigma_-
+    let (omega, gamma): (f64, f64) = (0.6, 0.5);
+    let zero = c64::new(0.0, 0.0);
+    let hamiltonian = Array::from_shape_vec(
+        (2, 2),
+        vec![
+            zero,
+            c64::new(omega / 2.0, 0.0),
+            c64::new(omega / 2.0, 0.0),
+            zero,
+        ],
+    )
+    .expect("ShapeError!");
+    let lowering =
+        Array::from_shape_vec((2, 2), vec![zero, c64::new(gamma.sqrt(), 0.0), zero, zero])
+            .expect("ShapeError!");
+
+    let l = liouvillian(&hamiltonian, &[lowering]).unwrap();
+    let rho = steady_state(&l, 1e-10).unwrap();
+
+    let residual = l.dot(&vectorize(&rho));
+    assert!(residual.iter().all(|x| x.norm() < 1e-10));
+
+    // Excited-state population of the resonantly driven two-level system (sigma_- maps |1> to |0>)
+    let excited = Array::from_shape_vec((2, 2), vec![zero, zero, zero, c64::new(1.0, 0.0)])
+        .expect("ShapeError!");
+    let population = expectation_value(&rho, &excited).unwrap();
+    let expected = omega.powi(2) / 4.0 / (gamma.powi(2) / 4.0 + omega.powi(2) / 2.0);
+    assert!((population.re - expected).abs() < 1e-10);
+}
+
+#[test]
+fn test_steady_state_fail() {
+    assert!(matches!(
+        steady_state(&Array2::zeros((0, 0)), 1e-10),
+        Err(QtnError::InvalidArgument(_))
+    ));
+
+    // A full-rank generator annihilates no state
+    assert!(matches!(
+        steady_state(&Array2::eye(4), 1e-10),
+        Err(QtnError::NotConverged(_))
+    ));
+}
@@ -1,5 +1,11 @@
-use ndarray::Array;
-use qua_ten_net::tencon::contract;
+use ndarray::{Array, Array2, ArrayD, IxDyn};
+use ndarray_linalg::c64;
+use qua_ten_net::error::QtnError;
+use qua_ten_net::path::{Optimizer, TensorStructure};
+use qua_ten_net::tencon::{
+    compile, contract, contract_info, contract_into, contract_profiled, contract_with,
+    contract_with_bounds, plan, ContractOptions, ContractionContext, PlanCache, SliceStrategy,
+};
 use qua_ten_net::tensor;
 
 #[test]
@@ -28,3 +34,679 @@ fn test_contract() {
 
     assert_eq!(con.unwrap(), correct);
 }
+
+#[test]
+fn test_contract_views() {
+    let a = tensor::random(&[4, 3, 5]);
+    let b = tensor::random(&[5, 3, 6]);
+    let order: [&[i32]; 2] = [&[-1, 1, 2], &[2, 1, -2]];
+    let correct = contract(&[a.clone(), b.clone()], &order).unwrap();
+
+    // Views are contracted without copying the inputs
+    let views = [a.view(), b.view()];
+    assert_eq!(contract(&views, &order).unwrap(), correct);
+
+    let plan = plan(&[vec![4, 3, 5], vec![5, 3, 6]], &order).unwrap();
+    assert_eq!(plan.execute(&views).unwrap(), correct);
+
+    let shared = [a.into_shared(), b.into_shared()];
+    assert_eq!(contract(&shared, &order).unwrap(), correct);
+}
+
+#[test]
+fn test_contract_into() {
+    let a = tensor::random(&[4, 3, 5]);
+    let b = tensor::random(&[5, 3, 6]);
+    let c = tensor::random(&[6, 2]);
+    let tensors = [a, b, c];
+    let shapes: Vec<Vec<usize>> = tensors.iter().map(|t| t.shape().to_vec()).collect();
+    let order: [&[i32]; 3] = [&[-1, 1, 2], &[2, 1, 3], &[3, -2]];
+
+    let correct = contract(&tensors, &order).unwrap();
+    let mut out = ArrayD::from_elem(vec![4, 2], f64::NAN);
+    contract_into(&mut out, &tensors, &order).unwrap();
+    assert_eq!(out, correct);
+
+    // Permuted outputs, deterministic kernels and reused buffers
+    for options in [
+        ContractOptions::default().out_order(&[-2, -1]),
+        ContractOptions::default().deterministic(true),
+        ContractOptions::default().memory_limit(40),
+    ] {
+        let plan = compile(&shapes, &order, &options).unwrap();
+        let rslt = plan.execute(&tensors).unwrap();
+        let mut out = ArrayD::zeros(plan.output_shape());
+        for _ in 0..2 {
+            plan.execute_into(&tensors, &mut out).unwrap();
+            assert_eq!(out, rslt);
+        }
+    }
+
+    let mut out = ArrayD::zeros(vec![2, 4]);
+    assert!(matches!(
+        contract_into(&mut out, &tensors, &order),
+        Err(QtnError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn test_plan_cache() {
+    let a = tensor::random(&[4, 3]);
+    let b = tensor::random(&[3, 5]);
+    let c = tensor::random(&[5, 2]);
+    let chain: [&[i32]; 3] = [&[-1, 1], &[1, 2], &[2, -2]];
+    let pair: [&[i32]; 2] = [&[-1, 1], &[1, -2]];
+    let three = [a.clone(), b.clone(), c.clone()];
+    let two = [a.clone(), b.clone()];
+
+    let mut cache = PlanCache::new(2);
+    for _ in 0..3 {
+        assert_eq!(
+            cache.contract(&three, &chain).unwrap(),
+            contract(&three, &chain).unwrap()
+        );
+    }
+    assert_eq!((cache.len(), cache.hits(), cache.misses()), (1, 2, 1));
+
+    // The least recently used plan is dropped when the cache is full
+    cache.contract(&two, &pair).unwrap();
+    cache.contract(&three, &chain).unwrap();
+    cache.contract(&[b.clone(), c.clone()], &pair).unwrap();
+    assert_eq!(cache.len(), 2);
+    cache.contract(&three, &chain).unwrap();
+    assert_eq!((cache.hits(), cache.misses()), (4, 3));
+    cache.contract(&two, &pair).unwrap();
+    assert_eq!(cache.misses(), 4);
+
+    cache.set_capacity(0);
+    assert_eq!((cache.capacity(), cache.len()), (1, 1));
+    assert!(matches!(
+        cache.contract(&two, &chain[..2]),
+        Err(QtnError::InvalidContractionOrder { .. })
+    ));
+
+    cache.clear();
+    assert!(cache.is_empty());
+    assert_eq!((cache.hits(), cache.misses()), (0, 0));
+}
+
+#[test]
+fn test_contraction_context() {
+    let a = tensor::random(&[4, 3, 5]);
+    let b = tensor::random(&[5, 3, 6]);
+    let c = tensor::random(&[6, 2, 7]);
+    let d = tensor::random(&[7]);
+    let tensors = [a, b, c, d];
+    let order: [&[i32]; 4] = [&[-1, 1, 2], &[2, 1, 3], &[3, -2, 4], &[4]];
+    let correct = contract(&tensors, &order).unwrap();
+
+    let mut ctx = ContractionContext::new();
+    for _ in 0..3 {
+        let rslt = ctx.contract(&tensors, &order).unwrap();
+        assert!((&rslt - &correct).iter().all(|x| x.abs() < 1e-12));
+        ctx.recycle(rslt);
+    }
+    assert_eq!(ctx.cached_plans(), 1);
+    assert!(ctx.pooled_buffers() > 0);
+
+    // Options are applied to every compiled plan
+    let options = ContractOptions::default().out_order(&[-2, -1]);
+    let mut ctx = ContractionContext::with_options(options);
+    let rslt = ctx.contract(&tensors, &order).unwrap();
+    assert!((&rslt - &correct.t()).iter().all(|x| x.abs() < 1e-12));
+
+    ctx.clear();
+    assert_eq!(ctx.cached_plans(), 0);
+    assert_eq!(ctx.pooled_buffers(), 0);
+}
+
+#[test]
+fn test_contract_complex() {
+    // Apply a random two-qubit unitary-like operator to a random complex wavefunction
+    let re = tensor::random(&[2, 2, 2, 2]);
+    let im = tensor::random(&[2, 2, 2, 2]);
+    let op = ndarray::Zip::from(&re)
+        .and(&im)
+        .map_collect(|&r, &i| c64::new(r, i));
+
+    let re = tensor::random(&[2, 2]);
+    let im = tensor::random(&[2, 2]);
+    let psi = ndarray::Zip::from(&re)
+        .and(&im)
+        .map_collect(|&r, &i| c64::new(r, i));
+
+    let con = contract(&[op.clone(), psi.clone()], &[&[-1, -2, 1, 2], &[1, 2]]).unwrap();
+
+    let op_mat = op.to_shape((4, 4)).unwrap();
+    let psi_vec = psi.to_shape(4).unwrap();
+    let correct = op_mat.dot(&psi_vec);
+
+    assert_eq!(con.shape(), &[2, 2]);
+    for (x, y) in con.iter().zip(correct.iter()) {
+        assert!((x - y).norm() < 1e-12);
+    }
+}
+
+#[test]
+fn test_contract_fail_order() {
+    let a = tensor::random(&[2, 3]);
+    let b = tensor::random(&[3, 2]);
+
+    let err = contract(&[a, b], &[&[-1, 1], &[1, 1]]).unwrap_err();
+    assert_eq!(
+        QtnError::InvalidContractionOrder { index: 1, count: 3 },
+        err
+    );
+}
+
+#[test]
+fn test_contract_fail_inputs() {
+    let a = tensor::random(&[2, 3]);
+    let b = tensor::random(&[3, 2]);
+    let c = tensor::random(&[2, 2]);
+
+    let empty: [ArrayD<f64>; 0] = [];
+    assert_eq!(QtnError::EmptyNetwork, contract(&empty, &[]).unwrap_err());
+    assert_eq!(
+        QtnError::OrderCountMismatch {
+            tensors: 2,
+            orders: 1
+        },
+        contract(&[a.clone(), b.clone()], &[&[-1, -2]]).unwrap_err()
+    );
+    assert_eq!(
+        QtnError::RankMismatch {
+            tensor: 1,
+            rank: 2,
+            order_len: 3
+        },
+        contract(&[a.clone(), b.clone()], &[&[-1, 1], &[1, -2, -3]]).unwrap_err()
+    );
+    assert_eq!(
+        QtnError::InvalidContractionOrder { index: 0, count: 2 },
+        contract(&[a.clone(), b], &[&[-1, 0], &[0, -2]]).unwrap_err()
+    );
+    assert_eq!(
+        QtnError::ShapeMismatch {
+            tensor: 0,
+            axis: 1,
+            dim: 3,
+            other_tensor: 1,
+            other_axis: 0,
+            other_dim: 2
+        },
+        contract(&[a, c], &[&[-1, 1], &[1, -2]]).unwrap_err()
+    );
+}
+
+#[test]
+fn test_contract_single_tensor_trace() {
+    let vec_a: Vec<f64> = (0..16).map(|x| x as f64).collect();
+    let a = Array::from_shape_vec(vec![2, 2, 2, 2], vec_a).expect("ShapeError!");
+
+    // Double trace over (0, 2) and (1, 3): 0 + 5 + 10 + 15
+    let con = contract(std::slice::from_ref(&a), &[&[1, 2, 1, 2]]).unwrap();
+    assert_eq!(con.sum(), 30.0);
+
+    let con = contract(&[a], &[&[1, -1, 1, -2]]).unwrap();
+    let correct =
+        Array::from_shape_vec(vec![2, 2], vec![10.0, 12.0, 18.0, 20.0]).expect("ShapeError!");
+    assert_eq!(con, correct);
+}
+
+#[test]
+fn test_contract_with_out_order() {
+    let a = tensor::random(&[2, 3, 4]);
+    let b = tensor::random(&[4, 5]);
+
+    let default = contract(&[a.clone(), b.clone()], &[&[-1, -2, 1], &[1, -3]]).unwrap();
+    let options = ContractOptions::default().out_order(&[-3, -1, -2]);
+    let ordered =
+        contract_with(&[a.clone(), b.clone()], &[&[-1, -2, 1], &[1, -3]], &options).unwrap();
+
+    assert_eq!(ordered.shape(), &[5, 2, 3]);
+    assert_eq!(ordered, default.permuted_axes(vec![2, 0, 1]));
+
+    let options = ContractOptions::default().out_order(&[-1, -2]);
+    assert_eq!(
+        QtnError::InvalidOutputOrder(vec![-1, -2]),
+        contract_with(&[a, b], &[&[-1, -2, 1], &[1, -3]], &options).unwrap_err()
+    );
+}
+
+#[test]
+fn test_contract_with_optimizer() {
+    let a = tensor::random(&[2, 3, 4]);
+    let b = tensor::random(&[4, 5, 2]);
+    let c = tensor::random(&[5, 3, 6]);
+    let d = tensor::random(&[6, 2, 7]);
+    let tensors = [a, b, c, d];
+    let order: [&[i32]; 4] = [&[-1, 1, 2], &[2, 3, 4], &[3, 1, 5], &[5, 4, -2]];
+
+    let correct = contract(&tensors, &order).unwrap();
+
+    for optimizer in [
+        Optimizer::RandomGreedy {
+            trials: 8,
+            temperature: 1.0,
+            seed: 3,
+        },
+        Optimizer::BranchAndBound { max_nodes: 1000 },
+        Optimizer::Reconfigure {
+            subtree_size: 3,
+            rounds: 2,
+        },
+        Optimizer::Path(vec![vec![2, 3], vec![0, 1], vec![0, 1]]),
+    ] {
+        let options = ContractOptions::default().optimizer(optimizer);
+        let con = contract_with(&tensors, &order, &options).unwrap();
+
+        assert_eq!(con.shape(), correct.shape());
+        for (x, y) in con.iter().zip(correct.iter()) {
+            assert!((x - y).abs() < 1e-10);
+        }
+    }
+}
+
+#[test]
+fn test_plan_execute() {
+    let shapes = vec![vec![2, 3, 4], vec![4, 5, 3], vec![5, 2, 2]];
+    let order: [&[i32]; 3] = [&[-1, 1, 2], &[2, 3, 1], &[3, -2, -3]];
+    let plan = plan(&shapes, &order).unwrap();
+    assert_eq!(plan.shapes(), shapes.as_slice());
+
+    // The same plan serves many contractions with the same dimensions
+    for _ in 0..3 {
+        let tensors: Vec<ArrayD<f64>> = shapes.iter().map(|s| tensor::random(s)).collect();
+        let con = plan.execute(&tensors).unwrap();
+        let correct = contract(&tensors, &order).unwrap();
+
+        assert_eq!(con.shape(), correct.shape());
+        for (x, y) in con.iter().zip(correct.iter()) {
+            assert!((x - y).abs() < 1e-12);
+        }
+    }
+}
+
+#[test]
+fn test_plan_execute_fail() {
+    let shapes = vec![vec![2, 3], vec![3, 2]];
+    let plan = compile(&shapes, &[&[-1, 1], &[1, -2]], &ContractOptions::default()).unwrap();
+
+    let a = tensor::random(&[2, 3]);
+    let b = tensor::random(&[3, 4]);
+    assert_eq!(
+        QtnError::PlanShapeMismatch {
+            tensor: 1,
+            expected: vec![3, 2],
+            found: vec![3, 4],
+        },
+        plan.execute(&[a.clone(), b]).unwrap_err()
+    );
+    assert_eq!(
+        QtnError::OrderCountMismatch {
+            tensors: 1,
+            orders: 2
+        },
+        plan.execute(&[a]).unwrap_err()
+    );
+}
+
+#[test]
+fn test_contract_with_deterministic() {
+    let a = tensor::random(&[40, 30, 5]);
+    let b = tensor::random(&[5, 30, 60]);
+    let c = tensor::random(&[60, 7]);
+    let tensors = [a, b, c];
+    let order: [&[i32]; 3] = [&[-1, 1, 2], &[2, 1, 3], &[3, -2]];
+
+    let options = ContractOptions::default().deterministic(true);
+    let first = contract_with(&tensors, &order, &options).unwrap();
+    let correct = contract(&tensors, &order).unwrap();
+
+    for (x, y) in first.iter().zip(correct.iter()) {
+        assert!((x - y).abs() < 1e-9 * y.abs().max(1.0));
+    }
+    for _ in 0..3 {
+        let again = contract_with(&tensors, &order, &options).unwrap();
+        assert!(first
+            .iter()
+            .zip(again.iter())
+            .all(|(x, y)| x.to_bits() == y.to_bits()));
+    }
+}
+
+#[test]
+fn test_contract_with_extended_precision() {
+    let options = ContractOptions::default().extended_precision(true);
+    let small = 2f64.powi(-30);
+
+    // (1 + e)(1 - e) - 1 = -e^2 is lost when the product is rounded
+    let a = Array::from_shape_vec(IxDyn(&[2]), vec![1.0 + small, -1.0]).unwrap();
+    let b = Array::from_shape_vec(IxDyn(&[2]), vec![1.0 - small, 1.0]).unwrap();
+    let tensors = [a, b];
+    let order: [&[i32]; 2] = [&[1], &[1]];
+    let exact = contract_with(&tensors, &order, &options).unwrap();
+    assert_eq!(exact[[]], -small * small);
+    assert_eq!(contract(&tensors, &order).unwrap()[[]], 0.0);
+
+    // Cancelling large terms in a trace
+    let diag = Array::from_shape_fn(IxDyn(&[3, 3]), |i| {
+        if i[0] != i[1] {
+            0.0
+        } else {
+            [1e16, 1.0, -1e16][i[0]]
+        }
+    });
+    let trace: [&[i32]; 1] = [&[1, 1]];
+    let traced = contract_with(std::slice::from_ref(&diag), &trace, &options).unwrap();
+    assert_eq!(traced[[]], 1.0);
+
+    // Larger networks agree with the default kernels
+    let tensors = [
+        tensor::random(&[6, 5, 4]),
+        tensor::random(&[4, 5, 7]),
+        tensor::random(&[7, 3]),
+    ];
+    let order: [&[i32]; 3] = [&[-1, 1, 2], &[2, 1, 3], &[3, -2]];
+    let extended = contract_with(&tensors, &order, &options).unwrap();
+    let correct = contract(&tensors, &order).unwrap();
+    assert!(extended
+        .iter()
+        .zip(correct.iter())
+        .all(|(x, y)| (x - y).abs() < 1e-12 * y.abs().max(1.0)));
+
+    // Complex products keep the extra digits in both parts
+    let i = c64::new(0.0, 1.0);
+    let a = Array::from_shape_vec(IxDyn(&[2]), vec![i * (1.0 + small), i]).unwrap();
+    let b = Array::from_shape_vec(IxDyn(&[2]), vec![i * (1.0 - small), -i]).unwrap();
+    let pair: [&[i32]; 2] = [&[1], &[1]];
+    let exact = contract_with(&[a, b], &pair, &options).unwrap();
+    assert_eq!(exact[[]], c64::new(small * small, 0.0));
+}
+
+#[test]
+fn test_contract_with_self_test() {
+    let tensors = [
+        tensor::random(&[4, 3, 5, 3]),
+        tensor::random(&[5, 6, 2]),
+        tensor::random(&[6, 7]),
+    ];
+    let shapes: Vec<Vec<usize>> = tensors.iter().map(|t| t.shape().to_vec()).collect();
+    let order: [&[i32]; 3] = [&[-1, 1, 2, 1], &[2, 3, -3], &[3, -2]];
+    let correct = contract(&tensors, &order).unwrap();
+
+    // Traces, permuted outputs, slices and parallel branches are all checked against the oracle
+    for options in [
+        ContractOptions::default(),
+        ContractOptions::default().out_order(&[-3, -1, -2]),
+        ContractOptions::default().memory_limit(56),
+        ContractOptions::default().parallel_branches(true),
+        ContractOptions::default().extended_precision(true),
+    ] {
+        let options = options.self_test(8);
+        let rslt = contract_with(&tensors, &order, &options).unwrap();
+
+        let compiled = compile(&shapes, &order, &options).unwrap();
+        let mut out = ArrayD::zeros(compiled.output_shape().to_vec());
+        compiled.execute_into(&tensors, &mut out).unwrap();
+        assert_eq!(out, rslt);
+
+        let mut ctx = ContractionContext::with_options(options);
+        assert_eq!(ctx.contract(&tensors, &order).unwrap(), rslt);
+    }
+    let rslt = contract_with(&tensors, &order, &ContractOptions::default().self_test(8)).unwrap();
+    assert!((&rslt - &correct).iter().all(|x| x.abs() < 1e-12));
+
+    // Scalars, outer products and complex tensors
+    let a = tensor::random(&[3]).mapv(|x| c64::new(x, 1.0 - x));
+    let b = tensor::random(&[2]).mapv(|x| c64::new(0.5, x));
+    let options = ContractOptions::default().self_test(4);
+    let outer: [&[i32]; 2] = [&[-2], &[-1]];
+    let rslt = contract_with(&[a.clone(), a.clone()], &[&[1], &[1]], &options).unwrap();
+    assert_eq!(rslt.ndim(), 0);
+    let rslt = contract_with(&[a.clone(), b.clone()], &outer, &options).unwrap();
+    assert_eq!(rslt[[1, 2]], a[[2]] * b[[1]]);
+}
+
+#[test]
+fn test_contract_with_memory_limit() {
+    let a = tensor::random(&[5, 6, 7]);
+    let b = tensor::random(&[6, 8, 9]);
+    let c = tensor::random(&[7, 8, 9, 4]);
+    let tensors = [a, b, c];
+    let shapes: Vec<Vec<usize>> = tensors.iter().map(|t| t.shape().to_vec()).collect();
+    let order: [&[i32]; 3] = [&[-1, 1, 2], &[1, 3, 4], &[2, 3, 4, -2]];
+
+    let full = contract_info(&shapes, &order).unwrap();
+    assert_eq!(full.slices, 1);
+    let limit = full.largest_intermediate / 10;
+
+    let correct = contract(&tensors, &order).unwrap();
+    for strategy in [
+        SliceStrategy::Overhead,
+        SliceStrategy::Memory,
+        SliceStrategy::Betweenness,
+    ] {
+        let options = ContractOptions::default()
+            .memory_limit(limit)
+            .slice_strategy(strategy);
+        let info = compile(&shapes, &order, &options).unwrap().info();
+        assert!(info.slices > 1);
+        assert!(info.largest_intermediate <= limit);
+        assert!(info.flops >= full.flops);
+
+        let sliced = contract_with(&tensors, &order, &options).unwrap();
+        assert_eq!(sliced.shape(), &[5, 4]);
+        for (x, y) in sliced.iter().zip(correct.iter()) {
+            assert!((x - y).abs() < 1e-9 * y.abs().max(1.0));
+        }
+    }
+    let options = ContractOptions::default().memory_limit(limit);
+
+    // Parallel slices are summed in slice order
+    let serial = options.clone().deterministic(true);
+    let parallel = serial.clone().parallel_slices(true);
+    let first = contract_with(&tensors, &order, &serial).unwrap();
+    let second = contract_with(&tensors, &order, &parallel).unwrap();
+    assert!(first
+        .iter()
+        .zip(second.iter())
+        .all(|(x, y)| x.to_bits() == y.to_bits()));
+
+    // The result itself cannot be sliced
+    let options = ContractOptions::default().memory_limit(10);
+    assert_eq!(
+        compile(&shapes, &order, &options),
+        Err(QtnError::MemoryLimitExceeded {
+            limit: 10,
+            required: 20
+        })
+    );
+}
+
+#[test]
+fn test_contract_with_parallel_branches() {
+    let shapes = [[4, 30], [30, 30], [30, 30], [30, 4]];
+    let tensors: Vec<ArrayD<f64>> = shapes.iter().map(|s| tensor::random(s)).collect();
+    let order: [&[i32]; 4] = [&[-1, 1], &[1, 2], &[2, 3], &[3, -2]];
+
+    // (AB)(CD): the two products are independent
+    let balanced = Optimizer::Path(vec![vec![0, 1], vec![1, 2], vec![0, 1]]);
+    let options = ContractOptions::default().optimizer(balanced);
+    let correct = contract_with(&tensors, &order, &options).unwrap();
+
+    let parallel = options.clone().parallel_branches(true);
+    assert_eq!(contract_with(&tensors, &order, &parallel).unwrap(), correct);
+
+    let (result, report) = contract_profiled(&tensors, &order, &parallel).unwrap();
+    assert_eq!(result, correct);
+    assert_eq!(report.steps.len(), 3);
+
+    // Branches also combine with slicing
+    let sliced = parallel.memory_limit(60);
+    let result = contract_with(&tensors, &order, &sliced).unwrap();
+    for (x, y) in result.iter().zip(correct.iter()) {
+        assert!((x - y).abs() < 1e-9 * y.abs().max(1.0));
+    }
+}
+
+#[test]
+fn test_contract_profiled() {
+    let a = tensor::random(&[5, 6, 7]);
+    let b = tensor::random(&[6, 8, 9]);
+    let c = tensor::random(&[7, 8, 9, 4]);
+    let tensors = [a, b, c];
+    let shapes: Vec<Vec<usize>> = tensors.iter().map(|t| t.shape().to_vec()).collect();
+    let order: [&[i32]; 3] = [&[-1, 1, 2], &[1, 3, 4], &[2, 3, 4, -2]];
+
+    let options = ContractOptions::default();
+    let (result, report) = contract_profiled(&tensors, &order, &options).unwrap();
+    let info = contract_info(&shapes, &order).unwrap();
+
+    assert_eq!(result, contract(&tensors, &order).unwrap());
+    assert_eq!(report.slices, 1);
+    assert_eq!(report.steps.len(), info.path.len());
+    for (step, pair) in report.steps.iter().zip(&info.path) {
+        assert_eq!(step.pair.to_vec(), *pair);
+    }
+    assert_eq!(
+        report.steps.iter().map(|s| s.flops).sum::<f64>(),
+        info.flops
+    );
+    assert_eq!(
+        report.steps.last().unwrap().shape.iter().product::<usize>(),
+        20
+    );
+    assert!(
+        report
+            .steps
+            .iter()
+            .map(|s| s.time)
+            .sum::<std::time::Duration>()
+            <= report.total
+    );
+    assert_eq!(report.to_string().lines().count(), info.path.len() + 2);
+
+    // Sliced steps are summed over the slices
+    let options = options.memory_limit(100);
+    let (_, sliced) = contract_profiled(&tensors, &order, &options).unwrap();
+    assert!(sliced.slices > 1);
+    let flops = compile(&shapes, &order, &options).unwrap().info().flops;
+    assert_eq!(sliced.steps.iter().map(|s| s.flops).sum::<f64>(), flops);
+}
+
+#[test]
+fn test_contract_with_bounds() {
+    // Single precision inputs with a trace, checked against double precision
+    let shapes: [&[usize]; 3] = [&[20, 30, 4, 4], &[30, 40], &[40, 5]];
+    let order: [&[i32]; 3] = [&[-1, 1, 2, 2], &[1, 3], &[3, -2]];
+    let single: Vec<ArrayD<f32>> = shapes
+        .iter()
+        .map(|s| tensor::random(s).mapv(|x| x as f32 - 0.5))
+        .collect();
+    let double: Vec<ArrayD<f64>> = single.iter().map(|t| t.mapv(|x| x as f64)).collect();
+    let exact = contract(&double, &order).unwrap();
+
+    for options in [
+        ContractOptions::default(),
+        ContractOptions::default().memory_limit(120),
+    ] {
+        let (value, bound) = contract_with_bounds(&single, &order, &options).unwrap();
+        assert_eq!(value, contract_with(&single, &order, &options).unwrap());
+        assert_eq!(bound.shape(), &[20, 5]);
+        for ((v, b), e) in value.iter().zip(bound.iter()).zip(exact.iter()) {
+            assert!((*v as f64 - e).abs() <= *b as f64);
+            assert!(*b > 0.0 && *b < 1e-3);
+        }
+    }
+
+    // Exact inputs with a single product of one term have no error beyond one rounding
+    let a = Array::from_shape_vec(vec![1, 1], vec![3.0]).unwrap();
+    let (value, bound) = contract_with_bounds(
+        &[a.clone(), a],
+        &[&[-1, 1], &[1, -2]],
+        &ContractOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(value[[0, 0]], 9.0);
+    assert!(bound[[0, 0]] < 1e-14);
+}
+
+#[test]
+fn test_contract_info() {
+    // Matrix chain (2x100)(100x3)(3x50) with a trace on the last tensor
+    let shapes = vec![vec![2, 100], vec![100, 3], vec![3, 50, 4, 4]];
+    let order: [&[i32]; 3] = [&[-1, 1], &[1, 2], &[2, -2, 3, 3]];
+    let info = contract_info(&shapes, &order).unwrap();
+
+    assert_eq!(info.output_shape, vec![2, 50]);
+    assert_eq!(info.path.len(), 2);
+    assert!(info.flops >= (2 * 100 * 3 + 2 * 3 * 50) as f64);
+    assert!(info.largest_intermediate >= 2 * 50);
+
+    let plan = plan(&shapes, &order).unwrap();
+    assert_eq!(plan.info(), info);
+    assert_eq!(plan.output_shape(), &[2, 50]);
+
+    // A single tensor has no pairwise step
+    let info = contract_info(&[vec![3, 3, 5]], &[&[1, 1, -1]]).unwrap();
+    assert_eq!(info.flops, 0.0);
+    assert_eq!(info.largest_intermediate, 5);
+    assert_eq!(info.output_shape, vec![5]);
+}
+
+#[test]
+fn test_contract_with_structure() {
+    let x = tensor::random(&[30, 40]);
+    let d = Array2::from_diag(&tensor::random(&[40]).into_dimensionality().unwrap()).into_dyn();
+    let id = Array2::<f64>::eye(30).into_dyn();
+    let y = tensor::random(&[40, 2]);
+    let tensors = [x, d, id, y];
+    let order: [&[i32]; 4] = [&[1, 2], &[2, 3], &[-1, 1], &[3, -2]];
+    let correct = contract(&tensors, &order).unwrap();
+
+    let structure = [
+        TensorStructure::Dense,
+        TensorStructure::Diagonal,
+        TensorStructure::Identity,
+        TensorStructure::Dense,
+    ];
+    let options = ContractOptions::default()
+        .structure(&structure)
+        .optimizer(Optimizer::BranchAndBound { max_nodes: 1000 });
+    let con = contract_with(&tensors, &order, &options).unwrap();
+
+    assert_eq!(con.shape(), correct.shape());
+    for (x, y) in con.iter().zip(correct.iter()) {
+        assert!((x - y).abs() < 1e-10);
+    }
+
+    // Absorbing the diagonal into the thin tensor and relabeling through the identity leaves a
+    // single dense product
+    let shapes: Vec<Vec<usize>> = tensors.iter().map(|t| t.shape().to_vec()).collect();
+    let info = compile(&shapes, &order, &options).unwrap().info();
+    assert_eq!(info.flops, (40 * 2 + 30 * 40 * 2) as f64);
+}
+
+#[test]
+fn test_contract_with_structure_fail() {
+    let a = tensor::random(&[2, 3]);
+    let b = tensor::random(&[3, 2]);
+    let order: [&[i32]; 2] = [&[-1, 1], &[1, -2]];
+
+    for structure in [
+        vec![TensorStructure::Diagonal, TensorStructure::Dense],
+        vec![TensorStructure::Dense],
+        vec![
+            TensorStructure::Dense,
+            TensorStructure::Sparse { density: 0.0 },
+        ],
+    ] {
+        let options = ContractOptions::default().structure(&structure);
+        assert!(matches!(
+            contract_with(&[a.clone(), b.clone()], &order, &options),
+            Err(QtnError::InvalidArgument(_))
+        ));
+    }
+}
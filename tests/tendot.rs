@@ -1,5 +1,6 @@
 use ndarray::*;
-use qua_ten_net::tendot::tensor_dot;
+use qua_ten_net::error::QtnError;
+use qua_ten_net::tendot::{outer, tensor_dot, tensor_dot_into, Axes};
 
 #[test]
 fn test_tensor_dot() {
@@ -39,7 +40,7 @@ fn test_tensor_dot_fail_axis_length() {
     match dot {
         Ok(_) => {}
         Err(err) => {
-            assert_eq!("Axis length is not even number!", err);
+            assert_eq!(QtnError::OddAxisCount { len: 3 }, err);
         }
     }
 }
@@ -58,9 +59,186 @@ fn test_tensor_dot_fail_index() {
         Ok(_) => {}
         Err(err) => {
             assert_eq!(
-                "Shape mismatch along specified axes: a[1] = 3, b[1] = 2",
+                QtnError::ShapeMismatch {
+                    tensor: 0,
+                    axis: 1,
+                    dim: 3,
+                    other_tensor: 1,
+                    other_axis: 1,
+                    other_dim: 2,
+                },
                 err
             );
         }
     }
 }
+
+#[test]
+fn test_tensor_dot_complex() {
+    let i = ndarray_linalg::c64::new(0.0, 1.0);
+    let one = ndarray_linalg::c64::new(1.0, 0.0);
+
+    // Pauli Y applied to |0> gives i|1>
+    let y =
+        Array::from_shape_vec(vec![2, 2], vec![0.0 * one, -i, i, 0.0 * one]).expect("ShapeError!");
+    let ket = Array::from_shape_vec(vec![2], vec![one, 0.0 * one]).expect("ShapeError!");
+
+    let result = tensor_dot(&y, &ket, vec![1, 0]).unwrap();
+    let rslt = Array::from_shape_vec(vec![2], vec![0.0 * one, i]).expect("ShapeError!");
+    assert_eq!(result, rslt);
+}
+
+#[test]
+fn test_tensor_dot_small_inner_dimension() {
+    // Inner dimensions 1..=4 take the unrolled kernel, 5 falls back to the matrix product
+    for k in 1..=5 {
+        let vec_a: Vec<f64> = (0..3 * k * 2).map(|x| (x as f64).sin()).collect();
+        let a = Array::from_shape_vec(vec![3, k, 2], vec_a).expect("ShapeError!");
+
+        let vec_b: Vec<f64> = (0..k * 5).map(|x| (x as f64).cos()).collect();
+        let b = Array::from_shape_vec(vec![k, 5], vec_b).expect("ShapeError!");
+
+        let result = tensor_dot(&a, &b, vec![1, 0]).unwrap();
+
+        let a_mat = a
+            .permuted_axes(vec![0, 2, 1])
+            .to_shape((6, k))
+            .unwrap()
+            .to_owned();
+        let b_mat = b.into_dimensionality::<Ix2>().unwrap();
+        let correct = a_mat
+            .dot(&b_mat)
+            .into_shape_with_order(vec![3, 2, 5])
+            .unwrap();
+
+        for (x, y) in result.iter().zip(correct.iter()) {
+            assert!((x - y).abs() < 1e-12);
+        }
+    }
+}
+
+#[test]
+fn test_tensor_dot_into() {
+    let vec_a: Vec<f64> = (0..60).map(|x| x as f64).collect();
+    let a = Array::from_shape_vec(vec![3, 4, 5], vec_a).expect("ShapeError!");
+    let vec_b: Vec<f64> = (0..80).map(|x| (x as f64).sin()).collect();
+    let b = Array::from_shape_vec(vec![5, 4, 4], vec_b).expect("ShapeError!");
+
+    // Inner dimensions 20 (GEMM) and 4 (unrolled kernel)
+    for axes in [vec![1, 2, 1, 0], vec![1, 1]] {
+        let rslt = tensor_dot(&a, &b, axes.clone()).unwrap();
+        let mut out = ArrayD::from_elem(rslt.shape(), f64::NAN);
+        tensor_dot_into(&a, &b, axes.clone(), &mut out).unwrap();
+        assert_eq!(out, rslt);
+
+        // Outputs in a non-standard layout are filled as well
+        let mut out = ArrayD::zeros(rslt.raw_dim().f());
+        tensor_dot_into(&a, &b, axes, &mut out).unwrap();
+        assert_eq!(out, rslt);
+    }
+
+    let mut out = ArrayD::zeros(vec![3, 3]);
+    assert!(matches!(
+        tensor_dot_into(&a, &b, vec![1, 2, 1, 0], &mut out),
+        Err(QtnError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn test_outer() {
+    let a = Array::from_shape_vec(vec![2, 3], (0..6).map(|x| x as f64).collect()).unwrap();
+    let b = Array::from_shape_vec(vec![2], vec![1.0, -2.0]).unwrap();
+
+    let product = outer(&a, &b);
+    assert_eq!(product.shape(), &[2, 3, 2]);
+    assert_eq!(product[[1, 2, 0]], 5.0);
+    assert_eq!(product[[1, 2, 1]], -10.0);
+
+    // Transposed inputs are read in their logical order
+    let at = a.t().to_owned();
+    assert_eq!(outer(&b, &at)[[1, 2, 1]], -10.0);
+
+    // A rank 0 tensor scales the other one
+    let scalar = Array::from_elem(IxDyn(&[]), 3.0);
+    assert_eq!(outer(&scalar, &a), &a * 3.0);
+
+    // Projector |psi><psi| of a normalized vector
+    let psi = Array::from_shape_vec(vec![2], vec![0.6f64, 0.8]).unwrap();
+    let projector = outer(&psi, &psi);
+    let squared = tensor_dot(&projector, &projector, vec![1, 0]).unwrap();
+    assert!((&squared - &projector).iter().all(|x| x.abs() < 1e-12));
+}
+
+#[test]
+fn test_tensor_dot_empty_axes() {
+    let a = Array::from_shape_vec(vec![2, 3], (0..6).map(|x| x as f64).collect()).unwrap();
+    let b = Array::from_shape_vec(vec![2], vec![1.0, -2.0]).unwrap();
+
+    // No contracted axes gives the outer product, as `numpy.tensordot(a, b, axes=0)`
+    let product = tensor_dot(&a, &b, vec![]).unwrap();
+    assert_eq!(product.shape(), &[2, 3, 2]);
+    assert_eq!(product, outer(&a, &b));
+
+    let mut out = ArrayD::zeros(vec![2, 3, 2]);
+    tensor_dot_into(&a, &b, vec![], &mut out).unwrap();
+    assert_eq!(out, product);
+}
+
+#[test]
+fn test_tensor_dot_numpy_axes() {
+    let a = Array::from_shape_vec(vec![2, 3, 4], (0..24).map(|x| x as f64).collect()).unwrap();
+    let b = Array::from_shape_vec(vec![3, 4, 2], (0..24).map(|x| x as f64).collect()).unwrap();
+
+    // `Last(2)` contracts the last two axes of `a` with the first two of `b`
+    let last = tensor_dot(&a, &b, Axes::Last(2)).unwrap();
+    assert_eq!(last.shape(), &[2, 2]);
+    assert_eq!(last, tensor_dot(&a, &b, vec![1, 2, 0, 1]).unwrap());
+
+    let pairs = tensor_dot(&a, &b, Axes::Pairs(vec![2, 1], vec![1, 0])).unwrap();
+    assert_eq!(pairs, last);
+
+    let b_t = b.clone().permuted_axes(IxDyn(&[2, 0, 1]));
+    let swapped = tensor_dot(&a, &b_t, Axes::Pairs(vec![1, 2], vec![1, 2])).unwrap();
+    assert_eq!(swapped, last);
+
+    let mut out = ArrayD::zeros(vec![2, 2]);
+    tensor_dot_into(&a, &b, Axes::Pairs(vec![1, 2], vec![0, 1]), &mut out).unwrap();
+    assert_eq!(out, last);
+
+    assert_eq!(tensor_dot(&a, &b, Axes::Last(0)).unwrap(), outer(&a, &b));
+}
+
+#[test]
+fn test_tensor_dot_axes_errors() {
+    let a = ArrayD::<f64>::zeros(vec![2, 3]);
+    let b = ArrayD::<f64>::zeros(vec![3, 2]);
+
+    assert!(matches!(
+        tensor_dot(&a, &b, Axes::Last(3)),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        tensor_dot(&a, &b, Axes::Pairs(vec![1], vec![0, 1])),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        tensor_dot(&a, &b, Axes::Pairs(vec![2], vec![0])),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        tensor_dot(&a, &b, vec![1, 5]),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        tensor_dot(&a, &b, Axes::Pairs(vec![0, 0], vec![0, 1])),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        tensor_dot(&a, &b, vec![0, 1, 1, 1]),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        tensor_dot(&a, &b, Axes::Last(2)),
+        Err(QtnError::ShapeMismatch { .. })
+    ));
+}
@@ -1,4 +1,6 @@
 use ndarray::{Array, Array1, Array2, IxDyn};
+use ndarray_linalg::c64;
+use qua_ten_net::error::QtnError;
 use qua_ten_net::tensor::*;
 
 #[test]
@@ -80,3 +82,513 @@ fn test_svd() {
     assert_eq!(svd.sigma, sigma);
     assert_eq!(svd.vt, vt);
 }
+
+#[test]
+fn test_svd_complex() {
+    let tnsr = Array2::from_shape_vec(
+        (2, 3),
+        (0..6).map(|x| c64::new(x as f64, 1.0 - x as f64)).collect(),
+    )
+    .expect("ShapeError!");
+    let svd = svd(tnsr.clone()).unwrap();
+
+    assert_eq!(svd.u.dim(), (2, 2));
+    assert_eq!(svd.sigma.len(), 2);
+    assert_eq!(svd.vt.dim(), (3, 3));
+    assert!(svd.sigma[0] >= svd.sigma[1]);
+
+    let mut s = Array2::<c64>::zeros((2, 3));
+    for (i, &x) in svd.sigma.iter().enumerate() {
+        s[[i, i]] = c64::new(x, 0.0);
+    }
+    let recon = svd.u.dot(&s).dot(&svd.vt);
+
+    for (x, y) in recon.iter().zip(tnsr.iter()) {
+        assert!((x - y).norm() < 1e-12);
+    }
+}
+
+#[test]
+fn test_qr_lq_rq() {
+    for (m, n) in [(3, 5), (5, 3), (4, 4)] {
+        let k = m.min(n);
+        let tnsr = Array2::from_shape_fn((m, n), |(i, j)| {
+            c64::new((i * n + j) as f64 % 7.0 - 3.0, (i + 2 * j) as f64 % 3.0)
+        });
+        let close = |x: &Array2<c64>, y: &Array2<c64>| {
+            x.dim() == y.dim() && x.iter().zip(y.iter()).all(|(a, b)| (a - b).norm() < 1e-10)
+        };
+        let adjoint = |x: &Array2<c64>| x.t().mapv(|v| v.conj());
+        let eye = Array2::from_diag(&Array1::from_elem(k, c64::new(1.0, 0.0)));
+
+        let (q, r) = qr(tnsr.clone()).unwrap();
+        assert_eq!((q.dim(), r.dim()), ((m, k), (k, n)));
+        assert!(close(&q.dot(&r), &tnsr));
+        assert!(close(&adjoint(&q).dot(&q), &eye));
+        assert!(r
+            .indexed_iter()
+            .all(|((i, j), x)| j >= i || x.norm() < 1e-12));
+
+        let (l, q) = lq(tnsr.clone()).unwrap();
+        assert_eq!((l.dim(), q.dim()), ((m, k), (k, n)));
+        assert!(close(&l.dot(&q), &tnsr));
+        assert!(close(&q.dot(&adjoint(&q)), &eye));
+        assert!(l
+            .indexed_iter()
+            .all(|((i, j), x)| j <= i || x.norm() < 1e-12));
+
+        let (r, q) = rq(tnsr.clone()).unwrap();
+        assert_eq!((r.dim(), q.dim()), ((m, k), (k, n)));
+        assert!(close(&r.dot(&q), &tnsr));
+        assert!(close(&q.dot(&adjoint(&q)), &eye));
+        assert!(r
+            .indexed_iter()
+            .all(|((i, j), x)| j + m >= i + k || x.norm() < 1e-12));
+    }
+}
+
+#[test]
+fn test_linear_solvers() {
+    let a = Array2::from_shape_vec((3, 3), vec![4.0, 1.0, 2.0, 1.0, 5.0, 0.5, 2.0, 0.5, 6.0])
+        .expect("ShapeError!");
+    let x =
+        Array2::from_shape_vec((3, 2), vec![1.0, -2.0, 0.5, 3.0, -1.0, 0.0]).expect("ShapeError!");
+    let b = a.dot(&x);
+    let close = |y: &Array2<f64>, z: &Array2<f64>| {
+        y.dim() == z.dim() && y.iter().zip(z.iter()).all(|(p, q)| (p - q).abs() < 1e-10)
+    };
+
+    assert!(close(&solve(&a, &b).unwrap(), &x));
+    assert!(close(&solve_cholesky(&a, &b).unwrap(), &x));
+    assert!(close(&least_squares(&a, &b, 1e-12).unwrap(), &x));
+
+    let l = cholesky(&a).unwrap();
+    assert!(close(&l.dot(&l.t()), &a));
+    assert!(l.indexed_iter().all(|((i, j), &v)| j <= i || v == 0.0));
+
+    // Overdetermined fit of a line y = 1 + 2 t through exact samples
+    let t = [0.0, 1.0, 2.0, 3.0];
+    let design = Array2::from_shape_fn((4, 2), |(i, j)| if j == 0 { 1.0 } else { t[i] });
+    let y = Array2::from_shape_fn((4, 1), |(i, _)| 1.0 + 2.0 * t[i]);
+    let coef = least_squares(&design, &y, 1e-12).unwrap();
+    assert!(close(
+        &coef,
+        &Array2::from_shape_vec((2, 1), vec![1.0, 2.0]).unwrap()
+    ));
+
+    // Rank-deficient systems give the minimum-norm solution
+    let ones = Array2::from_elem((2, 2), 1.0);
+    let rhs = Array2::from_elem((2, 1), 2.0);
+    let min_norm = least_squares(&ones, &rhs, 1e-12).unwrap();
+    assert!(close(&min_norm, &Array2::from_elem((2, 1), 1.0)));
+
+    assert!(matches!(
+        solve(&design, &y),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(solve(&a, &y), Err(QtnError::InvalidArgument(_))));
+    assert!(matches!(cholesky(&-&a), Err(QtnError::LinalgFailure(_))));
+}
+
+#[test]
+fn test_eigh() {
+    let a = Array2::from_shape_vec(
+        (3, 3),
+        vec![2.0, -1.0, 0.0, -1.0, 2.0, -1.0, 0.0, -1.0, 2.0],
+    )
+    .expect("ShapeError!");
+    let (w, v) = eigh(&a).unwrap();
+
+    let root = 2f64.sqrt();
+    let expected = [2.0 - root, 2.0, 2.0 + root];
+    assert!(w
+        .iter()
+        .zip(&expected)
+        .all(|(p, q): (&f64, &f64)| (p - q).abs() < 1e-12));
+    let rebuilt = v.dot(&Array2::from_diag(&w)).dot(&v.t());
+    assert!((&rebuilt - &a).iter().all(|x| x.abs() < 1e-12));
+
+    // Pauli Y has eigenvalues -1 and 1 with complex eigenvectors
+    let i = c64::new(0.0, 1.0);
+    let zero = c64::new(0.0, 0.0);
+    let y = Array2::from_shape_vec((2, 2), vec![zero, -i, i, zero]).expect("ShapeError!");
+    let (w, v) = eigh(&y).unwrap();
+    assert!((w[0] + 1.0).abs() < 1e-12 && (w[1] - 1.0).abs() < 1e-12);
+    let rebuilt = v
+        .dot(&Array2::from_diag(&w.mapv(|x| c64::new(x, 0.0))))
+        .dot(&v.t().mapv(|x| x.conj()));
+    assert!((&rebuilt - &y).iter().all(|x| x.norm() < 1e-12));
+
+    assert!(matches!(
+        eigh(&Array2::<f64>::zeros((2, 3))),
+        Err(QtnError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn test_eig() {
+    // Rotation by 90 degrees scaled by 2, plus a decoupled eigenvalue 0.5
+    let a: Array2<f64> =
+        Array2::from_shape_vec((3, 3), vec![0.0, -2.0, 0.0, 2.0, 0.0, 0.0, 0.0, 0.0, 0.5])
+            .expect("ShapeError!");
+    let (w, v) = eig(&a).unwrap();
+
+    assert!((w[0].norm() - 2.0).abs() < 1e-12 && (w[1].norm() - 2.0).abs() < 1e-12);
+    assert!((w[0].im.abs() - 2.0).abs() < 1e-12);
+    assert!((w[2] - c64::new(0.5, 0.0)).norm() < 1e-12);
+
+    let complex = a.mapv(|x| c64::new(x, 0.0));
+    for (value, vector) in w.iter().zip(v.columns()) {
+        let residual = complex.dot(&vector) - vector.mapv(|x| x * value);
+        assert!(residual.iter().all(|x| x.norm() < 1e-12));
+    }
+
+    assert!(matches!(
+        eig(&Array2::<f64>::zeros((3, 2))),
+        Err(QtnError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn test_elementwise() {
+    let a = Array::from_shape_vec(IxDyn(&[2, 2]), vec![0.0f64, 1.0, -2.0, 0.5]).unwrap();
+
+    let exp = map_elements(&a, f64::exp);
+    assert_eq!(exp[[0, 0]], 1.0);
+    assert!((exp[[0, 1]] - std::f64::consts::E).abs() < 1e-15);
+
+    // Thresholding in place
+    let mut cut = a.clone();
+    map_elements_inplace(&mut cut, |x| if x.abs() < 1.0 { 0.0 } else { x });
+    assert_eq!(
+        cut.iter().cloned().collect::<Vec<_>>(),
+        vec![0.0, 1.0, -2.0, 0.0]
+    );
+
+    assert_eq!(add_scalar(&a, 1.0)[[1, 0]], -1.0);
+    assert_eq!(scale(&a, -2.0)[[1, 1]], -1.0);
+    let mut b = a.clone();
+    add_scalar_inplace(&mut b, 2.0);
+    scale_inplace(&mut b, 0.5);
+    assert_eq!(b, (&a + 2.0) * 0.5);
+
+    // Complex phases
+    let i = c64::new(0.0, 1.0);
+    let z = map_elements(&a.mapv(c64::from), |x| (i * x).exp());
+    assert!((z[[1, 0]] - c64::new(2f64.cos(), -(2f64.sin()))).norm() < 1e-15);
+    assert_eq!(scale(&z, i)[[0, 0]], i);
+}
+
+#[test]
+fn test_norms() {
+    let a = Array::from_shape_vec(IxDyn(&[2, 2]), vec![3.0f64, 0.0, -4.0, 0.0]).unwrap();
+    assert_eq!(frobenius_norm(&a), 5.0);
+    assert_eq!(max_abs(&a), 4.0);
+
+    let unit = normalize(&a).unwrap();
+    assert!((frobenius_norm(&unit) - 1.0).abs() < 1e-15);
+    assert_eq!(unit[[1, 0]], -0.8);
+
+    let b = Array::from_shape_vec(IxDyn(&[2, 2]), vec![3.0, 1.0, -4.0, 1.0]).unwrap();
+    assert!((distance(&a, &b).unwrap() - 2f64.sqrt()).abs() < 1e-15);
+    assert_eq!(distance(&a, &a).unwrap(), 0.0);
+
+    // Complex elements contribute their modulus
+    let z =
+        Array::from_shape_vec(IxDyn(&[2]), vec![c64::new(3.0, 4.0), c64::new(0.0, -12.0)]).unwrap();
+    assert_eq!(frobenius_norm(&z), 13.0);
+    assert_eq!(max_abs(&z), 12.0);
+
+    assert!(matches!(
+        normalize(&zeros(&[3])),
+        Err(QtnError::InvalidArgument(_))
+    ));
+    assert!(matches!(
+        distance(&a, &zeros(&[4])),
+        Err(QtnError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn test_adjoint() {
+    let i = c64::new(0.0, 1.0);
+    let z = Array::from_shape_vec(IxDyn(&[2]), vec![c64::new(1.0, 2.0), -i]).unwrap();
+    assert_eq!(conj(&z)[[0]], c64::new(1.0, -2.0));
+    assert_eq!(conj(&z)[[1]], i);
+
+    // sigma_y is Hermitian, a rectangular matrix is transposed
+    let sy = Array2::from_shape_vec((2, 2), vec![c64::new(0.0, 0.0), -i, i, c64::new(0.0, 0.0)])
+        .unwrap();
+    assert_eq!(dagger(&sy), sy);
+    let m =
+        Array2::from_shape_vec((2, 3), (0..6).map(|x| c64::new(x as f64, 1.0)).collect()).unwrap();
+    let md = dagger(&m);
+    assert_eq!(md.dim(), (3, 2));
+    assert_eq!(md[[2, 1]], c64::new(5.0, -1.0));
+    assert_eq!(dagger(&identity(3)), identity(3));
+
+    // Two-site operator with legs (o1, o2, i1, i2)
+    let op = random(&[2, 3, 4, 5]).mapv(|x| c64::new(x, 1.0 - x));
+    let adj = adjoint(&op, 2).unwrap();
+    assert_eq!(adj.shape(), &[4, 5, 2, 3]);
+    assert_eq!(adj[[3, 4, 1, 2]], op[[1, 2, 3, 4]].conj());
+    assert_eq!(adjoint(&adj, 2).unwrap(), op);
+
+    // For matrices it agrees with the dagger
+    let md_dyn = adjoint(&m.clone().into_dyn(), 1).unwrap();
+    assert_eq!(md_dyn, md.into_dyn());
+    assert!(matches!(adjoint(&op, 5), Err(QtnError::InvalidArgument(_))));
+}
+
+#[test]
+fn test_kron() {
+    let sz = diagonal(&[0.5, -0.5]);
+    let sx = Array2::from_shape_vec((2, 2), vec![0.0, 0.5, 0.5, 0.0]).unwrap();
+
+    let zx = kron(&sz, &sx);
+    assert_eq!(zx.dim(), (4, 4));
+    assert_eq!(zx[[0, 1]], 0.25);
+    assert_eq!(zx[[2, 3]], -0.25);
+    assert_eq!(zx[[0, 2]], 0.0);
+
+    // Rectangular factors and the mixed-product rule (A ⊗ B)(C ⊗ D) = AC ⊗ BD
+    let a: Array2<f64> = random(&[2, 3]).into_dimensionality().unwrap();
+    let b: Array2<f64> = random(&[3, 1]).into_dimensionality().unwrap();
+    let c: Array2<f64> = random(&[3, 2]).into_dimensionality().unwrap();
+    let d: Array2<f64> = random(&[1, 4]).into_dimensionality().unwrap();
+    let lhs = kron(&a, &b).dot(&kron(&c, &d));
+    let rhs = kron(&a.dot(&c), &b.dot(&d));
+    assert_eq!(lhs.dim(), (6, 8));
+    assert!((&lhs - &rhs).iter().all(|x| x.abs() < 1e-12));
+
+    let zz = kron_all(&[sz.clone(), identity(2), sz.clone()]);
+    assert_eq!(zz.dim(), (8, 8));
+    assert_eq!(zz, kron(&kron(&sz, &identity(2)), &sz));
+    assert_eq!(kron_all::<c64>(&[]), Array2::eye(1));
+}
+
+#[test]
+fn test_direct_sum() {
+    let a: Array2<f64> = random(&[2, 3]).into_dimensionality().unwrap();
+    let b: Array2<f64> = random(&[1, 2]).into_dimensionality().unwrap();
+    let sum = direct_sum(&a, &b);
+    assert_eq!(sum.dim(), (3, 5));
+    assert_eq!(sum.slice(ndarray::s![..2, ..3]), a);
+    assert_eq!(sum.slice(ndarray::s![2.., 3..]), b);
+    assert!(sum.slice(ndarray::s![..2, 3..]).iter().all(|&x| x == 0.0));
+    assert!(sum.slice(ndarray::s![2.., ..3]).iter().all(|&x| x == 0.0));
+
+    // Adding two MPS: a left boundary block, then a bulk tensor with bond axes 0 and 2
+    let left = direct_sum_axes(&random(&[1, 2, 3]), &random(&[1, 2, 2]), &[2]).unwrap();
+    assert_eq!(left.shape(), &[1, 2, 5]);
+    let (x, y) = (random(&[3, 2, 4]), random(&[2, 2, 1]));
+    let bulk = direct_sum_axes(&x, &y, &[0, 2]).unwrap();
+    assert_eq!(bulk.shape(), &[5, 2, 5]);
+    assert_eq!(bulk[[4, 1, 4]], y[[1, 1, 0]]);
+    assert_eq!(bulk[[0, 1, 3]], x[[0, 1, 3]]);
+    assert_eq!(bulk[[4, 1, 0]], 0.0);
+
+    assert!(matches!(
+        direct_sum_axes(&x, &random(&[2, 3, 1]), &[0, 2]),
+        Err(QtnError::ShapeMismatch { axis: 1, .. })
+    ));
+    assert!(matches!(
+        direct_sum_axes(&x, &random(&[2, 2]), &[0]),
+        Err(QtnError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn test_delta() {
+    let d = delta(3, 2);
+    assert_eq!(d.shape(), &[2, 2, 2]);
+    assert_eq!(d.sum(), 2.0);
+    assert_eq!(d[[1, 1, 1]], 1.0);
+    assert_eq!(d[[1, 0, 1]], 0.0);
+    assert_eq!(delta(2, 3), identity(3).into_dyn());
+    assert_eq!(delta(1, 4), ones(&[4]));
+    assert_eq!(delta(0, 4)[[]], 1.0);
+
+    // Three vectors sharing one index: sum_i a_i b_i c_i
+    let (a, b, c) = (random(&[3]), random(&[3]), random(&[3]));
+    let copy = delta(3, 3);
+    let rslt = qua_ten_net::tencon::contract(
+        &[copy, a.clone(), b.clone(), c.clone()],
+        &[&[1, 2, 3], &[1], &[2], &[3]],
+    )
+    .unwrap();
+    let correct: f64 = (0..3).map(|i| a[[i]] * b[[i]] * c[[i]]).sum();
+    assert!((rslt[[]] - correct).abs() < 1e-12);
+}
+
+#[test]
+fn test_svd_with() {
+    let a: Array2<f64> = random(&[7, 4]).into_dimensionality().unwrap();
+    let reference = svd(a.clone()).unwrap();
+    for driver in [SvdDriver::Gesvd, SvdDriver::Gesdd] {
+        let result = svd_with(a.clone(), driver).unwrap();
+        assert_eq!(result.u.dim(), (7, 7));
+        assert_eq!(result.vt.dim(), (4, 4));
+        assert!((&result.sigma - &reference.sigma)
+            .iter()
+            .all(|x| x.abs() < 1e-12));
+
+        let us = &result.u.slice(ndarray::s![.., ..4]) * &result.sigma;
+        assert!((&us.dot(&result.vt) - &a).iter().all(|x| x.abs() < 1e-12));
+    }
+
+    // Rank-deficient complex input
+    let b = Array2::from_shape_fn((3, 5), |(i, j)| c64::new((i * j) as f64, 1.0));
+    let result = svd_with(b, SvdDriver::Gesdd).unwrap();
+    assert_eq!(result.sigma.len(), 3);
+    assert!(result.sigma[2] < 1e-12 * result.sigma[0]);
+}
+
+#[test]
+fn test_svd_batched() {
+    let stack = random(&[2, 3, 4, 5]);
+    for parallel in [false, true] {
+        let results = svd_batched(stack.clone(), parallel).unwrap();
+        assert_eq!(results.len(), 6);
+        for (k, result) in results.iter().enumerate() {
+            let matrix = stack
+                .index_axis(ndarray::Axis(0), k / 3)
+                .index_axis_move(ndarray::Axis(0), k % 3)
+                .into_dimensionality()
+                .unwrap()
+                .to_owned();
+            let single = svd(matrix).unwrap();
+            assert_eq!(result.u.dim(), (4, 4));
+            assert!((&result.sigma - &single.sigma)
+                .iter()
+                .all(|x| x.abs() < 1e-12));
+        }
+    }
+
+    // A single matrix is a batch of one
+    assert_eq!(svd_batched(random(&[3, 2]), true).unwrap().len(), 1);
+    assert!(matches!(
+        svd_batched(random(&[3]), false),
+        Err(QtnError::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn test_svd_truncated() {
+    // Singular values 4, 2, 1 and 1e-9
+    let a = Array2::from_diag(&Array1::from_vec(vec![2.0, 4.0, 1e-9, 1.0]));
+    let close = |x: f64, y: f64| (x - y).abs() < 1e-12;
+
+    let (full, weight) = svd_truncated(a.clone(), None, 0.0, false).unwrap();
+    assert_eq!(full.sigma.len(), 4);
+    assert!(close(weight, 0.0));
+
+    let (cut, weight) = svd_truncated(a.clone(), None, 1e-6, false).unwrap();
+    assert_eq!(cut.sigma.len(), 3);
+    assert_eq!(cut.u.dim(), (4, 3));
+    assert_eq!(cut.vt.dim(), (3, 4));
+    assert!(weight < 1e-17);
+
+    let (two, weight) = svd_truncated(a.clone(), Some(2), 0.0, false).unwrap();
+    assert!(close(two.sigma[0], 4.0) && close(two.sigma[1], 2.0));
+    assert_eq!(two.sigma_f64.len(), 2);
+    assert!(close(weight, 1.0 / 21.0));
+
+    // Renormalized singular values keep the norm of the input
+    let (one, weight) = svd_truncated(a, Some(1), 0.0, true).unwrap();
+    assert!(close(one.sigma[0], 21f64.sqrt()));
+    assert!(close(weight, 5.0 / 21.0));
+
+    // At least one singular value is kept
+    let (zero, weight) = svd_truncated(Array2::<f64>::zeros((2, 3)), Some(0), 0.5, true).unwrap();
+    assert_eq!(zero.sigma.len(), 1);
+    assert!(close(weight, 0.0));
+}
+
+#[test]
+fn test_svd_to_error() {
+    // Singular values 4, 2, 1 and 1e-9 with a total weight of 21
+    let a = Array2::from_diag(&Array1::from_vec(vec![2.0, 4.0, 1e-9, 1.0]));
+    let close = |x: f64, y: f64| (x - y).abs() < 1e-12;
+
+    // The bond grows until the discarded weight meets the target
+    let (two, weight) = svd_to_error(a.clone(), 0.06, None, false).unwrap();
+    assert_eq!(two.sigma.len(), 2);
+    assert!(close(weight, 1.0 / 21.0));
+    let (three, weight) = svd_to_error(a.clone(), 1e-6, None, false).unwrap();
+    assert_eq!(three.u.dim(), (4, 3));
+    assert!(weight < 1e-17);
+    let (full, _) = svd_to_error(a.clone(), 0.0, None, false).unwrap();
+    assert_eq!(full.sigma.len(), 4);
+
+    // The hard cap wins over the target
+    let (one, weight) = svd_to_error(a, 1e-6, Some(1), true).unwrap();
+    assert_eq!(one.vt.dim(), (1, 4));
+    assert!(close(one.sigma[0], 21f64.sqrt()));
+    assert!(close(weight, 5.0 / 21.0));
+}
+
+#[test]
+fn test_nullspace() {
+    // Rank 1 map from R^3 to R^2: the kernel is the plane orthogonal to (1, 2, 3)
+    let a: Array2<f64> =
+        Array2::from_shape_vec((2, 3), vec![1.0, 2.0, 3.0, 2.0, 4.0, 6.0]).expect("ShapeError!");
+    let kernel = nullspace(a.clone(), 1e-12).unwrap();
+    assert_eq!(kernel.dim(), (3, 2));
+    assert!(a.dot(&kernel).iter().all(|x| x.abs() < 1e-12));
+    let gram = kernel.t().dot(&kernel);
+    assert!((&gram - &identity(2)).iter().all(|x| x.abs() < 1e-12));
+
+    // Invertible matrices have a trivial kernel, zero matrices a full one
+    assert_eq!(nullspace(identity(3), 1e-12).unwrap().dim(), (3, 0));
+    assert_eq!(
+        nullspace(Array2::<f64>::zeros((2, 2)), 1e-12)
+            .unwrap()
+            .dim(),
+        (2, 2)
+    );
+
+    // Complex kernel spanned by (i, 1)
+    let i = c64::new(0.0, 1.0);
+    let one = c64::new(1.0, 0.0);
+    let b = Array2::from_shape_vec((1, 2), vec![one, i]).expect("ShapeError!");
+    let kernel = nullspace(b.clone(), 1e-12).unwrap();
+    assert_eq!(kernel.dim(), (2, 1));
+    assert!(b.dot(&kernel).iter().all(|x| x.norm() < 1e-12));
+}
+
+#[test]
+fn test_eig_generalized() {
+    let a: Array2<f64> =
+        Array2::from_shape_vec((3, 3), vec![2.0, 1.0, 0.0, 1.0, 3.0, 1.0, 0.0, 1.0, 4.0])
+            .expect("ShapeError!");
+    let b = Array2::from_shape_vec((3, 3), vec![2.0, 0.5, 0.0, 0.5, 1.0, 0.2, 0.0, 0.2, 1.5])
+        .expect("ShapeError!");
+
+    let (w, v) = eigh_generalized(&a, &b).unwrap();
+    assert!(w[0] <= w[1] && w[1] <= w[2]);
+    let residual = a.dot(&v) - b.dot(&v).dot(&Array2::from_diag(&w));
+    assert!(residual.iter().all(|x| x.abs() < 1e-10));
+    let gram = v.t().dot(&b).dot(&v);
+    assert!((&gram - &identity(3)).iter().all(|x| x.abs() < 1e-10));
+
+    // The general solver finds the same spectrum, largest first
+    let (values, vectors) = eig_generalized(&a, &b).unwrap();
+    for (value, expected) in values.iter().zip(w.iter().rev()) {
+        assert!((value - c64::new(*expected, 0.0)).norm() < 1e-10);
+    }
+    let (ac, bc) = (a.mapv(|x| c64::new(x, 0.0)), b.mapv(|x| c64::new(x, 0.0)));
+    for (value, vector) in values.iter().zip(vectors.columns()) {
+        let residual = ac.dot(&vector) - bc.dot(&vector).mapv(|x| x * value);
+        assert!(residual.iter().all(|x| x.norm() < 1e-10));
+    }
+
+    assert!(matches!(
+        eigh_generalized(&a, &-&b),
+        Err(QtnError::LinalgFailure(_))
+    ));
+    assert!(matches!(
+        eig_generalized(&a, &identity(2)),
+        Err(QtnError::InvalidArgument(_))
+    ));
+}
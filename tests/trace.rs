@@ -1,4 +1,5 @@
 use ndarray::Array;
+use qua_ten_net::error::QtnError;
 use qua_ten_net::trace::trace;
 
 #[test]
@@ -30,9 +31,10 @@ fn test_trace_fail_axes_len() {
     match trc {
         Ok(_) => {}
         Err(err) => {
+            assert_eq!(QtnError::TraceAxisCount { len: 3 }, err);
             assert_eq!(
                 "Trace calculation need two axes index. (Axes length is 3!)",
-                err
+                err.to_string()
             );
         }
     }
@@ -49,7 +51,14 @@ fn test_trace_fail_axes_dim() {
         Ok(_) => {}
         Err(err) => {
             assert_eq!(
-                "Shape mismatch along specified axes: tenosr[1] = 3, tensor[3] = 2",
+                QtnError::ShapeMismatch {
+                    tensor: 0,
+                    axis: 1,
+                    dim: 3,
+                    other_tensor: 0,
+                    other_axis: 3,
+                    other_dim: 2,
+                },
                 err
             );
         }
@@ -0,0 +1,109 @@
+use ndarray::{arr1, Array, Array2};
+use qua_ten_net::error::QtnError;
+use qua_ten_net::transfer::*;
+
+fn ising(beta: f64) -> Array2<f64> {
+    let (p, m) = (beta.exp(), (-beta).exp());
+    Array::from_shape_vec((2, 2), vec![p, m, m, p]).expect("ShapeError!")
+}
+
+#[test]
+fn test_column_transfer_matrix() {
+    let t = ising(0.5);
+    let local = t
+        .clone()
+        .into_shape_with_order(vec![2, 1, 2, 1])
+        .expect("ShapeError!");
+
+    let single = column_transfer_matrix(std::slice::from_ref(&local), false).unwrap();
+    assert_eq!(single, t);
+
+    let double = column_transfer_matrix(&[local.clone(), local], true).unwrap();
+    assert_eq!(double.shape(), &[4, 4]);
+    for l in 0..4 {
+        for r in 0..4 {
+            let expected = t[[l / 2, r / 2]] * t[[l % 2, r % 2]];
+            assert!((double[[l, r]] - expected).abs() < 1e-12);
+        }
+    }
+}
+
+#[test]
+fn test_column_transfer_matrix_fail_rank() {
+    let err = column_transfer_matrix(&[ising(0.5).into_dyn()], true).unwrap_err();
+    assert_eq!(
+        "Local tensor 0 must have four legs (left, up, right, down), found 2.",
+        err.to_string()
+    );
+}
+
+#[test]
+fn test_log_partition_function() {
+    let beta: f64 = 0.3;
+    let length = 40;
+    let z = (2.0 * beta.cosh()).powi(length) + (2.0 * beta.sinh()).powi(length);
+    let log_z = log_partition_function(&ising(beta), length as usize, true).unwrap();
+    assert!((log_z - z.ln()).abs() < 1e-10);
+
+    let power = transfer_power(&ising(beta), 3).unwrap();
+    let log_z = log_partition_function(&ising(beta), 3, false).unwrap();
+    assert!((log_z - power.sum().ln()).abs() < 1e-12);
+}
+
+#[test]
+fn test_leading_eigenvalue() {
+    let beta: f64 = 0.7;
+    let (lambda, v) = leading_eigenvalue(&ising(beta), 1e-12, 1000).unwrap();
+    assert!((lambda - 2.0 * beta.cosh()).abs() < 1e-10);
+    assert!((v[0] - v[1]).abs() < 1e-10);
+
+    let f = free_energy_density(&ising(beta), beta, 1).unwrap();
+    assert!((f + (2.0 * beta.cosh()).ln() / beta).abs() < 1e-10);
+
+    let xi = correlation_length(&ising(beta)).unwrap();
+    assert!((xi + 1.0 / beta.tanh().ln()).abs() < 1e-10);
+}
+
+#[test]
+fn test_spectrum() {
+    // Block upper triangular matrix with eigenvalues 1, -3, 2 and a complex pair 0.5 +- 2i
+    let mut t = Array2::from_diag(&arr1(&[1.0, -3.0, 2.0, 0.5, 0.5]));
+    t[[0, 1]] = 5.0;
+    t[[3, 4]] = -2.0;
+    t[[4, 3]] = 2.0;
+
+    let eigvals = spectrum(&t).unwrap();
+    let moduli: Vec<f64> = eigvals.iter().map(|x| x.norm()).collect();
+    let expected = [3.0, 17.0f64.sqrt() / 2.0, 17.0f64.sqrt() / 2.0, 2.0, 1.0];
+    assert!(moduli
+        .iter()
+        .zip(&expected)
+        .all(|(x, y)| (x - y).abs() < 1e-10));
+    assert!((eigvals[0].re + 3.0).abs() < 1e-10);
+    assert!((eigvals[1].im.abs() - 2.0).abs() < 1e-10);
+}
+
+#[test]
+fn test_finite_free_energy_density() {
+    let (beta, length): (f64, i32) = (0.4, 12);
+    let z = (2.0 * beta.cosh()).powi(length) + (2.0 * beta.sinh()).powi(length);
+    let f = finite_free_energy_density(&ising(beta), length as usize, beta, 1, true).unwrap();
+    assert!((f + z.ln() / (beta * length as f64)).abs() < 1e-12);
+
+    // The same weights counted as two sites per column halve the density
+    let f_wide = finite_free_energy_density(&ising(beta), length as usize, beta, 2, true).unwrap();
+    assert!((2.0 * f_wide - f).abs() < 1e-12);
+
+    // The finite density approaches the thermodynamic limit
+    let f_long = finite_free_energy_density(&ising(beta), 400, beta, 1, true).unwrap();
+    let f_limit = free_energy_density(&ising(beta), beta, 1).unwrap();
+    assert!((f_long - f_limit).abs() < 1e-10);
+}
+
+#[test]
+fn test_free_energy_density_fail_empty() {
+    assert!(matches!(
+        free_energy_density(&Array2::zeros((0, 0)), 1.0, 1),
+        Err(QtnError::InvalidArgument(_))
+    ));
+}